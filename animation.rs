@@ -0,0 +1,195 @@
+use crate::color::Color;
+
+// Animación continua de parámetros: una curva de cuadros clave por parámetro, evaluada en cada
+// tick de la simulación y escrita en el valor vivo. Los parámetros se nombran con una ruta
+// `grupo.campo`, por ejemplo `light.ambient`; cada struct registra sus campos con `params!`.
+
+// Referencia a un campo animable
+pub enum ParamMut<'a> {
+    Float(&'a mut f32),
+    Color(&'a mut Color),
+}
+
+impl ParamMut<'_> {
+    // Componentes que tiene un valor de este tipo en una curva
+    fn components(&self) -> usize {
+        match self {
+            ParamMut::Float(_) => 1,
+            ParamMut::Color(_) => 3,
+        }
+    }
+
+    fn parse(&self, text: &str) -> Option<Vec<f32>> {
+        match self {
+            ParamMut::Float(_) => text.parse().ok().map(|value| vec![value]),
+            ParamMut::Color(_) => {
+                let hex = u32::from_str_radix(text.trim_start_matches('#'), 16).ok()?;
+                Some([16, 8, 0].iter().map(|shift| ((hex >> shift) & 0xFF) as f32).collect())
+            }
+        }
+    }
+
+    // Escribe un valor con la misma sintaxis que los cuadros clave (`0.5`, `#ff8800`); false si no se entiende
+    pub fn assign(self, text: &str) -> bool {
+        match self.parse(text) {
+            Some(value) if value.len() == self.components() => {
+                self.set(&value);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn set(self, value: &[f32]) {
+        match self {
+            ParamMut::Float(field) => *field = value[0],
+            ParamMut::Color(field) => {
+                let channel = |c: f32| c.round().clamp(0.0, 255.0) as u8;
+                *field = Color::new(channel(value[0]), channel(value[1]), channel(value[2]));
+            }
+        }
+    }
+}
+
+// Un struct cuyos campos se pueden animar por nombre
+pub trait Params {
+    fn param(&mut self, name: &str) -> Option<ParamMut<'_>>;
+}
+
+// Lo que resuelve el primer tramo de la ruta (`light`, `atmosphere`...) a un struct de parámetros
+pub trait ParamRoot {
+    fn group(&mut self, name: &str) -> Option<&mut dyn Params>;
+}
+
+// `params!(Light { ambient, diffuse } colors { color })` implementa `Params` con esos campos
+macro_rules! params {
+    ($type:ty { $($field:ident),* $(,)? } $(colors { $($color:ident),* $(,)? })?) => {
+        impl $crate::animation::Params for $type {
+            fn param(&mut self, name: &str) -> Option<$crate::animation::ParamMut<'_>> {
+                match name {
+                    $(stringify!($field) => Some($crate::animation::ParamMut::Float(&mut self.$field)),)*
+                    $($(stringify!($color) => Some($crate::animation::ParamMut::Color(&mut self.$color)),)*)?
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+pub(crate) use params;
+
+fn resolve<'a>(root: &'a mut dyn ParamRoot, path: &str) -> Option<ParamMut<'a>> {
+    let (group, field) = path.split_once('.')?;
+    root.group(group)?.param(field)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    // Catmull-Rom: pasa por cada cuadro clave con tangentes suaves; en los extremos se repite el último
+    Cubic,
+}
+
+pub struct Curve {
+    // (tiempo en segundos de simulación, valor por componente), ordenados por tiempo
+    keys: Vec<(f32, Vec<f32>)>,
+    interpolation: Interpolation,
+    // Al llegar al último cuadro clave vuelve a empezar desde el primero
+    looping: bool,
+}
+
+impl Curve {
+    pub fn evaluate(&self, time: f32) -> Vec<f32> {
+        if self.keys.len() == 1 {
+            return self.keys[0].1.clone();
+        }
+        let (first, last) = (self.keys[0].0, self.keys[self.keys.len() - 1].0);
+        let time = if self.looping && last > first {
+            first + (time - first).rem_euclid(last - first)
+        } else {
+            time.clamp(first, last)
+        };
+
+        let next = self.keys.iter().position(|(t, _)| *t > time).unwrap_or(self.keys.len() - 1).max(1);
+        let current = next - 1;
+        let (t0, t1) = (self.keys[current].0, self.keys[next].0);
+        let s = if t1 > t0 { ((time - t0) / (t1 - t0)).clamp(0.0, 1.0) } else { 1.0 };
+
+        let value = |index: usize| &self.keys[index.min(self.keys.len() - 1)].1;
+        let (p1, p2) = (value(current), value(next));
+        match self.interpolation {
+            Interpolation::Linear => p1.iter().zip(p2).map(|(a, b)| a + (b - a) * s).collect(),
+            Interpolation::Cubic => {
+                let (p0, p3) = (value(current.saturating_sub(1)), value(next + 1));
+                (0..p1.len())
+                    .map(|i| catmull_rom(p0[i], p1[i], p2[i], p3[i], s))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+struct Animation {
+    path: String,
+    curve: Curve,
+}
+
+#[derive(Default)]
+pub struct Animations {
+    animations: Vec<Animation>,
+}
+
+impl Animations {
+    // `--animate light.ambient 0=0.05 120=0.6 240=0.05 [cubic] [loop]`, una vez por parámetro.
+    // Los colores se escriben en hex: `--animate light.color 0=ffffff 200=ffa060`.
+    // Una ruta desconocida o un valor que no corresponde al tipo del campo es un error al cargar.
+    pub fn from_args(args: &[String], root: &mut dyn ParamRoot) -> Result<Self, String> {
+        let mut animations = Vec::new();
+        for (index, _) in args.iter().enumerate().filter(|(_, arg)| *arg == "--animate") {
+            let mut tokens = args[index + 1..].iter().take_while(|arg| !arg.starts_with("--"));
+            let path = tokens.next().ok_or("--animate necesita una ruta como light.ambient")?;
+            let field = resolve(root, path).ok_or_else(|| format!("No existe el parámetro animable '{}'", path))?;
+
+            let mut curve = Curve {
+                keys: Vec::new(),
+                interpolation: Interpolation::Linear,
+                looping: false,
+            };
+            for token in tokens {
+                match token.as_str() {
+                    "linear" => curve.interpolation = Interpolation::Linear,
+                    "cubic" => curve.interpolation = Interpolation::Cubic,
+                    "loop" => curve.looping = true,
+                    key => {
+                        let parsed = key
+                            .split_once('=')
+                            .and_then(|(time, value)| Some((time.parse::<f32>().ok()?, field.parse(value)?)))
+                            .filter(|(_, value)| value.len() == field.components());
+                        let key = parsed.ok_or_else(|| format!("Cuadro clave inválido '{}' para {}", key, path))?;
+                        curve.keys.push(key);
+                    }
+                }
+            }
+            if curve.keys.is_empty() {
+                return Err(format!("--animate {} no tiene cuadros clave (tiempo=valor)", path));
+            }
+            curve.keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+            animations.push(Animation { path: path.clone(), curve });
+        }
+        Ok(Animations { animations })
+    }
+
+    // Escribe el valor de cada curva en su parámetro; las rutas ya se validaron al cargar
+    pub fn apply(&self, root: &mut dyn ParamRoot, sim_time: f32) {
+        for animation in &self.animations {
+            if let Some(field) = resolve(root, &animation.path) {
+                field.set(&animation.curve.evaluate(sim_time));
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,170 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::animation::params;
+use crate::cli::key_values;
+use crate::framebuffer::Framebuffer;
+use crate::shaders::smoothstep;
+use crate::Uniforms;
+
+// Alto de la capa de aire, relativo al radio del planeta; por encima la densidad ya es despreciable
+const SHELL_HEIGHT: f32 = 0.12;
+const SAMPLES: usize = 16;
+// Brillo del cielo en el cenit visto desde el suelo, relativo al color del aire (ver `sky_from_inside`)
+const ZENITH_BRIGHTNESS: f32 = 0.8;
+const HAZE_COLOR: Vec3 = Vec3::new(90.0, 150.0, 255.0);
+const SUNSET_COLOR: Vec3 = Vec3::new(255.0, 115.0, 50.0);
+
+// Atmósfera por longitud de cuerda: para cada píxel se corta el rayo de vista con la esfera de aire,
+// se descuenta lo que tapa el planeta y se integra una densidad que cae exponencialmente con la altitud.
+// El limbo queda más brillante justo en el borde, donde el rayo roza más aire.
+pub struct Atmosphere {
+    pub enabled: bool,
+    // Altura de escala (altitud donde la densidad cae a 1/e), relativa al radio
+    pub scale_height: f32,
+    // Extinción por unidad de longitud al nivel del suelo
+    pub density: f32,
+    // Cuánto se enrojece el aire donde el sol está cerca del horizonte
+    pub sunset_strength: f32,
+}
+
+impl Atmosphere {
+    // `--atmosphere [scale_height=0.03] [density=8] [sunset=0.8]` la activa desde el inicio
+    pub fn from_args(args: &[String]) -> Self {
+        let pairs = key_values(args, "--atmosphere");
+        let value = |key: &str, default: f32| {
+            pairs
+                .as_ref()
+                .and_then(|pairs| pairs.iter().find(|(k, _)| k == key))
+                .and_then(|(_, v)| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Atmosphere {
+            enabled: pairs.is_some(),
+            scale_height: value("scale_height", 0.03).max(1e-4),
+            density: value("density", 8.0).max(0.0),
+            sunset_strength: value("sunset", 0.8).clamp(0.0, 1.0),
+        }
+    }
+
+    // Se dibuja después de la superficie: atenúa lo que hay detrás y suma la luz que dispersa el aire
+    pub fn draw(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, planet_radius: f32) {
+        if !self.enabled {
+            return;
+        }
+
+        let Some(inverse) = (uniforms.projection_matrix * uniforms.view_matrix).try_inverse() else {
+            return;
+        };
+        let center = (uniforms.model_matrix * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+        let eye = uniforms.camera_position;
+        let shell_radius = planet_radius * (1.0 + SHELL_HEIGHT);
+        let zenith_opacity = self.zenith_opacity(&(eye - center), planet_radius);
+
+        for y in 0..framebuffer.height {
+            for x in 0..framebuffer.width {
+                let ndc_x = (x as f32 + 0.5) / framebuffer.width as f32 * 2.0 - 1.0;
+                let ndc_y = 1.0 - (y as f32 + 0.5) / framebuffer.height as f32 * 2.0;
+                let far = inverse * Vec4::new(ndc_x, ndc_y, 1.0, 1.0);
+                let direction = (far.xyz() / far.w - eye).normalize();
+
+                let Some((enter, exit)) = ray_sphere(&eye, &direction, &center, shell_radius) else {
+                    continue;
+                };
+                // El tramo útil termina donde el rayo choca con el planeta
+                let ground = ray_sphere(&eye, &direction, &center, planet_radius).map(|(hit, _)| hit).filter(|hit| *hit > 0.0);
+                let exit = ground.unwrap_or(exit);
+                let enter = enter.max(0.0);
+                if exit <= enter {
+                    continue;
+                }
+
+                let (transmittance, scattered) = self.integrate(&eye, &direction, &center, planet_radius, (enter, exit), &uniforms.light.position);
+                let (transmittance, scattered) = match zenith_opacity {
+                    Some(zenith) if ground.is_none() => sky_from_inside(transmittance, scattered, zenith),
+                    _ => (transmittance, scattered),
+                };
+                let index = y * framebuffer.width + x;
+                framebuffer.buffer[index] = composite(framebuffer.buffer[index], transmittance, &scattered);
+            }
+        }
+    }
+
+    // Opacidad del aire hacia el cenit si el ojo está dentro de la capa (el observador en la superficie);
+    // `None` desde afuera
+    fn zenith_opacity(&self, offset: &Vec3, planet_radius: f32) -> Option<f32> {
+        let scale_height = self.scale_height * planet_radius;
+        let altitude = (offset.magnitude() - planet_radius).max(0.0);
+        let top = SHELL_HEIGHT * planet_radius;
+        if altitude >= top {
+            return None;
+        }
+        let zenith_depth = self.density * scale_height * ((-altitude / scale_height).exp() - (-top / scale_height).exp());
+        Some((1.0 - (-zenith_depth).exp()).max(1e-4))
+    }
+
+    // Suma a lo largo de la cuerda: profundidad óptica para la transmitancia
+    // y luz dispersada, teñida hacia el rojo cerca del terminador
+    fn integrate(&self, eye: &Vec3, direction: &Vec3, center: &Vec3, planet_radius: f32, (enter, exit): (f32, f32), light_position: &Vec3) -> (f32, Vec3) {
+        let scale_height = self.scale_height * planet_radius;
+        let step = (exit - enter) / SAMPLES as f32;
+        let mut optical_depth = 0.0_f32;
+        let mut scattered = Vec3::zeros();
+
+        for sample in 0..SAMPLES {
+            let point = eye + direction * (enter + (sample as f32 + 0.5) * step);
+            let offset = point - center;
+            let altitude = (offset.magnitude() - planet_radius).max(0.0);
+            let local_density = self.density * (-altitude / scale_height).exp();
+            let extinction = local_density * step;
+
+            // Iluminación del aire: de día plena, se apaga poco después del terminador
+            let sun_height = offset.normalize().dot(&(light_position - point).normalize());
+            let daylight = smoothstep(-0.25, 0.2, sun_height);
+            let sunset = self.sunset_strength * (1.0 - (sun_height.abs() / 0.35).min(1.0));
+            let color = HAZE_COLOR.lerp(&SUNSET_COLOR, sunset);
+
+            scattered += color * (extinction * daylight * (-optical_depth).exp());
+            optical_depth += extinction;
+        }
+
+        ((-optical_depth).exp(), scattered)
+    }
+}
+
+params!(Atmosphere { scale_height, density, sunset_strength });
+
+// Distancias de entrada y salida del rayo en la esfera, si la corta
+fn ray_sphere(origin: &Vec3, direction: &Vec3, center: &Vec3, radius: f32) -> Option<(f32, f32)> {
+    let offset = origin - center;
+    let b = offset.dot(direction);
+    let c = offset.dot(&offset) - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let exit = -b + root;
+    (exit > 0.0).then_some((-b - root, exit))
+}
+
+// Cielo visto desde adentro de la capa. El rayo hacia arriba cruza mucho menos aire que el que roza
+// el limbo desde afuera y el cielo quedaría casi transparente, así que el brillo se mide relativo al
+// del cenit: el cenit llega a `ZENITH_BRIGHTNESS` del color del aire y hacia el horizonte, con una
+// cuerda más larga, se satura. El color sigue siendo el promedio teñido de la cuerda.
+fn sky_from_inside(transmittance: f32, scattered: Vec3, zenith_opacity: f32) -> (f32, Vec3) {
+    let opacity = 1.0 - transmittance;
+    if opacity <= 0.0 {
+        return (transmittance, scattered);
+    }
+    let relative = opacity / zenith_opacity;
+    let brightness = 1.0 - (1.0 - ZENITH_BRIGHTNESS).powf(relative);
+    (transmittance, scattered * (brightness / opacity))
+}
+
+fn composite(color: u32, transmittance: f32, scattered: &Vec3) -> u32 {
+    let channel = |shift: u32, light: f32| {
+        let value = ((color >> shift) & 0xFF) as f32 * transmittance + light;
+        (value.min(255.0) as u32) << shift
+    };
+    channel(16, scattered.x) | channel(8, scattered.y) | channel(0, scattered.z)
+}
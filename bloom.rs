@@ -0,0 +1,164 @@
+use std::any::Any;
+use crate::cli::key_values;
+use crate::dof::FOCUSED;
+use crate::frame_graph::{PassIo, PassTargets, TargetDesc, SCENE_EMISSION};
+use crate::framebuffer::Framebuffer;
+use crate::post::PostPass;
+use crate::shaders::smoothstep;
+use crate::Uniforms;
+
+pub const BLOOMED: TargetDesc = TargetDesc::color("bloomed");
+
+// Resplandor de lo que emite luz: del buffer de emisión que escriben los shaders (solo el sol)
+// se toma lo que pasa el umbral, se desenfoca con una gaussiana separable y se suma a la imagen.
+// El desenfoque va a media resolución: el resplandor es suave y así cuesta la cuarta parte.
+pub struct Bloom {
+    pub enabled: bool,
+    // Brillo de la emisión (su canal más alto, en [0, 1]) desde el que empieza a brillar; la
+    // transición es suave hasta 1. Con la luma el naranja del sol apenas pasaría el umbral.
+    pub threshold: f32,
+    // Radio del desenfoque en píxeles del framebuffer; la desviación es la mitad
+    pub radius: usize,
+    pub intensity: f32,
+    // Emisión filtrada a media resolución y el resultado de la pasada horizontal, en RGB 0..255
+    glow: Vec<[f32; 3]>,
+    horizontal: Vec<[f32; 3]>,
+}
+
+impl Bloom {
+    // `--bloom [threshold=0.5] [radius=32] [intensity=1.2]`; sin la opción queda apagado hasta presionar F2
+    pub fn from_args(args: &[String]) -> Self {
+        let pairs = key_values(args, "--bloom");
+        let value = |key: &str, default: f32| {
+            pairs.as_ref().and_then(|pairs| pairs.iter().find(|(k, _)| k == key)).and_then(|(_, v)| v.parse().ok()).unwrap_or(default)
+        };
+        Bloom {
+            enabled: pairs.is_some(),
+            threshold: value("threshold", 0.5).clamp(0.0, 0.99),
+            radius: value("radius", 32.0).clamp(2.0, 128.0) as usize,
+            intensity: value("intensity", 1.2).max(0.0),
+            glow: Vec::new(),
+            horizontal: Vec::new(),
+        }
+    }
+
+    // Pesos de la gaussiana en píxeles de media resolución
+    fn kernel(&self) -> Vec<f32> {
+        let radius = (self.radius / 2) as i32;
+        let sigma = (radius as f32 * 0.5).max(0.5);
+        (-radius..=radius).map(|offset| (-(offset * offset) as f32 / (2.0 * sigma * sigma)).exp()).collect()
+    }
+}
+
+// Una pasada de la gaussiana a lo largo de `count` muestras separadas por `stride`. En el borde solo
+// cuentan los pesos que caen dentro y se divide por su suma, así el resplandor no se oscurece contra
+// el marco de la pantalla.
+fn blur_line(source: &[[f32; 3]], target: &mut [[f32; 3]], kernel: &[f32], start: usize, stride: usize, count: usize) {
+    let radius = (kernel.len() / 2) as isize;
+    for i in 0..count as isize {
+        let (mut sum, mut weights) = ([0.0; 3], 0.0);
+        for (tap, weight) in kernel.iter().enumerate() {
+            let at = i + tap as isize - radius;
+            if at < 0 || at >= count as isize {
+                continue;
+            }
+            let sample = source[start + at as usize * stride];
+            for (total, channel) in sum.iter_mut().zip(sample) {
+                *total += channel * weight;
+            }
+            weights += weight;
+        }
+        target[start + i as usize * stride] = sum.map(|total| total / weights);
+    }
+}
+
+impl PostPass for Bloom {
+    fn name(&self) -> &'static str {
+        "bloom"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn io(&self) -> PassIo {
+        PassIo { reads: vec![FOCUSED, SCENE_EMISSION], writes: vec![BLOOMED], scratch: Vec::new() }
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, _uniforms: &Uniforms, _targets: &mut PassTargets) {
+        if !self.enabled || self.intensity <= 0.0 {
+            return;
+        }
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+
+        // Umbral con rodilla suave: por debajo no aporta y desde ahí sube hasta la emisión completa.
+        // Cada texel de media resolución promedia los píxeles de su bloque de 2x2 que caen en pantalla.
+        let threshold = self.threshold;
+        let bright = |pixel: u32| {
+            let rgb = [(pixel >> 16) & 0xFF, (pixel >> 8) & 0xFF, pixel & 0xFF].map(|channel| channel as f32);
+            let brightness = rgb.iter().fold(0.0_f32, |max, &channel| max.max(channel)) / 255.0;
+            let t = smoothstep(threshold, 1.0, brightness);
+            rgb.map(|channel| channel * t)
+        };
+        self.glow.clear();
+        let mut lit = false;
+        for y in 0..half_height {
+            for x in 0..half_width {
+                let (mut sum, mut count) = ([0.0; 3], 0.0);
+                for (px, py) in [(2 * x, 2 * y), (2 * x + 1, 2 * y), (2 * x, 2 * y + 1), (2 * x + 1, 2 * y + 1)] {
+                    if px < width && py < height {
+                        for (total, channel) in sum.iter_mut().zip(bright(framebuffer.emission[py * width + px])) {
+                            *total += channel;
+                        }
+                        count += 1.0;
+                    }
+                }
+                lit |= sum.iter().any(|&channel| channel > 0.0);
+                self.glow.push(sum.map(|total| total / count));
+            }
+        }
+        if !lit {
+            return;
+        }
+        self.horizontal.resize(self.glow.len(), [0.0; 3]);
+
+        let kernel = self.kernel();
+        for y in 0..half_height {
+            blur_line(&self.glow, &mut self.horizontal, &kernel, y * half_width, 1, half_width);
+        }
+        for x in 0..half_width {
+            blur_line(&self.horizontal, &mut self.glow, &kernel, x, half_width, half_height);
+        }
+
+        // Composición aditiva, con el resplandor de media resolución muestreado de forma bilineal
+        let glow = &self.glow;
+        let texel = |x: usize, y: usize| glow[y.min(half_height - 1) * half_width + x.min(half_width - 1)];
+        for y in 0..height {
+            let gy = ((y as f32 + 0.5) * 0.5 - 0.5).max(0.0);
+            let (y0, fy) = (gy as usize, gy.fract());
+            for x in 0..width {
+                let gx = ((x as f32 + 0.5) * 0.5 - 0.5).max(0.0);
+                let (x0, fx) = (gx as usize, gx.fract());
+                let (a, b, c, d) = (texel(x0, y0), texel(x0 + 1, y0), texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+                let pixel = &mut framebuffer.buffer[y * width + x];
+                let mut channels = [(*pixel >> 16) & 0xFF, (*pixel >> 8) & 0xFF, *pixel & 0xFF];
+                for (channel, value) in channels.iter_mut().enumerate() {
+                    let top = a[channel] + (b[channel] - a[channel]) * fx;
+                    let bottom = c[channel] + (d[channel] - c[channel]) * fx;
+                    let glow = top + (bottom - top) * fy;
+                    *value = (*value as f32 + glow * self.intensity).min(255.0) as u32;
+                }
+                *pixel = (channels[0] << 16) | (channels[1] << 8) | channels[2];
+            }
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
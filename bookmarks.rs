@@ -0,0 +1,419 @@
+use nalgebra_glm::Vec3;
+use minifb::{Key, Window};
+use std::fs;
+use std::io;
+
+use crate::camera::Camera;
+use crate::clock::REFERENCE_FPS;
+#[cfg(feature = "overlay")]
+use crate::framebuffer::Framebuffer;
+#[cfg(feature = "overlay")]
+use crate::overlay::{adaptive_backing, draw_text, LINE_HEIGHT, ADVANCE};
+use crate::shaders::{smoothstep, ShaderKind};
+
+// Archivo de estado donde se guardan los marcadores entre sesiones
+pub const STATE_FILE: &str = "bookmarks.txt";
+pub const SLOT_COUNT: usize = 9;
+pub const SLOT_KEYS: [Key; SLOT_COUNT] = [
+    Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5,
+    Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+];
+
+// Versión del archivo: la 1 (sin línea `version`) guardaba el tiempo en cuadros; desde la 2 va en
+// segundos de simulación, con la escala de tiempo (1 si falta)
+const FORMAT_VERSION: u32 = 2;
+
+const MAX_NAME_LENGTH: usize = 20;
+const PANEL_SCALE: usize = 2;
+const PANEL_MARGIN: usize = 10;
+const PANEL_PADDING: usize = 6;
+
+// Instantánea de la cámara, el cuerpo enfocado (shader), el tiempo de simulación y su escala
+#[derive(Clone)]
+pub struct Bookmark {
+    pub name: String,
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+    pub time: f32,
+    pub time_scale: f32,
+    pub shader: ShaderKind,
+}
+
+impl Bookmark {
+    pub fn capture(name: String, camera: &Camera, time: f32, time_scale: f32, shader: ShaderKind) -> Self {
+        Bookmark {
+            name,
+            eye: camera.eye,
+            center: camera.center,
+            up: camera.up,
+            time,
+            time_scale,
+            shader,
+        }
+    }
+
+    // Coloca la cámara en el marcador sin transición
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.eye = self.eye;
+        camera.center = self.center;
+        camera.up = self.up;
+        camera.has_changed = true;
+    }
+}
+
+pub struct Bookmarks {
+    path: String,
+    slots: Vec<Option<Bookmark>>,
+}
+
+impl Bookmarks {
+    // Lee el archivo de estado; si no existe se empieza sin marcadores y las líneas inválidas se ignoran
+    pub fn load(path: &str) -> Self {
+        let mut bookmarks = Bookmarks {
+            path: path.to_string(),
+            slots: vec![None; SLOT_COUNT],
+        };
+        let Ok(contents) = fs::read_to_string(path) else {
+            return bookmarks;
+        };
+        bookmarks.parse(&contents);
+        bookmarks
+    }
+
+    fn parse(&mut self, contents: &str) {
+        let mut version = 1;
+        let mut slot: Option<usize> = None;
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("version").and_then(|rest| rest.trim_start().strip_prefix('=')) {
+                version = value.trim().parse().unwrap_or(version);
+                continue;
+            }
+            if let Some(number) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                slot = number.parse::<usize>().ok().filter(|n| (1..=SLOT_COUNT).contains(n)).map(|n| n - 1);
+                if let Some(index) = slot {
+                    self.slots[index] = Some(Bookmark {
+                        name: format!("Marcador {}", index + 1),
+                        eye: Vec3::new(0.0, 0.0, 5.0),
+                        center: Vec3::new(0.0, 0.0, 0.0),
+                        up: Vec3::new(0.0, 1.0, 0.0),
+                        time: 0.0,
+                        time_scale: 1.0,
+                        shader: ShaderKind::Sun,
+                    });
+                }
+                continue;
+            }
+
+            let (Some(bookmark), Some((key, value))) = (slot.and_then(|i| self.slots[i].as_mut()), line.split_once('=')) else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "name" => bookmark.name = value.to_string(),
+                "eye" => bookmark.eye = parse_vec3(value).unwrap_or(bookmark.eye),
+                "center" => bookmark.center = parse_vec3(value).unwrap_or(bookmark.center),
+                "up" => bookmark.up = parse_vec3(value).unwrap_or(bookmark.up),
+                "time" => bookmark.time = value.parse().unwrap_or(bookmark.time),
+                "time_scale" => bookmark.time_scale = value.parse().unwrap_or(bookmark.time_scale),
+                "shader" => bookmark.shader = ShaderKind::parse(value).unwrap_or(bookmark.shader),
+                _ => {}
+            }
+        }
+
+        // Los archivos de antes contaban cuadros a 60 por segundo
+        if version < 2 {
+            for bookmark in self.slots.iter_mut().flatten() {
+                bookmark.time /= REFERENCE_FPS;
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut contents = format!("# Marcadores de cámara: Ctrl+1..9 guarda, 1..9 recupera\nversion = {}\n", FORMAT_VERSION);
+        for (index, bookmark) in self.slots.iter().enumerate() {
+            if let Some(bookmark) = bookmark {
+                contents.push_str(&format!(
+                    "\n[{}]\nname = {}\neye = {}\ncenter = {}\nup = {}\ntime = {}\ntime_scale = {}\nshader = {}\n",
+                    index + 1,
+                    bookmark.name,
+                    format_vec3(&bookmark.eye),
+                    format_vec3(&bookmark.center),
+                    format_vec3(&bookmark.up),
+                    bookmark.time,
+                    bookmark.time_scale,
+                    bookmark.shader.name(),
+                ));
+            }
+        }
+        fs::write(&self.path, contents)
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&Bookmark> {
+        self.slots.get(slot).and_then(|bookmark| bookmark.as_ref())
+    }
+
+    // Guarda en `slot`; si ya había un marcador conserva su nombre
+    pub fn store(&mut self, slot: usize, mut bookmark: Bookmark) {
+        if let Some(previous) = self.get(slot) {
+            bookmark.name = previous.name.clone();
+        }
+        self.slots[slot] = Some(bookmark);
+    }
+
+    pub fn rename(&mut self, slot: usize, name: &str) {
+        if let Some(bookmark) = self.slots[slot].as_mut() {
+            bookmark.name = name.to_string();
+        }
+    }
+
+    // Busca por nombre (sin distinguir mayúsculas) o por número de ranura
+    pub fn find(&self, name: &str) -> Option<&Bookmark> {
+        if let Ok(number) = name.parse::<usize>() {
+            return number.checked_sub(1).and_then(|slot| self.get(slot));
+        }
+        self.slots
+            .iter()
+            .flatten()
+            .find(|bookmark| bookmark.name.eq_ignore_ascii_case(name))
+    }
+}
+
+fn parse_vec3(value: &str) -> Option<Vec3> {
+    let components: Vec<f32> = value.split_whitespace().filter_map(|c| c.parse().ok()).collect();
+    match components[..] {
+        [x, y, z] => Some(Vec3::new(x, y, z)),
+        _ => None,
+    }
+}
+
+fn format_vec3(v: &Vec3) -> String {
+    format!("{} {} {}", v.x, v.y, v.z)
+}
+
+// Transición suave hacia un marcador: el centro se mueve en línea recta y la cámara
+// gira alrededor de él (dirección y distancia por separado) para no atravesar el planeta
+pub struct CameraTransition {
+    from_center: Vec3,
+    to_center: Vec3,
+    from_offset: Vec3,
+    to_offset: Vec3,
+    from_up: Vec3,
+    to_up: Vec3,
+    frame: u32,
+    frames: u32,
+}
+
+impl CameraTransition {
+    pub fn new(camera: &Camera, target: &Bookmark, frames: u32) -> Self {
+        CameraTransition {
+            from_center: camera.center,
+            to_center: target.center,
+            from_offset: camera.eye - camera.center,
+            to_offset: target.eye - target.center,
+            from_up: camera.up,
+            to_up: target.up,
+            frame: 0,
+            frames: frames.max(1),
+        }
+    }
+
+    // Avanza un cuadro y mueve la cámara; devuelve true cuando la transición terminó
+    pub fn step(&mut self, camera: &mut Camera) -> bool {
+        self.frame = (self.frame + 1).min(self.frames);
+        let t = smoothstep(0.0, 1.0, self.frame as f32 / self.frames as f32);
+
+        let center = self.from_center.lerp(&self.to_center, t);
+        let distance = self.from_offset.magnitude() + (self.to_offset.magnitude() - self.from_offset.magnitude()) * t;
+        let direction = blend_direction(&self.from_offset, &self.to_offset, t);
+
+        camera.center = center;
+        camera.eye = center + direction * distance;
+        camera.up = blend_direction(&self.from_up, &self.to_up, t);
+        camera.has_changed = true;
+
+        self.frame == self.frames
+    }
+
+    // Velocidad relativa de la transición en [0, 1]: derivada de la curva de suavizado,
+    // nula al principio y exactamente 0 al terminar
+    pub fn speed(&self) -> f32 {
+        let t = self.frame as f32 / self.frames as f32;
+        4.0 * t * (1.0 - t)
+    }
+
+    pub fn target_center(&self) -> Vec3 {
+        self.to_center
+    }
+}
+
+fn blend_direction(from: &Vec3, to: &Vec3, t: f32) -> Vec3 {
+    let blended = from.normalize().lerp(&to.normalize(), t);
+    if blended.magnitude() > 1e-4 {
+        blended.normalize()
+    } else {
+        to.normalize()
+    }
+}
+
+// Lista de ranuras ocupadas y modo de edición del nombre del marcador actual
+pub struct BookmarkPanel {
+    pub visible: bool,
+    pub current: Option<usize>,
+    editing: bool,
+    draft: String,
+}
+
+impl BookmarkPanel {
+    pub fn new() -> Self {
+        BookmarkPanel {
+            visible: false,
+            current: None,
+            editing: false,
+            draft: String::new(),
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    // Empieza a renombrar el marcador actual, si lo hay
+    pub fn start_editing(&mut self, bookmarks: &Bookmarks) {
+        if let Some(bookmark) = self.current.and_then(|slot| bookmarks.get(slot)) {
+            self.draft = bookmark.name.clone();
+            self.editing = true;
+            self.visible = true;
+        }
+    }
+
+    // Mientras se edita, las teclas escriben el nombre: Enter lo confirma y Escape lo descarta
+    pub fn handle_text_input(&mut self, window: &Window, bookmarks: &mut Bookmarks) {
+        if window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No) {
+            self.editing = false;
+            return;
+        }
+        if window.is_key_pressed(Key::Enter, minifb::KeyRepeat::No) {
+            if let (Some(slot), false) = (self.current, self.draft.trim().is_empty()) {
+                bookmarks.rename(slot, self.draft.trim());
+                if let Err(error) = bookmarks.save() {
+                    eprintln!("No se pudieron guardar los marcadores: {}", error);
+                }
+            }
+            self.editing = false;
+            return;
+        }
+        if window.is_key_pressed(Key::Backspace, minifb::KeyRepeat::Yes) {
+            self.draft.pop();
+        }
+
+        for key in window.get_keys_pressed(minifb::KeyRepeat::Yes) {
+            if let Some(c) = key_char(key) {
+                if self.draft.chars().count() < MAX_NAME_LENGTH {
+                    self.draft.push(c);
+                }
+            }
+        }
+    }
+
+    // `inset` baja el panel para que no quede debajo de las franjas de cine
+    #[cfg(feature = "overlay")]
+    pub fn draw(&self, framebuffer: &mut Framebuffer, bookmarks: &Bookmarks, inset: usize) {
+        if !self.visible {
+            return;
+        }
+
+        let mut lines: Vec<(usize, String)> = Vec::new();
+        for slot in 0..SLOT_COUNT {
+            let Some(bookmark) = bookmarks.get(slot) else {
+                continue;
+            };
+            let name = if self.editing && self.current == Some(slot) {
+                format!("{}_", self.draft)
+            } else {
+                bookmark.name.clone()
+            };
+            lines.push((slot, format!("{} {}", slot + 1, name)));
+        }
+        if lines.is_empty() {
+            lines.push((SLOT_COUNT, "Sin marcadores (Ctrl+1..9)".to_string()));
+        }
+
+        let line_height = LINE_HEIGHT * PANEL_SCALE;
+        let width = (MAX_NAME_LENGTH + 3) * ADVANCE * PANEL_SCALE + 2 * PANEL_PADDING;
+        let height = lines.len() * line_height + 2 * PANEL_PADDING;
+        let top = PANEL_MARGIN + inset;
+        let style = adaptive_backing(framebuffer, PANEL_MARGIN, top, width, height);
+
+        for (row, (slot, text)) in lines.iter().enumerate() {
+            let color = if self.current == Some(*slot) { style.accent } else { style.text };
+            let y = top + PANEL_PADDING + row * line_height;
+            draw_text(framebuffer, PANEL_MARGIN + PANEL_PADDING, y, text, color, PANEL_SCALE);
+        }
+    }
+}
+
+// Caracteres que se pueden escribir en un nombre
+fn key_char(key: Key) -> Option<char> {
+    let letters = [
+        Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+        Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    ];
+    let digits = [
+        Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+        Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    ];
+
+    if let Some(index) = letters.iter().position(|k| *k == key) {
+        return Some((b'a' + index as u8) as char);
+    }
+    if let Some(index) = digits.iter().position(|k| *k == key) {
+        return Some((b'0' + index as u8) as char);
+    }
+    match key {
+        Key::Space => Some(' '),
+        Key::Minus => Some('-'),
+        Key::Period => Some('.'),
+        _ => None,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(contents: &str) -> Bookmarks {
+        let mut bookmarks = Bookmarks { path: String::new(), slots: vec![None; SLOT_COUNT] };
+        bookmarks.parse(contents);
+        bookmarks
+    }
+
+    #[test]
+    fn legacy_frame_times_become_seconds() {
+        let bookmarks = parsed("[1]\nname = viejo\ntime = 600\n[2]\ntime = 90\n");
+        assert_eq!(bookmarks.get(0).map(|bookmark| bookmark.time), Some(10.0));
+        assert_eq!(bookmarks.get(1).map(|bookmark| bookmark.time), Some(1.5));
+    }
+
+    #[test]
+    fn versioned_times_are_seconds() {
+        let bookmarks = parsed("version = 2\n[3]\ntime = 12.5\ntime_scale = 4\n[4]\ntime = 3\n");
+        assert_eq!(bookmarks.get(2).map(|bookmark| (bookmark.time, bookmark.time_scale)), Some((12.5, 4.0)));
+        // Sin `time_scale` el marcador corre a velocidad normal
+        assert_eq!(bookmarks.get(3).map(|bookmark| bookmark.time_scale), Some(1.0));
+    }
+
+    #[test]
+    fn saved_time_scale_reads_back() {
+        let path = std::env::temp_dir().join(format!("bookmarks-{}.txt", std::process::id()));
+        let mut bookmarks = Bookmarks { path: path.to_string_lossy().into_owned(), slots: vec![None; SLOT_COUNT] };
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::y());
+        bookmarks.store(0, Bookmark::capture("rápido".to_string(), &camera, 7.25, 0.5, ShaderKind::Sun));
+        bookmarks.save().unwrap();
+        let loaded = Bookmarks::load(&bookmarks.path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.get(0).map(|bookmark| (bookmark.time, bookmark.time_scale)), Some((7.25, 0.5)));
+    }
+}
@@ -1,10 +1,16 @@
-use nalgebra_glm::{Vec3, rotate_vec3};
+use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+// Distancia mínima al centro: el zoom se detiene antes de atravesarlo
+const MIN_DISTANCE: f32 = 0.2;
+// Plano cercano de la proyección; el observador en la superficie lo acerca a su altura
+pub const DEFAULT_NEAR: f32 = 0.1;
+
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
     pub up: Vec3,
+    pub near: f32,
     pub has_changed: bool,
 }
 
@@ -14,6 +20,7 @@ impl Camera {
             eye,
             center,
             up,
+            near: DEFAULT_NEAR,
             has_changed: true,
         }
     }
@@ -39,19 +46,31 @@ impl Camera {
         self.has_changed = true;
     }
 
-    // Cambia el centro de la cámara moviéndolo en la dirección especificada
-    pub fn move_center(&mut self, direction: Vec3) {
-        let movement = direction.normalize() * 0.1; // Adjust the factor for movement speed
+    // Desplaza el centro y el ojo juntos en el plano de la pantalla: `dx` hacia la derecha de la
+    // vista y `dy` hacia arriba, en unidades de mundo
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward);
+        let movement = right * dx + up * dy;
+        if !movement.iter().all(|c| c.is_finite()) {
+            return;
+        }
         self.center += movement;
         self.eye += movement;
 
         self.has_changed = true;
     }
 
-    // Acerca o aleja la cámara hacia el punto central
+    // Acerca o aleja la cámara hacia el punto central, sin pasar de `MIN_DISTANCE`
     pub fn zoom(&mut self, delta: f32) {
-        let direction = (self.center - self.eye).normalize();
-        self.eye += direction * delta;
+        let offset = self.eye - self.center;
+        let distance = offset.magnitude();
+        if distance <= 0.0 {
+            return;
+        }
+        let new_distance = (distance - delta).max(MIN_DISTANCE);
+        self.eye = self.center + offset * (new_distance / distance);
         self.has_changed = true;
     }
 
@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::cli::{arg_value, key_values};
+use crate::framebuffer::Framebuffer;
+use crate::image_io::save_png_rgb;
+
+// Grabación sin ventana: `count` cuadros en PNG, avanzando el tiempo `step` cuadros de 1/60 s
+// entre uno y otro. Con step=1 y una vuelta completa del planeta sale un giro de 360 grados.
+pub struct RecordOptions {
+    pub dir: String,
+    pub count: u32,
+    pub step: u32,
+}
+
+impl RecordOptions {
+    // `--record [dir=frames] [count=120] [step=1]`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--record")?;
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+        Some(RecordOptions {
+            dir: value("dir").unwrap_or_else(|| "frames".to_string()),
+            count: value("count").and_then(|count| count.parse().ok()).unwrap_or(120),
+            step: value("step").and_then(|step| step.parse().ok()).filter(|&step| step > 0).unwrap_or(1),
+        })
+    }
+
+    pub fn frame_path(&self, index: u32) -> String {
+        Path::new(&self.dir).join(format!("frame_{:04}.png", index)).to_string_lossy().into_owned()
+    }
+}
+
+// Carpeta de las capturas de la ventana: `--screenshot-dir capturas`, por defecto la actual
+pub fn screenshot_dir(args: &[String]) -> String {
+    arg_value(args, "--screenshot-dir").unwrap_or_else(|| ".".to_string())
+}
+
+// `captura_20240131_235959_123.png` con la hora UTC; los milisegundos evitan pisar dos capturas
+// del mismo segundo
+pub fn screenshot_path(dir: &str) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = now.as_secs();
+    let (year, month, day) = civil_date((seconds / 86_400) as i64);
+    let time = seconds % 86_400;
+    let name = format!(
+        "captura_{:04}{:02}{:02}_{:02}{:02}{:02}_{:03}.png",
+        year, month, day, time / 3600, time / 60 % 60, time % 60, now.subsec_millis()
+    );
+    Path::new(dir).join(name).to_string_lossy().into_owned()
+}
+
+// El framebuffer ya va en filas de arriba hacia abajo y 0xRRGGBB, igual que el PNG: no hay que
+// invertirlo. Crea la carpeta si falta.
+pub fn save_frame(path: &str, framebuffer: &Framebuffer) -> Result<(), String> {
+    if let Some(parent) = Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+    }
+    save_png_rgb(path, framebuffer.width, framebuffer.height, &framebuffer.buffer).map_err(|error| error.to_string())
+}
+
+// Año, mes y día del calendario gregoriano para un número de días desde 1970-01-01
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,18 @@
+#!/bin/sh
+# Compila el proyecto con cada combinación de las features opcionales (post, io, overlay), desde
+# el núcleo solo hasta todas juntas. `gamepad` no activa código y no se combina.
+# Uso: ./check_features.sh [argumentos extra para cargo check, p. ej. --release]
+
+failed=""
+for features in "" post io overlay post,io post,overlay io,overlay post,io,overlay; do
+    echo "== --no-default-features --features \"$features\""
+    if ! cargo check --all-targets --no-default-features --features "$features" "$@"; then
+        failed="$failed [$features]"
+    fi
+done
+
+if [ -n "$failed" ]; then
+    echo "Fallaron:$failed"
+    exit 1
+fi
+echo "Las 8 combinaciones compilan"
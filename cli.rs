@@ -0,0 +1,34 @@
+// Utilidades mínimas para leer opciones de la línea de comandos
+
+// Devuelve el valor que sigue a `flag`, por ejemplo `--columns 1920`
+pub fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+}
+
+
+// Lee los pares `clave=valor` que siguen a `flag`, por ejemplo `--export-heightmap size=1024 out=h.png`
+pub fn key_values(args: &[String], flag: &str) -> Option<Vec<(String, String)>> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    Some(pairs_after(args, index))
+}
+
+// Igual que `key_values` pero para cada vez que aparece `flag`, por ejemplo varios `--decal`
+pub fn all_key_values(args: &[String], flag: &str) -> Vec<Vec<(String, String)>> {
+    args.iter()
+        .enumerate()
+        .filter(|(_, arg)| *arg == flag)
+        .map(|(index, _)| pairs_after(args, index))
+        .collect()
+}
+
+fn pairs_after(args: &[String], index: usize) -> Vec<(String, String)> {
+    args[index + 1..]
+        .iter()
+        .take_while(|arg| !arg.starts_with("--"))
+        .filter_map(|arg| arg.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
@@ -0,0 +1,110 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::vertex::Vertex;
+
+// Planos cercano y lejano en espacio de recorte: un punto está dentro si `plano · (x, y, z, w) >= 0`.
+// Los laterales no hacen falta: con w por encima del plano cercano la división da coordenadas finitas
+// y el rasterizador ya recorta el triángulo a la pantalla.
+const PLANES: [Vec4; 2] = [
+    Vec4::new(0.0, 0.0, 1.0, 1.0),  // Cercano: z >= -w
+    Vec4::new(0.0, 0.0, -1.0, 1.0), // Lejano: z <= w
+];
+
+pub enum Clip {
+    // Entero dentro: se proyecta tal cual
+    Inside,
+    // Entero fuera de alguno de los planos
+    Outside,
+    // Cortado por un plano: el polígono que queda, en abanico
+    Clipped(Vec<[Vertex; 3]>),
+}
+
+// Recorta un triángulo con la salida de `vertex_shader` contra los planos cercano y lejano
+// (Sutherland-Hodgman). Los vértices nuevos interpolan todos sus atributos en espacio de recorte.
+pub fn clip_triangle(triangle: &[Vertex; 3]) -> Clip {
+    let distances = triangle.each_ref().map(|vertex| PLANES.map(|plane| plane.dot(&vertex.clip_position)));
+    if (0..PLANES.len()).any(|plane| distances.iter().all(|distance| distance[plane] < 0.0)) {
+        return Clip::Outside;
+    }
+    if distances.iter().flatten().all(|distance| *distance >= 0.0) {
+        return Clip::Inside;
+    }
+
+    let mut polygon = triangle.to_vec();
+    for plane in PLANES {
+        let mut kept = Vec::with_capacity(polygon.len() + 1);
+        for (index, current) in polygon.iter().enumerate() {
+            let next = &polygon[(index + 1) % polygon.len()];
+            let (from, to) = (plane.dot(&current.clip_position), plane.dot(&next.clip_position));
+            if from >= 0.0 {
+                kept.push(current.clone());
+            }
+            if (from >= 0.0) != (to >= 0.0) {
+                kept.push(current.lerp(next, from / (from - to)));
+            }
+        }
+        polygon = kept;
+        if polygon.len() < 3 {
+            return Clip::Outside;
+        }
+    }
+
+    let pieces = (1..polygon.len() - 1).map(|i| [polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()]).collect();
+    Clip::Clipped(pieces)
+}
+
+// División por w y transformación de viewport, después del recorte
+pub fn project(vertex: &mut Vertex, viewport: &Mat4) {
+    let clip = vertex.clip_position;
+    let w = clip.w;
+    let ndc = Vec4::new(clip.x / w, clip.y / w, clip.z / w, 1.0);
+    let screen = viewport * ndc;
+    vertex.transformed_position = Vec3::new(screen.x, screen.y, screen.z);
+    vertex.inverse_w = 1.0 / w;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec2;
+
+    fn vertex(clip: Vec4, uv: Vec2) -> Vertex {
+        Vertex { clip_position: clip, tex_coords: uv, ..Vertex::default() }
+    }
+
+    #[test]
+    fn vertex_behind_near_plane_splits_into_two_triangles() {
+        // El primero queda detrás del plano cercano (z < -w); los otros dos, dentro
+        let triangle = [
+            vertex(Vec4::new(0.0, 0.0, -2.0, 1.0), Vec2::new(0.0, 0.0)),
+            vertex(Vec4::new(1.0, 0.0, 0.0, 1.0), Vec2::new(1.0, 0.0)),
+            vertex(Vec4::new(0.0, 1.0, 0.0, 1.0), Vec2::new(0.0, 1.0)),
+        ];
+        let Clip::Clipped(pieces) = clip_triangle(&triangle) else {
+            panic!("el triángulo debía recortarse");
+        };
+        assert_eq!(pieces.len(), 2);
+
+        // Los cortes caen a mitad de las aristas que cruzan el plano, sobre z = -w
+        let (a, b) = (Vec4::new(0.5, 0.0, -1.0, 1.0), Vec4::new(0.0, 0.5, -1.0, 1.0));
+        let positions: Vec<[Vec4; 3]> = pieces.iter().map(|piece| piece.each_ref().map(|v| v.clip_position)).collect();
+        assert_eq!(positions[0], [a, triangle[1].clip_position, triangle[2].clip_position]);
+        assert_eq!(positions[1], [a, triangle[2].clip_position, b]);
+        assert_eq!(pieces[0][0].tex_coords, Vec2::new(0.5, 0.0));
+        assert_eq!(pieces[1][2].tex_coords, Vec2::new(0.0, 0.5));
+    }
+
+    #[test]
+    fn inside_and_outside_are_not_split() {
+        let inside = [
+            vertex(Vec4::new(0.0, 0.0, 0.0, 1.0), Vec2::zeros()),
+            vertex(Vec4::new(1.0, 0.0, 0.5, 1.0), Vec2::zeros()),
+            vertex(Vec4::new(0.0, 1.0, -0.5, 1.0), Vec2::zeros()),
+        ];
+        assert!(matches!(clip_triangle(&inside), Clip::Inside));
+        let behind = inside.clone().map(|mut v| {
+            v.clip_position.z = -2.0;
+            v
+        });
+        assert!(matches!(clip_triangle(&behind), Clip::Outside));
+    }
+}
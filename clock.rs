@@ -0,0 +1,162 @@
+use std::time::Instant;
+
+// Las velocidades de los shaders y las órbitas están en unidades por segundo, medidas para que a
+// este ritmo se vean como cuando el tiempo contaba cuadros
+pub const REFERENCE_FPS: f32 = 60.0;
+pub const FRAME_SECONDS: f32 = 1.0 / REFERENCE_FPS;
+
+// Cada tanto se corre el origen del tiempo que ven los shaders, así el f32 nunca pasa de esto y
+// resuelve un cuadro con margen (en f32 a los 4200 s el paso es de 0,5 ms). Es múltiplo de los
+// períodos por defecto de órbitas, cometa, día del observador y año de las estaciones, que no
+// saltan al correrlo; los giros de los cuerpos y el desplazamiento del ruido sí, una vez cada 70
+// minutos de simulación.
+const REBASE_SECONDS: f64 = 4200.0;
+// Un cuadro que tarda más que esto (la ventana arrastrada, un volcado) avanza solo esto
+const MAX_DELTA: f32 = 0.1;
+const MIN_SCALE: f32 = 0.25;
+const MAX_SCALE: f32 = 8.0;
+
+// Reloj de la simulación en segundos. En la ventana sigue al reloj real; sin ventana avanza
+// `FRAME_SECONDS` por cuadro para que la salida no dependa de lo que tarda cada uno. Se acumula en
+// f64; los shaders reciben en f32 solo lo que pasó desde `epoch`.
+pub struct Clock {
+    seconds: f64,
+    // Múltiplo de `REBASE_SECONDS` desde el que se cuenta `seconds()`
+    epoch: f64,
+    delta: f32,
+    pub scale: f32,
+    pub paused: bool,
+    step_requested: bool,
+    last_tick: Option<Instant>,
+    realtime: bool,
+}
+
+impl Clock {
+    pub fn real(start: f32) -> Self {
+        Clock { realtime: true, ..Clock::fixed(start) }
+    }
+
+    pub fn fixed(start: f32) -> Self {
+        let mut clock = Clock {
+            seconds: start as f64,
+            epoch: 0.0,
+            delta: 0.0,
+            scale: 1.0,
+            paused: false,
+            step_requested: false,
+            last_tick: None,
+            realtime: false,
+        };
+        clock.rebase();
+        clock
+    }
+
+    // Avanza un cuadro con `rate` veces la escala (el observador acelera el día) y devuelve el
+    // tiempo nuevo. En pausa no avanza, salvo un cuadro de referencia si se pidió un paso.
+    pub fn tick(&mut self, rate: f32) -> f32 {
+        let now = Instant::now();
+        let elapsed = match (self.realtime, self.last_tick) {
+            (true, Some(last)) => now.duration_since(last).as_secs_f32().min(MAX_DELTA),
+            _ => FRAME_SECONDS,
+        };
+        self.last_tick = Some(now);
+
+        self.delta = if !self.paused {
+            elapsed * self.scale * rate
+        } else if std::mem::take(&mut self.step_requested) {
+            FRAME_SECONDS * self.scale * rate
+        } else {
+            0.0
+        };
+        self.seconds += self.delta as f64;
+        self.rebase();
+        self.seconds()
+    }
+
+    // Tiempo de la simulación visto desde el origen actual, el que va a los shaders y las órbitas
+    pub fn seconds(&self) -> f32 {
+        (self.seconds - self.epoch) as f32
+    }
+
+    fn rebase(&mut self) {
+        self.epoch = (self.seconds / REBASE_SECONDS).floor() * REBASE_SECONDS;
+    }
+
+    // Lo que avanzó el último `tick`: 0 en pausa
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    // Salta a otro instante, como al recuperar un marcador
+    pub fn set(&mut self, seconds: f32) {
+        self.seconds = seconds as f64;
+        self.rebase();
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    // Con el reloj en pausa, el próximo `tick` avanza un cuadro
+    pub fn step(&mut self) {
+        self.step_requested = self.paused;
+    }
+
+    // Escala recuperada de un marcador, dentro del mismo rango que las teclas
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = if scale.is_finite() { scale.clamp(MIN_SCALE, MAX_SCALE) } else { 1.0 };
+    }
+
+    pub fn faster(&mut self) {
+        self.scale = (self.scale * 2.0).min(MAX_SCALE);
+    }
+
+    pub fn slower(&mut self) {
+        self.scale = (self.scale / 2.0).max(MIN_SCALE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_clock_rebases_by_whole_periods() {
+        let mut clock = Clock::fixed(4199.9);
+        let mut previous = clock.seconds();
+        for _ in 0..60 {
+            let now = clock.tick(1.0);
+            let step = (now - previous).rem_euclid(REBASE_SECONDS as f32);
+            assert!((step - FRAME_SECONDS).abs() < 1e-3, "{} -> {}", previous, now);
+            previous = now;
+        }
+        assert!(clock.seconds() < 1.0, "no se corrió el origen: {}", clock.seconds());
+    }
+
+    #[test]
+    fn day_long_clock_still_resolves_frames() {
+        // El paso tiene el mismo error que en la primera vuelta: el redondeo de f32 antes de
+        // `REBASE_SECONDS`, a lo sumo 0,5 ms
+        for start in [1e5, 1e6, 4095.0 + 1e6] {
+            let mut clock = Clock::fixed(start);
+            let mut previous = clock.seconds();
+            for _ in 0..600 {
+                let now = clock.tick(1.0);
+                assert!((now - previous - FRAME_SECONDS).abs() < 5e-4, "{} -> {} empezando en {}", previous, now, start);
+                previous = now;
+            }
+        }
+    }
+
+    #[test]
+    fn paused_clock_only_moves_on_step() {
+        let mut clock = Clock::fixed(10.0);
+        clock.toggle_pause();
+        assert_eq!(clock.tick(1.0), 10.0);
+        clock.step();
+        assert!((clock.tick(1.0) - (10.0 + FRAME_SECONDS)).abs() < 1e-6);
+        assert_eq!(clock.delta(), FRAME_SECONDS);
+        assert_eq!(clock.tick(1.0), clock.seconds());
+        assert_eq!(clock.delta(), 0.0);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::animation::params;
+use crate::cli::key_values;
+
+// Nubes de la Tierra como una esfera aparte, un poco más grande que el planeta y transparente.
+// Gira con su propia velocidad, independiente de los continentes, y se dibuja después de lo opaco.
+pub struct CloudShell {
+    // Altura de la capa sobre la superficie, relativa al radio
+    pub height: f32,
+    // Radianes por segundo de tiempo local que gira la capa alrededor del eje polar
+    pub speed: f32,
+    // Si escribe profundidad; por defecto no, así no tapa lo transparente que venga detrás
+    pub write_depth: bool,
+}
+
+impl CloudShell {
+    // `--cloud-shell [height=0.02] [speed=0.18] [depth=on]`; sin la opción las nubes van en el shader
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--cloud-shell")?;
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let number = |key: &str, default: f32| value(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+
+        Some(CloudShell {
+            height: number("height", 0.02).max(0.0),
+            speed: number("speed", 0.18),
+            write_depth: value("depth") == Some("on"),
+        })
+    }
+}
+
+params!(CloudShell { height, speed });
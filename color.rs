@@ -1,73 +1,129 @@
 use std::fmt;
 
+// Gamma de la codificación de pantalla: los colores se escriben en 0..255 como siempre, pero la
+// aritmética de los shaders (luz, mezclas, interpolaciones) se hace en luz lineal
+const GAMMA: f32 = 2.2;
+// Luz lineal que se ve como blanco después del mapeo de tonos de `tone_map`
+const WHITE_POINT: f32 = 2.0;
+const TO_LINEAR: [f32; 256] = linear_table();
+
+// Canales en luz lineal, en coma flotante y sin tope superior: 1 es el blanco de pantalla antes
+// del mapeo de tonos y lo más brillante (el sol, los reflejos) puede pasarse
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
+    r: f32,
+    g: f32,
+    b: f32,
+    // Opacidad en [0, 1]; solo la usa la mezcla alfa del pipeline, `to_hex` la ignora
+    a: f32,
 }
 
 impl Color {
+    // Los literales 0..255 son colores de pantalla (gamma 2.2); se pasan a lineal al construirlos
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
-        Color { r, g, b }
+        Color { r: TO_LINEAR[r as usize], g: TO_LINEAR[g as usize], b: TO_LINEAR[b as usize], a: 1.0 }
+    }
+
+    // El mismo color con opacidad `alpha` en [0, 1]
+    pub fn with_alpha(self, alpha: f32) -> Self {
+        let alpha = if alpha.is_finite() { alpha.clamp(0.0, 1.0) } else { 0.0 };
+        Color { a: alpha, ..self }
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.a
     }
 
     pub const fn from_hex(hex: u32) -> Self {
-        let r = ((hex >> 16) & 0xFF) as u8;
-        let g = ((hex >> 8) & 0xFF) as u8;
-        let b = (hex & 0xFF) as u8;
-        Color { r, g, b }
+        Color::new(((hex >> 16) & 0xFF) as u8, ((hex >> 8) & 0xFF) as u8, (hex & 0xFF) as u8)
     }
 
     pub const fn black() -> Self {
-        Color { r: 0, g: 0, b: 0 }
+        Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 }
+    }
+
+    // Codifica con gamma 2.2 a 0xRRGGBB; lo que pase de 1 se recorta (ver `tone_map`)
+    pub fn to_hex(self) -> u32 {
+        self.to_hex_dithered(0.0)
+    }
+
+    // Lo mismo con `offset` niveles de 0-255, en [-0.5, 0.5), sumados ya con la gamma y antes de
+    // redondear: es el tramado de la salida (ver `OutputDither`)
+    pub fn to_hex_dithered(self, offset: f32) -> u32 {
+        let [r, g, b] = self.levels().map(|level| (level + offset).round().clamp(0.0, 255.0) as u32);
+        (r << 16) | (g << 8) | b
+    }
+
+    // Canales con la gamma ya aplicada, en niveles de 0-255 sin redondear
+    pub fn levels(self) -> [f32; 3] {
+        [self.r, self.g, self.b].map(|c| c.clamp(0.0, 1.0).powf(1.0 / GAMMA) * 255.0)
     }
 
-    pub fn to_hex(&self) -> u32 {
-        ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+    // Mapeo de tonos al escribir en el framebuffer: exposición y Reinhard extendido por canal.
+    // Las sombras y los tonos medios quedan casi lineales y las luces se comprimen hacia
+    // `WHITE_POINT` en vez de recortarse. La opacidad no cambia.
+    pub fn tone_map(&self, exposure: f32) -> Self {
+        let white = WHITE_POINT * WHITE_POINT;
+        let channel = |c: f32| {
+            let c = (c * exposure).max(0.0);
+            c * (1.0 + c / white) / (1.0 + c)
+        };
+        Color { r: channel(self.r), g: channel(self.g), b: channel(self.b), a: self.a }
     }
 
-    // Linear interpolation between two colors
+    // Linear interpolation between two colors, alpha included
     pub fn lerp(&self, other: &Color, t: f32) -> Self {
-        let t = t.clamp(0.0, 1.0);
+        let t = if t.is_finite() { t.clamp(0.0, 1.0) } else { 0.0 };
+        let channel = |from: f32, to: f32| from + (to - from) * t;
         Color {
-            r: (self.r as f32 + (other.r as f32 - self.r as f32) * t).round() as u8,
-            g: (self.g as f32 + (other.g as f32 - self.g as f32) * t).round() as u8,
-            b: (self.b as f32 + (other.b as f32 - self.b as f32) * t).round() as u8,
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: channel(self.a, other.a),
         }
     }
 
     pub fn is_black(&self) -> bool {
-        self.r == 0 && self.g == 0 && self.b == 0 
+        self.r == 0.0 && self.g == 0.0 && self.b == 0.0
     }
 
     pub fn blend_normal(&self, blend: &Color) -> Color {
         if blend.is_black() { *self } else { *blend }
       }
-    
+
+    // Las mezclas conservan la opacidad del color de base
     pub fn blend_multiply(&self, blend: &Color) -> Color {
-        Color::new(
-            ((self.r as f32 * blend.r as f32) / 255.0) as u8,
-            ((self.g as f32 * blend.g as f32) / 255.0) as u8,
-            ((self.b as f32 * blend.b as f32) / 255.0) as u8
-        )
+        Color { r: self.r * blend.r, g: self.g * blend.g, b: self.b * blend.b, a: self.a }
     }
-    
+
     pub fn blend_add(&self, blend: &Color) -> Color {
-        Color::new(
-            (self.r as u16 + blend.r as u16).min(255) as u8,
-            (self.g as u16 + blend.g as u16).min(255) as u8,
-            (self.b as u16 + blend.b as u16).min(255) as u8
-        )
+        Color { r: self.r + blend.r, g: self.g + blend.g, b: self.b + blend.b, a: self.a }
     }
-    
+
     pub fn blend_subtract(&self, blend: &Color) -> Color {
-        let r = (self.r as i16 - blend.r as i16).max(0).min(255) as u8;
-        let g = (self.g as i16 - blend.g as i16).max(0).min(255) as u8;
-        let b = (self.b as i16 - blend.b as i16).max(0).min(255) as u8;
+        let channel = |base: f32, blend: f32| (base - blend).max(0.0);
+        Color { r: channel(self.r, blend.r), g: channel(self.g, blend.g), b: channel(self.b, blend.b), a: self.a }
+    }
+}
 
-        Color::new(r, g, b)
+// Tabla de 0..255 con gamma a luz lineal. `powf` no se puede evaluar en una constante, así que
+// x^2.2 se arma como x² · x^0.2 y la raíz quinta sale por Newton.
+const fn linear_table() -> [f32; 256] {
+    let mut table = [0.0; 256];
+    let mut index = 1;
+    while index < 256 {
+        let x = index as f32 / 255.0;
+        let mut root = 1.0;
+        let mut step = 0;
+        while step < 32 {
+            let fourth = root * root * root * root;
+            root -= (fourth * root - x) / (5.0 * fourth);
+            step += 1;
+        }
+        table[index] = x * x * root;
+        index += 1;
     }
+    table
 }
 
 use std::ops::Add;
@@ -76,11 +132,7 @@ impl Add for Color {
     type Output = Color;
 
     fn add(self, other: Color) -> Color {
-        Color {
-            r: self.r.saturating_add(other.r),
-            g: self.g.saturating_add(other.g),
-            b: self.b.saturating_add(other.b),
-        }
+        self.blend_add(&other)
     }
 }
 
@@ -90,16 +142,20 @@ impl Mul<f32> for Color {
     type Output = Color;
 
     fn mul(self, scalar: f32) -> Color {
+        // Una intensidad NaN o infinita cuenta como 0 en vez de ensuciar el cuadro.
+        // La intensidad escala la luz, no la opacidad.
+        let scalar = if scalar.is_finite() { scalar.max(0.0) } else { 0.0 };
         Color {
-            r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
-            g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
-            b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+            r: self.r * scalar,
+            g: self.g * scalar,
+            b: self.b * scalar,
+            a: self.a,
         }
     }
 }
 
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
+        write!(f, "Color(r: {:.3}, g: {:.3}, b: {:.3})", self.r, self.g, self.b)
     }
-}
\ No newline at end of file
+}
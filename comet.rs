@@ -0,0 +1,199 @@
+use nalgebra_glm::{rotate_vec3, Vec3};
+use std::f32::consts::PI;
+use crate::camera::Camera;
+use crate::cli::key_values;
+#[cfg(feature = "overlay")]
+use crate::framebuffer::Framebuffer;
+#[cfg(feature = "overlay")]
+use crate::overlay::{adaptive_backing, draw_text, ADVANCE, LINE_HEIGHT};
+
+// Muestras de la órbita que se revisan hacia adelante buscando el próximo sobrevuelo, sobre medio período
+const LOOKAHEAD_STEPS: usize = 120;
+// Un cuerpo cuenta como sobrevuelo si el cometa pasa a menos de esta distancia
+const FLYBY_RANGE: f32 = 3.0;
+
+const HUD_SCALE: usize = 2;
+const HUD_MARGIN: usize = 10;
+const HUD_PADDING: usize = 6;
+
+// Cometa en una órbita de Kepler excéntrica con el origen (el cuerpo central de la escena) en un foco
+pub struct Comet {
+    pub semi_major: f32,   // Semieje mayor
+    pub eccentricity: f32, // 0 es un círculo; cerca de 1, una elipse muy alargada
+    pub period: f32,       // Segundos por vuelta
+    pub tilt: f32,         // Inclinación del plano de la órbita sobre XZ, en radianes
+    pub perihelion: f32,   // Ángulo del perihelio dentro del plano, en radianes
+    pub size: f32,         // Escala del núcleo respecto de la esfera del modelo
+}
+
+impl Comet {
+    // `--comet a=5 e=0.6 period=40 tilt=0.25 arg=0 size=0.08`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--comet")?;
+        let value = |key: &str, default: f32| {
+            pairs
+                .iter()
+                .find(|(k, _)| k == key)
+                .and_then(|(_, v)| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Some(Comet {
+            semi_major: value("a", 5.0).max(0.1),
+            eccentricity: value("e", 0.6).clamp(0.0, 0.99),
+            period: value("period", 40.0).max(0.1),
+            tilt: value("tilt", 0.25),
+            perihelion: value("arg", 0.0),
+            size: value("size", 0.08).max(0.001),
+        })
+    }
+
+    // Posición y velocidad (unidades por segundo) en un instante. La velocidad es la derivada
+    // analítica de la posición respecto del tiempo, así que no depende del paso entre cuadros.
+    pub fn state(&self, time: f32) -> (Vec3, Vec3) {
+        let e = self.eccentricity;
+        let mean_motion = 2.0 * PI / self.period;
+        let mean_anomaly = (time * mean_motion).rem_euclid(2.0 * PI);
+
+        // Ecuación de Kepler M = E - e sin E, por Newton desde E = M (o π si la órbita es muy excéntrica)
+        let mut anomaly = if e > 0.8 { PI } else { mean_anomaly };
+        for _ in 0..8 {
+            anomaly -= (anomaly - e * anomaly.sin() - mean_anomaly) / (1.0 - e * anomaly.cos());
+        }
+
+        let minor = (1.0 - e * e).sqrt();
+        let (sin, cos) = anomaly.sin_cos();
+        let anomaly_rate = mean_motion / (1.0 - e * cos);
+        let position = Vec3::new(cos - e, 0.0, minor * sin) * self.semi_major;
+        let velocity = Vec3::new(-sin, 0.0, minor * cos) * (self.semi_major * anomaly_rate);
+        (self.orient(&position), self.orient(&velocity))
+    }
+
+    fn orient(&self, vector: &Vec3) -> Vec3 {
+        let in_plane = rotate_vec3(vector, self.perihelion, &Vec3::y());
+        rotate_vec3(&in_plane, self.tilt, &Vec3::x())
+    }
+
+    // Próximo cuerpo del que el cometa pasa cerca: recorre medio período hacia adelante y se queda
+    // con el primer acercamiento mínimo dentro de `FLYBY_RANGE`; si no hay ninguno, con el más cercano.
+    // `bodies` da la posición de cada cuerpo en un instante. Devuelve su índice.
+    pub fn next_flyby(&self, time: f32, bodies: &[&dyn Fn(f32) -> Vec3]) -> Option<usize> {
+        let step = self.period * 0.5 / LOOKAHEAD_STEPS as f32;
+        let distance = |body: usize, step_index: usize| {
+            let at = time + step * step_index as f32;
+            (self.state(at).0 - bodies[body](at)).magnitude()
+        };
+
+        let mut closest: Option<(usize, f32)> = None;
+        for step_index in 0..=LOOKAHEAD_STEPS {
+            for body in 0..bodies.len() {
+                let current = distance(body, step_index);
+                let approaching_minimum = step_index < LOOKAHEAD_STEPS
+                    && current <= distance(body, step_index + 1)
+                    && (step_index == 0 || current <= distance(body, step_index - 1));
+                if approaching_minimum && current < FLYBY_RANGE {
+                    return Some(body);
+                }
+                if closest.is_none_or(|(_, best)| current < best) {
+                    closest = Some((body, current));
+                }
+            }
+        }
+        closest.map(|(body, _)| body)
+    }
+}
+
+// Cámara de persecución montada en el cometa: detrás y por encima de su velocidad, mirando hacia el
+// próximo sobrevuelo. El ojo y el centro siguen su pose con un resorte críticamente amortiguado.
+pub struct CometRide {
+    pub active: bool,
+    pub behind: f32,    // Distancia detrás del cometa, a lo largo de la velocidad
+    pub above: f32,     // Altura sobre el cometa, perpendicular a la velocidad
+    pub look_bias: f32, // Cuánto se corre la mirada del cometa hacia el cuerpo que se acerca
+    pub stiffness: f32, // Frecuencia del resorte por cuadro; más alta sigue más de cerca
+    eye: Vec3,
+    eye_velocity: Vec3,
+    center: Vec3,
+    center_velocity: Vec3,
+}
+
+// Lo que muestra el HUD del paseo
+pub struct RideStatus {
+    pub speed: f32,
+    pub target: String,
+    pub distance: f32,
+}
+
+impl CometRide {
+    pub fn new() -> Self {
+        CometRide {
+            active: false,
+            behind: 0.6,
+            above: 0.2,
+            look_bias: 0.3,
+            stiffness: 0.25,
+            eye: Vec3::zeros(),
+            eye_velocity: Vec3::zeros(),
+            center: Vec3::zeros(),
+            center_velocity: Vec3::zeros(),
+        }
+    }
+
+    // Ojo y centro de la persecución para el cometa en `position` con velocidad `velocity`
+    pub fn chase_pose(&self, position: &Vec3, velocity: &Vec3, target: &Vec3) -> (Vec3, Vec3) {
+        let forward = velocity.try_normalize(1e-6).unwrap_or_else(Vec3::z);
+        let up = (Vec3::y() - forward * forward.dot(&Vec3::y())).try_normalize(1e-6).unwrap_or_else(Vec3::x);
+        let eye = position - forward * self.behind + up * self.above;
+        (eye, position.lerp(target, self.look_bias))
+    }
+
+    // Empieza el paseo con el resorte en reposo en `pose`, donde termina la transición de entrada
+    pub fn enter(&mut self, pose: (Vec3, Vec3)) {
+        self.active = true;
+        (self.eye, self.center) = pose;
+        self.eye_velocity = Vec3::zeros();
+        self.center_velocity = Vec3::zeros();
+    }
+
+    // Avanza un cuadro el resorte hacia `pose` y coloca la cámara
+    pub fn follow(&mut self, camera: &mut Camera, pose: (Vec3, Vec3)) {
+        spring(&mut self.eye, &mut self.eye_velocity, &pose.0, self.stiffness);
+        spring(&mut self.center, &mut self.center_velocity, &pose.1, self.stiffness);
+        camera.eye = self.eye;
+        camera.center = self.center;
+        camera.up = Vec3::y();
+        camera.has_changed = true;
+    }
+
+    #[cfg(feature = "overlay")]
+    pub fn draw_hud(&self, framebuffer: &mut Framebuffer, status: &RideStatus, inset: usize) {
+        if !self.active {
+            return;
+        }
+        let lines = [
+            format!("Cometa: {:.4} u/s", status.speed),
+            format!("Sobrevuelo: {} a {:.2} u", status.target, status.distance),
+        ];
+
+        let line_height = LINE_HEIGHT * HUD_SCALE;
+        let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let width = columns * ADVANCE * HUD_SCALE + 2 * HUD_PADDING;
+        let height = lines.len() * line_height + 2 * HUD_PADDING;
+        let left = framebuffer.width.saturating_sub(width + HUD_MARGIN);
+        let top = framebuffer.height.saturating_sub(height + HUD_MARGIN + inset);
+        let style = adaptive_backing(framebuffer, left, top, width, height);
+        for (row, line) in lines.iter().enumerate() {
+            draw_text(framebuffer, left + HUD_PADDING, top + HUD_PADDING + row * line_height, line, style.text, HUD_SCALE);
+        }
+    }
+}
+
+// Resorte críticamente amortiguado resuelto en forma cerrada para un paso de un cuadro: llega a
+// `target` sin pasarse ni oscilar, y un objetivo que se mueve de a saltos no lo hace temblar
+fn spring(value: &mut Vec3, velocity: &mut Vec3, target: &Vec3, omega: f32) {
+    let offset = *value - target;
+    let decay = (-omega).exp();
+    let temp = *velocity + offset * omega;
+    *velocity = (*velocity - temp * omega) * decay;
+    *value = target + (offset + temp) * decay;
+}
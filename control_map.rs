@@ -0,0 +1,147 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::cli::{arg_value, key_values};
+use crate::color::Color;
+use crate::heightmap::equirect_positions;
+use crate::image_io::{load_png_rgba, save_png_rgb};
+use crate::noise::NoiseSource;
+use crate::spherical::to_lat_long;
+use crate::plates::PlateField;
+use crate::shader_params::EarthParams;
+use crate::terrain::earth_albedo;
+
+// Mapa de control: PNG equirectangular pequeño (por ejemplo 64x32) pintado a mano.
+// El RGB se mezcla con el albedo procedural según `weight`; el alfa decide dónde sigue habiendo
+// detalle procedural (255) y dónde queda solo el color del mapa (0).
+pub struct ControlMap {
+    width: usize,
+    height: usize,
+    // RGB y alfa en [0, 1], filas de arriba (polo norte) hacia abajo
+    texels: Vec<[f32; 4]>,
+    // Promedio de la primera y la última fila: todas las longitudes se juntan en el polo
+    north_pole: [f32; 4],
+    south_pole: [f32; 4],
+    pub weight: f32,
+}
+
+impl ControlMap {
+    // `--control-map mapa.png` con `--control-weight 0.5` opcional
+    pub fn from_args(args: &[String]) -> Option<Result<Self, String>> {
+        let path = arg_value(args, "--control-map")?;
+        let weight = arg_value(args, "--control-weight")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0.5_f32)
+            .clamp(0.0, 1.0);
+        Some(ControlMap::load(&path, weight))
+    }
+
+    pub fn load(path: &str, weight: f32) -> Result<Self, String> {
+        let (width, height, pixels) = load_png_rgba(path).map_err(|error| format!("No se pudo cargar el mapa de control {}: {}", path, error))?;
+        if width == 0 || height == 0 {
+            return Err(format!("El mapa de control {} está vacío", path));
+        }
+
+        let texels: Vec<[f32; 4]> = pixels.iter().map(|p| p.map(|c| c as f32 / 255.0)).collect();
+        let row_average = |row: usize| {
+            let mut sum = [0.0; 4];
+            for texel in &texels[row * width..(row + 1) * width] {
+                for (total, value) in sum.iter_mut().zip(texel) {
+                    *total += value;
+                }
+            }
+            sum.map(|total| total / width as f32)
+        };
+        let (north_pole, south_pole) = (row_average(0), row_average(height - 1));
+
+        Ok(ControlMap {
+            width,
+            height,
+            texels,
+            north_pole,
+            south_pole,
+            weight,
+        })
+    }
+
+    // Muestreo bilineal. La longitud da la vuelta en la costura; entre el centro de la primera
+    // (o última) fila y el polo se funde con el promedio de esa fila para que no se vea un pellizco.
+    fn sample(&self, position: &Vec3) -> [f32; 4] {
+        let (latitude, longitude) = to_lat_long(position);
+        let u = (longitude + PI) / (2.0 * PI) * self.width as f32 - 0.5;
+        let v = (PI / 2.0 - latitude) / PI * self.height as f32 - 0.5;
+
+        let x0 = u.floor();
+        let fx = u - x0;
+        let left = (x0 as i32).rem_euclid(self.width as i32) as usize;
+        let right = (left + 1) % self.width;
+
+        let last_row = (self.height - 1) as f32;
+        let y = v.clamp(0.0, last_row);
+        let top = y.floor() as usize;
+        let bottom = (top + 1).min(self.height - 1);
+        let fy = y - top as f32;
+
+        let texel = |x: usize, y: usize| self.texels[y * self.width + x];
+        let upper = mix(texel(left, top), texel(right, top), fx);
+        let lower = mix(texel(left, bottom), texel(right, bottom), fx);
+        let color = mix(upper, lower, fy);
+
+        if v < 0.0 {
+            mix(color, self.north_pole, (-v / 0.5).min(1.0))
+        } else if v > last_row {
+            mix(color, self.south_pole, ((v - last_row) / 0.5).min(1.0))
+        } else {
+            color
+        }
+    }
+
+    pub fn blend(&self, position: &Vec3, procedural: Color) -> Color {
+        let [r, g, b, alpha] = self.sample(position);
+        let painted = Color::new((r * 255.0).round() as u8, (g * 255.0).round() as u8, (b * 255.0).round() as u8);
+        let mixed = procedural.lerp(&painted, self.weight);
+        painted.lerp(&mixed, alpha)
+    }
+}
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+pub struct ControlMapBakeOptions {
+    pub entity: String,
+    pub size: usize,
+    pub output: String,
+    pub time: f32,
+}
+
+impl ControlMapBakeOptions {
+    // `--bake-control-map entity=earth size=64 out=control.png [time=0]`; el alto es size / 2
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--bake-control-map")?;
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+
+        Some(ControlMapBakeOptions {
+            entity: value("entity").unwrap_or_else(|| "earth".to_string()),
+            size: value("size").and_then(|v| v.parse().ok()).filter(|&s| s >= 2).unwrap_or(64),
+            output: value("out").unwrap_or_else(|| "control.png".to_string()),
+            time: value("time").and_then(|v| v.parse().ok()).unwrap_or(0.0),
+        })
+    }
+}
+
+// Hornea el albedo procedural actual en un PNG que después se puede editar y cargar con `--control-map`.
+// Se guarda sin alfa, que al cargarlo cuenta como opaco (detalle procedural en todas partes).
+pub fn bake_control_map(options: &ControlMapBakeOptions, noise: &dyn NoiseSource, radius: f32, plates: Option<&PlateField>) -> Result<(), String> {
+    let albedo: fn(&dyn NoiseSource, &Vec3, f32, Option<&PlateField>) -> Color = match options.entity.as_str() {
+        "earth" => |noise, position, time, plates| earth_albedo(&EarthParams::default(), noise, position, time, plates, None, 0.0),
+        other => return Err(format!("La entidad '{}' no tiene albedo procedural", other)),
+    };
+
+    let width = options.size;
+    let height = (options.size / 2).max(1);
+    let pixels: Vec<u32> = equirect_positions(width, height, radius)
+        .map(|position| albedo(noise, &position, options.time, plates).to_hex())
+        .collect();
+
+    save_png_rgb(&options.output, width, height, &pixels).map_err(|e| e.to_string())
+}
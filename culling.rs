@@ -0,0 +1,59 @@
+use nalgebra_glm::{Mat4, Vec3};
+use crate::vertex::Vertex;
+
+// Esfera que contiene toda una malla, en espacio del modelo
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    // Centrada en la caja de la malla: no es la mínima, pero para la esfera y los anillos es exacta
+    pub fn of(vertices: &[Vertex]) -> Self {
+        let Some(first) = vertices.first() else {
+            return BoundingSphere { center: Vec3::zeros(), radius: 0.0 };
+        };
+        let (min, max) = vertices.iter().fold((first.position, first.position), |(min, max), vertex| {
+            (min.inf(&vertex.position), max.sup(&vertex.position))
+        });
+        let center = (min + max) * 0.5;
+        let radius = vertices.iter().map(|vertex| (vertex.position - center).magnitude()).fold(0.0, f32::max);
+        BoundingSphere { center, radius }
+    }
+
+    // Si la esfera, agrandada en `margin`, toca el volumen de vista. Los seis planos salen de las filas
+    // de `clip` (proyección · vista · modelo) y quedan en espacio del modelo, así que la escala de la
+    // matriz de modelo ya está incluida al normalizarlos.
+    pub fn in_frustum(&self, clip: &Mat4, margin: f32) -> bool {
+        let w = clip.row(3).transpose();
+        for axis in 0..3 {
+            let row = clip.row(axis).transpose();
+            for plane in [w + row, w - row] {
+                let length = plane.xyz().magnitude();
+                if length < f32::EPSILON {
+                    continue;
+                }
+                if (plane.xyz().dot(&self.center) + plane.w) / length < -(self.radius + margin) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+// Lo que el render necesita de una malla para descartarla, o descartar sus caras, antes de sombrear
+#[derive(Clone, Copy)]
+pub struct Mesh<'a> {
+    pub vertices: &'a [Vertex],
+    pub bounds: BoundingSphere,
+    // Sin descarte de caras traseras: mallas planas como los anillos se ven de los dos lados
+    pub double_sided: bool,
+}
+
+// Un triángulo ya en pantalla da la espalda si su área con signo (la de `edge_function` en el
+// rasterizador) es negativa: las caras de frente giran antihorario y el viewport invierte Y.
+pub fn faces_away(a: &Vec3, b: &Vec3, c: &Vec3) -> bool {
+    (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x) < 0.0
+}
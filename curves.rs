@@ -0,0 +1,270 @@
+use std::any::Any;
+use crate::cli::key_values;
+use crate::frame_graph::{PassIo, PassTargets, TargetDesc};
+use crate::framebuffer::Framebuffer;
+use crate::post::PostPass;
+use crate::radial_blur::HYPERSPACE;
+use crate::Uniforms;
+
+const MAX_POINTS: usize = 8;
+
+pub const GRADED: TargetDesc = TargetDesc::color("graded");
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurveChannel {
+    Master,
+    Red,
+    Green,
+    Blue,
+}
+
+// Curvas de color por canal (maestra, R, G, B) definidas por hasta 8 puntos de control
+// en [0, 255] e interpoladas con Catmull-Rom. Se precalculan en tablas de 256 entradas
+// que se reconstruyen al cambiar los puntos; la curva maestra se aplica antes que la del canal.
+pub struct ColorCurves {
+    pub enabled: bool,
+    master: Vec<(f32, f32)>,
+    red: Vec<(f32, f32)>,
+    green: Vec<(f32, f32)>,
+    blue: Vec<(f32, f32)>,
+    tables: [[u8; 256]; 3],
+    identity: bool,
+}
+
+impl ColorCurves {
+    pub fn new() -> Self {
+        let mut curves = ColorCurves {
+            enabled: false,
+            master: identity_points(),
+            red: identity_points(),
+            green: identity_points(),
+            blue: identity_points(),
+            tables: [[0; 256]; 3],
+            identity: true,
+        };
+        curves.rebuild();
+        curves
+    }
+
+    // `--curves master=0:0,64:80,255:240 red=...`; sin la opción se usa una curva suave
+    // que levanta sombras y comprime las luces, desactivada hasta pulsar la tecla
+    pub fn from_args(args: &[String]) -> Self {
+        let mut curves = ColorCurves::new();
+
+        match key_values(args, "--curves") {
+            Some(pairs) => {
+                for (key, value) in pairs {
+                    let channel = match key.as_str() {
+                        "master" => CurveChannel::Master,
+                        "red" | "r" => CurveChannel::Red,
+                        "green" | "g" => CurveChannel::Green,
+                        "blue" | "b" => CurveChannel::Blue,
+                        other => {
+                            eprintln!("Canal de curva desconocido: {}", other);
+                            continue;
+                        }
+                    };
+                    let points: Vec<(f32, f32)> = value
+                        .split(',')
+                        .filter_map(|point| {
+                            let (x, y) = point.split_once(':')?;
+                            Some((x.parse().ok()?, y.parse().ok()?))
+                        })
+                        .collect();
+                    curves.set_curve(channel, points);
+                }
+                curves.enabled = true;
+            }
+            None => {
+                curves.set_curve(CurveChannel::Master, vec![(0.0, 0.0), (64.0, 76.0), (192.0, 200.0), (255.0, 242.0)]);
+            }
+        }
+
+        curves
+    }
+
+    pub fn set_curve(&mut self, channel: CurveChannel, points: Vec<(f32, f32)>) {
+        let points = sanitize(points);
+        match channel {
+            CurveChannel::Master => self.master = points,
+            CurveChannel::Red => self.red = points,
+            CurveChannel::Green => self.green = points,
+            CurveChannel::Blue => self.blue = points,
+        }
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        let master = build_table(&self.master);
+        for (table, points) in self.tables.iter_mut().zip([&self.red, &self.green, &self.blue]) {
+            let channel = build_table(points);
+            for (value, entry) in table.iter_mut().enumerate() {
+                *entry = channel[master[value] as usize];
+            }
+        }
+
+        self.identity = self
+            .tables
+            .iter()
+            .all(|table| table.iter().enumerate().all(|(value, &entry)| entry as usize == value));
+    }
+
+    fn remap(&self, framebuffer: &mut Framebuffer) {
+        // Curvas identidad: no se toca el framebuffer
+        if self.identity {
+            return;
+        }
+
+        let [red, green, blue] = &self.tables;
+        for pixel in framebuffer.buffer.iter_mut() {
+            let r = red[((*pixel >> 16) & 0xFF) as usize] as u32;
+            let g = green[((*pixel >> 8) & 0xFF) as usize] as u32;
+            let b = blue[(*pixel & 0xFF) as usize] as u32;
+            *pixel = (r << 16) | (g << 8) | b;
+        }
+    }
+}
+
+fn identity_points() -> Vec<(f32, f32)> {
+    vec![(0.0, 0.0), (255.0, 255.0)]
+}
+
+// Ordena por x, descarta puntos no finitos o con x repetida, limita a 8 puntos y
+// garantiza al menos dos; con menos se vuelve a la identidad
+fn sanitize(points: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    let mut points: Vec<(f32, f32)> = points
+        .into_iter()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .map(|(x, y)| (x.clamp(0.0, 255.0), y.clamp(0.0, 255.0)))
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    points.dedup_by(|a, b| (a.0 - b.0).abs() < 0.5);
+    points.truncate(MAX_POINTS);
+
+    if points.len() < 2 {
+        return identity_points();
+    }
+    points
+}
+
+// Evalúa la curva en los 256 valores de entrada. Fuera del primer y último punto la curva
+// es constante. Los puntos fantasma de los extremos se extrapolan linealmente, así una
+// curva recta (como la identidad) se reproduce exactamente.
+fn build_table(points: &[(f32, f32)]) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let last = points.len() - 1;
+
+    for (value, entry) in table.iter_mut().enumerate() {
+        let x = value as f32;
+        let y = if x <= points[0].0 {
+            points[0].1
+        } else if x >= points[last].0 {
+            points[last].1
+        } else {
+            let i = points.iter().rposition(|p| p.0 <= x).unwrap_or(0).min(last - 1);
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[i + 1];
+            let y0 = if i == 0 { 2.0 * y1 - y2 } else { points[i - 1].1 };
+            let y3 = if i + 2 > last { 2.0 * y2 - y1 } else { points[i + 2].1 };
+            let t = (x - x1) / (x2 - x1);
+            catmull_rom(y0, y1, y2, y3, t)
+        };
+
+        *entry = if y.is_finite() { y.round().clamp(0.0, 255.0) as u8 } else { value as u8 };
+    }
+
+    table
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (-p0 + p2) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t3)
+}
+
+impl PostPass for ColorCurves {
+    fn name(&self) -> &'static str {
+        "curves"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn io(&self) -> PassIo {
+        PassIo { reads: vec![HYPERSPACE], writes: vec![GRADED], scratch: Vec::new() }
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, _uniforms: &Uniforms, _targets: &mut PassTargets) {
+        if self.enabled {
+            self.remap(framebuffer);
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Un píxel por cada combinación de valores que recorre los tres canales
+    fn ramp() -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(256, 4);
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            let value = (index % 256) as u32;
+            let row = (index / 256) as u32;
+            *pixel = (value << 16) | (((value + 85 * row) % 256) << 8) | (255 - value);
+        }
+        framebuffer
+    }
+
+    fn is_monotonic(table: &[u8; 256]) -> bool {
+        table.windows(2).all(|pair| pair[0] <= pair[1])
+    }
+
+    #[test]
+    fn identity_curves_leave_the_frame_untouched() {
+        let curves = ColorCurves::new();
+        let original = ramp();
+        let mut framebuffer = ramp();
+        curves.remap(&mut framebuffer);
+        assert!(curves.identity);
+        assert_eq!(framebuffer.buffer, original.buffer);
+    }
+
+    #[test]
+    fn inverted_master_flips_every_channel() {
+        let mut curves = ColorCurves::new();
+        curves.set_curve(CurveChannel::Master, vec![(0.0, 255.0), (255.0, 0.0)]);
+        for table in &curves.tables {
+            for (value, &entry) in table.iter().enumerate() {
+                assert_eq!(entry as usize, 255 - value, "invertida en {}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn extreme_curves_stay_in_range() {
+        let mut curves = ColorCurves::new();
+        curves.set_curve(CurveChannel::Red, vec![(0.0, 0.0), (10.0, 255.0), (20.0, 0.0), (255.0, 255.0)]);
+        curves.set_curve(CurveChannel::Green, vec![(0.0, 0.0), (f32::NAN, 30.0), (64.0, 80.0), (64.2, 200.0), (f32::INFINITY, 0.0), (128.0, f32::NEG_INFINITY), (255.0, 240.0)]);
+        curves.set_curve(CurveChannel::Blue, vec![(-40.0, -10.0), (30.0, 200.0), (40.0, 250.0), (900.0, 400.0)]);
+
+        let mut framebuffer = ramp();
+        curves.remap(&mut framebuffer);
+        assert!(framebuffer.buffer.iter().all(|&pixel| pixel <= 0xFFFFFF));
+        // Verde y azul tienen puntos crecientes
+        assert!(is_monotonic(&curves.tables[1]), "verde: {:?}", curves.tables[1]);
+        assert!(is_monotonic(&curves.tables[2]), "azul: {:?}", curves.tables[2]);
+    }
+}
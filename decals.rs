@@ -0,0 +1,229 @@
+use nalgebra_glm::Vec3;
+use crate::cli::all_key_values;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::overlay::{draw_text, ADVANCE, GLYPH_HEIGHT};
+use crate::spherical::lat_long_to_dir;
+
+// Escala del mapa de bits de la fuente al rasterizar el texto de una calcomanía
+const TEXT_SCALE: usize = 4;
+// Distancia máxima (en texels) que guarda el campo; también es el margen alrededor de la forma
+const SPREAD: usize = 6;
+const POLYGON_RESOLUTION: usize = 64;
+// Ancho del borde suavizado y del bisel del relieve, en texels
+const EDGE_WIDTH: f32 = 1.5;
+const BEVEL_WIDTH: f32 = 2.5;
+
+// Campo de distancia con signo: negativo dentro de la forma, en texels
+pub struct DistanceField {
+    width: usize,
+    height: usize,
+    distances: Vec<f32>,
+}
+
+impl DistanceField {
+    // El texto se dibuja con la fuente de mapa de bits; la interpolación del campo redondea los píxeles
+    pub fn from_text(text: &str) -> Self {
+        let columns = text.chars().count().max(1);
+        let width = columns * ADVANCE * TEXT_SCALE - TEXT_SCALE + 2 * SPREAD;
+        let height = GLYPH_HEIGHT * TEXT_SCALE + 2 * SPREAD;
+
+        let mut mask = Framebuffer::new(width, height);
+        draw_text(&mut mask, SPREAD, SPREAD, text, 0xFFFFFF, TEXT_SCALE);
+        let inside: Vec<bool> = mask.buffer.iter().map(|pixel| *pixel != 0).collect();
+
+        Self::from_mask(&inside, width, height)
+    }
+
+    // Polígono simple con vértices en [-1, 1] (x a la derecha, y hacia arriba), regla par-impar
+    pub fn from_polygon(points: &[(f32, f32)]) -> Self {
+        let size = POLYGON_RESOLUTION + 2 * SPREAD;
+        let mut inside = vec![false; size * size];
+
+        for y in 0..size {
+            for x in 0..size {
+                let px = ((x as f32 + 0.5 - SPREAD as f32) / POLYGON_RESOLUTION as f32) * 2.0 - 1.0;
+                let py = 1.0 - ((y as f32 + 0.5 - SPREAD as f32) / POLYGON_RESOLUTION as f32) * 2.0;
+                let mut crossings = false;
+                for (i, a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    if (a.1 > py) != (b.1 > py) && px < a.0 + (py - a.1) / (b.1 - a.1) * (b.0 - a.0) {
+                        crossings = !crossings;
+                    }
+                }
+                inside[y * size + x] = crossings;
+            }
+        }
+
+        Self::from_mask(&inside, size, size)
+    }
+
+    // Transformada de distancia por fuerza bruta dentro de una ventana de SPREAD texels;
+    // solo se hace una vez al cargar la calcomanía
+    fn from_mask(inside: &[bool], width: usize, height: usize) -> Self {
+        let spread = SPREAD as i32;
+        let mut distances = vec![SPREAD as f32; width * height];
+
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let state = inside[(y as usize) * width + x as usize];
+                let mut nearest = (SPREAD * SPREAD) as i32;
+                for dy in -spread..=spread {
+                    for dx in -spread..=spread {
+                        let (sx, sy) = (x + dx, y + dy);
+                        // Fuera del campo se considera exterior
+                        let other = sx >= 0 && sy >= 0 && sx < width as i32 && sy < height as i32
+                            && inside[(sy as usize) * width + sx as usize];
+                        if other != state {
+                            nearest = nearest.min(dx * dx + dy * dy);
+                        }
+                    }
+                }
+                let distance = ((nearest as f32).sqrt() - 0.5).min(SPREAD as f32);
+                distances[(y as usize) * width + x as usize] = if state { -distance } else { distance };
+            }
+        }
+
+        DistanceField { width, height, distances }
+    }
+
+    fn texel(&self, x: i32, y: i32) -> f32 {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return SPREAD as f32;
+        }
+        self.distances[y as usize * self.width + x as usize]
+    }
+
+    // Muestreo bilineal en coordenadas de texel
+    pub fn sample(&self, x: f32, y: f32) -> f32 {
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor() as i32, y.floor() as i32);
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+// Texto o forma proyectada sobre la superficie alrededor de un punto de anclaje (lat/long).
+// Se muestrea en espacio de objeto, así que gira junto con el modelo.
+pub struct Decal {
+    field: DistanceField,
+    anchor: Vec3,
+    right: Vec3,
+    up: Vec3,
+    half_width: f32,
+    rotation: (f32, f32),
+    color: Color,
+    emboss: f32,
+}
+
+impl Decal {
+    // Ángulos en grados; `angular_size` es el ancho que cubre la calcomanía sobre la esfera
+    pub fn new(field: DistanceField, latitude: f32, longitude: f32, angular_size: f32, rotation: f32, color: Color, emboss: f32) -> Self {
+        let anchor = lat_long_to_dir(latitude.to_radians(), longitude.to_radians());
+        // Base tangente vista desde fuera: "right" hacia la derecha y "up" hacia el norte
+        let reference = if anchor.y.abs() > 0.999 { Vec3::new(0.0, 0.0, -anchor.y.signum()) } else { Vec3::new(0.0, 1.0, 0.0) };
+        let right = reference.cross(&anchor).normalize();
+        let up = anchor.cross(&right);
+
+        Decal {
+            field,
+            anchor,
+            right,
+            up,
+            half_width: (angular_size.to_radians() * 0.5).clamp(0.01, 1.4).tan(),
+            rotation: rotation.to_radians().sin_cos(),
+            color,
+            emboss,
+        }
+    }
+
+    // Una calcomanía por cada `--decal text=HOLA lat=10 long=90 size=40 rotation=0 color=ffffff emboss=0.5`;
+    // `polygon=x:y,x:y,...` usa un polígono en lugar de texto
+    pub fn from_args(args: &[String]) -> Vec<Decal> {
+        all_key_values(args, "--decal")
+            .into_iter()
+            .filter_map(|pairs| {
+                let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+                let number = |key: &str, default: f32| value(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+
+                let field = match (value("text"), value("polygon")) {
+                    (_, Some(points)) => {
+                        let points: Vec<(f32, f32)> = points
+                            .split(',')
+                            .filter_map(|point| {
+                                let (x, y) = point.split_once(':')?;
+                                Some((x.parse().ok()?, y.parse().ok()?))
+                            })
+                            .collect();
+                        if points.len() < 3 {
+                            eprintln!("La calcomanía necesita al menos 3 puntos en polygon=");
+                            return None;
+                        }
+                        DistanceField::from_polygon(&points)
+                    }
+                    (Some(text), None) => DistanceField::from_text(text),
+                    (None, None) => {
+                        eprintln!("--decal necesita text= o polygon=");
+                        return None;
+                    }
+                };
+                let color = value("color")
+                    .and_then(|hex| u32::from_str_radix(hex.trim_start_matches('#'), 16).ok())
+                    .map(Color::from_hex)
+                    .unwrap_or(Color::new(240, 240, 240));
+
+                Some(Decal::new(
+                    field,
+                    number("lat", 0.0),
+                    number("long", 90.0),
+                    number("size", 40.0),
+                    number("rotation", 0.0),
+                    color,
+                    number("emboss", 0.0),
+                ))
+            })
+            .collect()
+    }
+
+    // Mezcla la calcomanía sobre `base` en la posición de espacio de objeto dada
+    pub fn shade(&self, object_position: &Vec3, base: Color) -> Color {
+        let direction = object_position.normalize();
+        let facing = direction.dot(&self.anchor);
+        if facing <= 0.0 {
+            return base;
+        }
+
+        // Proyección gnomónica sobre el plano tangente del ancla y luego la rotación de la calcomanía
+        let u = direction.dot(&self.right) / facing;
+        let v = direction.dot(&self.up) / facing;
+        let (sin, cos) = self.rotation;
+        let (u, v) = (u * cos + v * sin, v * cos - u * sin);
+
+        // La altura se deduce del ancho conservando la proporción del campo
+        let texels_per_unit = self.field.width as f32 / (2.0 * self.half_width);
+        let tx = self.field.width as f32 * 0.5 + u * texels_per_unit;
+        let ty = self.field.height as f32 * 0.5 - v * texels_per_unit;
+        let distance = self.field.sample(tx, ty);
+        if distance >= EDGE_WIDTH {
+            return base;
+        }
+
+        let coverage = (0.5 - distance / (2.0 * EDGE_WIDTH)).clamp(0.0, 1.0);
+        let mut color = self.color;
+
+        // Relieve falso: el gradiente del campo inclina la "normal" del bisel hacia una luz arriba a la izquierda
+        if self.emboss > 0.0 && distance > -BEVEL_WIDTH {
+            let gx = self.field.sample(tx + 1.0, ty) - self.field.sample(tx - 1.0, ty);
+            let gy = self.field.sample(tx, ty - 1.0) - self.field.sample(tx, ty + 1.0);
+            let length = (gx * gx + gy * gy).sqrt().max(1e-6);
+            let lit = (-gx + gy) / (length * std::f32::consts::SQRT_2);
+            let bevel = 1.0 - (-distance / BEVEL_WIDTH).clamp(0.0, 1.0);
+            color = color * (1.0 + self.emboss * lit * bevel).max(0.0);
+        }
+
+        base.lerp(&color, coverage)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,110 @@
+use nalgebra_glm::Vec3;
+use crate::cli::key_values;
+use crate::noise::{sphere_noise, NoiseSource};
+use crate::plates::PlateField;
+use crate::shaders::ShaderKind;
+use crate::terrain::{earth_elevation, EARTH_DRIFT, EARTH_ZOOM, SEA_LEVEL};
+
+// De dónde sale la altura del relieve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relief {
+    // Ruido propio, con `frequency` y `speed`
+    Noise,
+    // La elevación de la Tierra sobre el nivel del mar, con sus placas si las hay; el océano queda liso
+    Terrain,
+}
+
+// Desplazamiento de vértices a lo largo de la normal según el ruido de los uniformes
+#[derive(Debug, Clone, Copy)]
+pub struct DisplacementParams {
+    pub amplitude: f32, // Altura máxima del relieve, en unidades del modelo
+    pub frequency: f32, // Zoom del ruido; el de FastNoise necesita cientos para verse en la esfera
+    pub speed: f32,     // Avance del ruido por segundo; 0 deja el relieve quieto
+    pub relief: Relief,
+}
+
+impl DisplacementParams {
+    // Relieve propio de cada shader: la superficie del sol ondula, la luna tiene bultos fijos y la
+    // Tierra levanta sus continentes y cordilleras
+    fn preset(shader: ShaderKind) -> Option<Self> {
+        match shader {
+            ShaderKind::Sun => Some(DisplacementParams { amplitude: 0.025, frequency: 220.0, speed: 90.0, relief: Relief::Noise }),
+            ShaderKind::Moon => Some(DisplacementParams { amplitude: 0.018, frequency: 500.0, speed: 0.0, relief: Relief::Noise }),
+            ShaderKind::EarthClouds => Some(DisplacementParams { amplitude: 0.03, frequency: EARTH_ZOOM, speed: 0.0, relief: Relief::Terrain }),
+            _ => None,
+        }
+    }
+
+    fn height(&self, noise: &dyn NoiseSource, plates: Option<&PlateField>, position: &Vec3, time: f32) -> f32 {
+        match self.relief {
+            Relief::Noise => self.amplitude * sphere_noise(noise, position, self.frequency, Vec3::repeat(time * self.speed)),
+            Relief::Terrain => {
+                // El mismo reloj del terreno que `earth_clouds`
+                let elevation = earth_elevation(noise, position, time * EARTH_DRIFT, plates);
+                self.amplitude * ((elevation - SEA_LEVEL) / (1.0 - SEA_LEVEL)).clamp(0.0, 1.0)
+            }
+        }
+    }
+
+    // Posición desplazada y normal recalculada con diferencias centrales sobre dos tangentes.
+    // En la esfera el desplazamiento es radial, así que la dirección de cada punto no cambia y
+    // las distancias angulares de los shaders (ondas, calcomanías) siguen coincidiendo.
+    pub fn displace(&self, noise: &dyn NoiseSource, plates: Option<&PlateField>, position: &Vec3, normal: &Vec3, time: f32) -> (Vec3, Vec3) {
+        let original = *normal;
+        let normal = normal.normalize();
+        if !normal.iter().all(|c| c.is_finite()) {
+            return (*position, original);
+        }
+        let displaced = |point: Vec3| point + normal * self.height(noise, plates, &point, time);
+
+        let reference = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+        let tangent = normal.cross(&reference).normalize();
+        let bitangent = normal.cross(&tangent);
+        // Un cuarto del tamaño de un rasgo del ruido: menos es ruido numérico, más lo suaviza
+        let step = 0.25 / self.frequency.max(1e-3);
+        let du = displaced(position + tangent * step) - displaced(position - tangent * step);
+        let dv = displaced(position + bitangent * step) - displaced(position - bitangent * step);
+
+        let perturbed = du.cross(&dv).normalize();
+        let perturbed = if perturbed.dot(&normal) < 0.0 { -perturbed } else { perturbed };
+        (displaced(*position), perturbed)
+    }
+}
+
+// `--displace` activa el relieve de cada shader; `amplitude=`, `frequency=` y `speed=` lo reemplazan
+// y, si se dan, también deforman los shaders que no tienen uno propio
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Displacement {
+    amplitude: Option<f32>,
+    frequency: Option<f32>,
+    speed: Option<f32>,
+}
+
+impl Displacement {
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--displace")?;
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).and_then(|(_, v)| v.parse::<f32>().ok());
+        Some(Displacement {
+            amplitude: value("amplitude"),
+            frequency: value("frequency").map(|f| f.max(1e-3)),
+            speed: value("speed"),
+        })
+    }
+
+    // Lo que corresponde a un shader; ninguno si no tiene relieve propio ni se dio ningún valor.
+    // Los anillos (una malla plana) y la capa de nubes nunca se deforman.
+    pub fn resolve(&self, shader: ShaderKind) -> Option<DisplacementParams> {
+        if matches!(shader, ShaderKind::Ring | ShaderKind::CloudLayer) {
+            return None;
+        }
+        let overridden = self.amplitude.is_some() || self.frequency.is_some() || self.speed.is_some();
+        let base = DisplacementParams::preset(shader)
+            .or_else(|| overridden.then_some(DisplacementParams { amplitude: 0.02, frequency: 300.0, speed: 0.0, relief: Relief::Noise }))?;
+        Some(DisplacementParams {
+            amplitude: self.amplitude.unwrap_or(base.amplitude),
+            frequency: self.frequency.unwrap_or(base.frequency),
+            speed: self.speed.unwrap_or(base.speed),
+            relief: base.relief,
+        })
+    }
+}
@@ -0,0 +1,232 @@
+use std::ops::Range;
+use std::sync::OnceLock;
+use crate::cli::arg_value;
+use crate::color::Color;
+
+// Bayer 8x8, valores 0..63
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+const BLUE_NOISE_SIZE: usize = 64;
+// Radio del núcleo gaussiano con que void-and-cluster mide lo apiñado de cada punto
+const BLUE_NOISE_SIGMA: f32 = 1.5;
+// El patrón inicial ocupa uno de cada tantos píxeles de la baldosa antes de relajarlo
+const INITIAL_DENSITY: usize = 10;
+
+// Tramado de la salida: antes de redondear cada canal a 8 bits se le suma un desplazamiento de
+// menos de un nivel que depende del píxel, así un degradado lento entre dos colores vecinos se
+// reparte en una mezcla de los dos niveles en vez de escalonarse en bandas. Va después de la
+// gamma, en la misma cuenta de `Color::to_hex_dithered`, y el patrón es fijo entre cuadros.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputDither {
+    Off,
+    // Bayer 8x8: deja una trama regular apenas visible en las zonas planas
+    Bayer,
+    // Baldosa de ruido azul de 64x64: sin trama, solo grano fino
+    BlueNoise,
+}
+
+impl OutputDither {
+    pub const ALL: [OutputDither; 3] = [OutputDither::Off, OutputDither::Bayer, OutputDither::BlueNoise];
+
+    // `--output-dither off|bayer|blue`; por defecto apagado, así la salida queda igual que sin tramado
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        match arg_value(args, "--output-dither") {
+            None => Ok(OutputDither::Off),
+            Some(value) => OutputDither::ALL
+                .into_iter()
+                .find(|mode| mode.name() == value)
+                .ok_or_else(|| format!("Tramado de salida desconocido '{}' (off, bayer o blue)", value)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputDither::Off => "off",
+            OutputDither::Bayer => "bayer",
+            OutputDither::BlueNoise => "blue",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let position = OutputDither::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        OutputDither::ALL[(position + 1) % OutputDither::ALL.len()]
+    }
+
+    // Desplazamiento del píxel (x, y) en niveles de 0-255, en [-0.5, 0.5): su promedio es 0 y no
+    // cambia el brillo medio
+    pub fn offset(self, x: usize, y: usize) -> f32 {
+        match self {
+            OutputDither::Off => 0.0,
+            OutputDither::Bayer => (BAYER_8X8[y % 8][x % 8] as f32 + 0.5) / 64.0 - 0.5,
+            OutputDither::BlueNoise => {
+                let tile = blue_noise_tile();
+                tile[(y % BLUE_NOISE_SIZE) * BLUE_NOISE_SIZE + x % BLUE_NOISE_SIZE]
+            }
+        }
+    }
+}
+
+// Floyd–Steinberg al redondear a 8 bits lo que se guarda o se transmite: el resto de cada canal pasa
+// a los vecinos que faltan, con recorrido en serpentina. Es serial y cambia de un cuadro a otro, así
+// que la ventana sigue con `OutputDither`. Las filas fuera de `rows` (las franjas negras, que se
+// dibujan encima) se redondean sin tramado y no reciben error.
+pub fn diffuse(colors: &[Color], width: usize, rows: Range<usize>) -> Vec<u32> {
+    let mut output: Vec<u32> = colors.iter().map(|color| color.to_hex()).collect();
+    let rows = rows.start..rows.end.min(colors.len() / width.max(1));
+    let mut error = vec![[0.0f32; 3]; colors.len()];
+
+    for y in rows.clone() {
+        let forward = (y - rows.start).is_multiple_of(2);
+        for step in 0..width {
+            let x = if forward { step } else { width - 1 - step };
+            let index = y * width + x;
+            let levels = colors[index].levels();
+            let wanted: [f32; 3] = std::array::from_fn(|channel| levels[channel] + error[index][channel]);
+            let got = wanted.map(|level| level.round().clamp(0.0, 255.0));
+            output[index] = ((got[0] as u32) << 16) | ((got[1] as u32) << 8) | got[2] as u32;
+
+            let ahead = |dx: i32| if forward { x as i32 + dx } else { x as i32 - dx };
+            for (dx, dy, weight) in [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)] {
+                let (nx, ny) = (ahead(dx), y + dy);
+                if nx < 0 || nx >= width as i32 || ny >= rows.end {
+                    continue;
+                }
+                let neighbor = &mut error[ny * width + nx as usize];
+                for channel in 0..3 {
+                    neighbor[channel] += (wanted[channel] - got[channel]) * weight / 16.0;
+                }
+            }
+        }
+    }
+    output
+}
+
+// La baldosa se genera una vez, la primera vez que se pide
+fn blue_noise_tile() -> &'static [f32] {
+    static TILE: OnceLock<Vec<f32>> = OnceLock::new();
+    TILE.get_or_init(|| {
+        let count = BLUE_NOISE_SIZE * BLUE_NOISE_SIZE;
+        void_and_cluster()
+            .into_iter()
+            .map(|rank| (rank as f32 + 0.5) / count as f32 - 0.5)
+            .collect()
+    })
+}
+
+// Void-and-cluster (Ulichney): ordena los píxeles de la baldosa de modo que los primeros `n` de
+// cualquier umbral queden repartidos lo más parejo posible. La energía de un píxel es la suma
+// gaussiana, con la baldosa repetida en los bordes, de los puntos ya puestos; el punto más
+// apiñado es el de mayor energía y el hueco más grande el libre de menor.
+fn void_and_cluster() -> Vec<usize> {
+    let size = BLUE_NOISE_SIZE;
+    let count = size * size;
+    let kernel: Vec<f32> = (0..count)
+        .map(|index| {
+            let wrap = |d: usize| d.min(size - d) as f32;
+            let (dx, dy) = (wrap(index % size), wrap(index / size));
+            (-(dx * dx + dy * dy) / (2.0 * BLUE_NOISE_SIGMA * BLUE_NOISE_SIGMA)).exp()
+        })
+        .collect();
+    // Suma (o resta) el núcleo centrado en `index`; cada fila va en dos tramos por la vuelta del borde
+    let splat = |energy: &mut [f32], index: usize, sign: f32| {
+        let (x, y) = (index % size, index / size);
+        for (row, values) in energy.chunks_mut(size).enumerate() {
+            let weights = &kernel[(row + size - y) % size * size..][..size];
+            let (before, after) = values.split_at_mut(x);
+            for (value, weight) in after.iter_mut().zip(weights).chain(before.iter_mut().zip(&weights[size - x..])) {
+                *value += sign * weight;
+            }
+        }
+    };
+    let extreme = |energy: &[f32], points: &[bool], set: bool, tightest: bool| {
+        (0..count)
+            .filter(|&index| points[index] == set)
+            .max_by(|&a, &b| if tightest { energy[a].total_cmp(&energy[b]) } else { energy[b].total_cmp(&energy[a]) })
+            .unwrap_or(0)
+    };
+
+    // Patrón inicial con un generador fijo, relajado hasta que sacar el punto más apiñado y
+    // ponerlo en el hueco más grande lo deja igual (o hasta un tope, por si oscila)
+    let mut points = vec![false; count];
+    let mut energy = vec![0.0; count];
+    let mut state: u32 = 0x9e37_79b9;
+    let mut placed = 0;
+    while placed < count / INITIAL_DENSITY {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let index = state as usize % count;
+        if !points[index] {
+            points[index] = true;
+            splat(&mut energy, index, 1.0);
+            placed += 1;
+        }
+    }
+    for _ in 0..count {
+        let cluster = extreme(&energy, &points, true, true);
+        points[cluster] = false;
+        splat(&mut energy, cluster, -1.0);
+        let void = extreme(&energy, &points, false, false);
+        points[void] = true;
+        splat(&mut energy, void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    // Los puntos del patrón inicial toman los primeros rangos, del más apiñado hacia atrás, y
+    // después cada hueco más grande el siguiente
+    let mut ranks = vec![0; count];
+    let (mut removing, mut removing_energy) = (points.clone(), energy.clone());
+    for rank in (0..placed).rev() {
+        let cluster = extreme(&removing_energy, &removing, true, true);
+        removing[cluster] = false;
+        splat(&mut removing_energy, cluster, -1.0);
+        ranks[cluster] = rank;
+    }
+    for rank in placed..count {
+        let void = extreme(&energy, &points, false, false);
+        points[void] = true;
+        splat(&mut energy, void, 1.0);
+        ranks[void] = rank;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffusion_keeps_the_mean_and_skips_the_bars() {
+        let (width, height) = (32, 24);
+        // Un gris entre dos niveles de 8 bits
+        let gray = Color::from_hex(0x404040).lerp(&Color::from_hex(0x414141), 0.5);
+        let mut colors = vec![gray; width * height];
+        // Las filas de las franjas con un color que, difundido, ensuciaría las de al lado
+        let bar = Color::from_hex(0x7F7F7F).lerp(&Color::from_hex(0x808080), 0.5);
+        for (index, color) in colors.iter_mut().enumerate() {
+            if !(4..20).contains(&(index / width)) {
+                *color = bar;
+            }
+        }
+
+        let output = diffuse(&colors, width, 4..20);
+        let visible = &output[4 * width..20 * width];
+        let mean = visible.iter().map(|&pixel| (pixel & 0xFF) as f32).sum::<f32>() / visible.len() as f32;
+        let expected = gray.levels()[2];
+        assert!((mean - expected).abs() < 0.05, "promedio {} en vez de {}", mean, expected);
+        assert!(visible.contains(&0x404040) && visible.contains(&0x414141));
+        // Las franjas quedan redondeadas sin tramado
+        assert!(output[..4 * width].iter().chain(&output[20 * width..]).all(|&pixel| pixel == bar.to_hex()));
+    }
+}
@@ -0,0 +1,130 @@
+use nalgebra_glm::Vec3;
+use std::any::Any;
+use crate::frame_graph::{PassIo, PassTargets, TargetDesc, SCENE_DEPTH};
+use crate::framebuffer::Framebuffer;
+use crate::half_resolution::{linear_depth, HalfResolution};
+use crate::motion_blur::MOTION_BLURRED;
+use crate::post::PostPass;
+use crate::Uniforms;
+
+const DISC_SAMPLES: usize = 32;
+
+pub const FOCUSED: TargetDesc = TargetDesc::color("focused");
+
+// Profundidad de campo: círculo de confusión por píxel a partir del z-buffer,
+// desenfoque de disco a media resolución y reescalado bilateral.
+pub struct DepthOfField {
+    pub enabled: bool,
+    // Radio del círculo de confusión (en píxeles) para un objeto al doble de la distancia focal
+    pub aperture: f32,
+    // Ajuste manual sumado a la distancia focal automática (distancia cámara-modelo)
+    pub focus_offset: f32,
+    pub max_radius: f32,
+    disc: Vec<(f32, f32)>,
+}
+
+impl DepthOfField {
+    pub fn new() -> Self {
+        // Espiral de ángulo áureo: muestras repartidas uniformemente en el disco unidad
+        let golden_angle = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+        let disc = (0..DISC_SAMPLES)
+            .map(|i| {
+                let radius = ((i as f32 + 0.5) / DISC_SAMPLES as f32).sqrt();
+                let angle = i as f32 * golden_angle;
+                (radius * angle.cos(), radius * angle.sin())
+            })
+            .collect();
+
+        DepthOfField {
+            enabled: false,
+            aperture: 6.0,
+            focus_offset: 0.0,
+            max_radius: 16.0,
+            disc,
+        }
+    }
+
+    fn focus_distance(&self, uniforms: &Uniforms) -> f32 {
+        let model_center = uniforms.model_matrix.column(3).xyz();
+        ((uniforms.camera_position - model_center).magnitude() + self.focus_offset).max(0.1)
+    }
+
+    fn circle_of_confusion(&self, depth: f32, focus: f32) -> f32 {
+        (self.aperture * (depth - focus).abs() / depth).min(self.max_radius)
+    }
+
+    fn blur(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        let Some(depth) = linear_depth(framebuffer, uniforms) else {
+            return;
+        };
+        let focus = self.focus_distance(uniforms);
+        let coc: Vec<f32> = depth.iter().map(|&d| self.circle_of_confusion(d, focus)).collect();
+
+        // CoC del píxel más cercano de cada bloque, en píxeles de media resolución
+        let half = HalfResolution::downsample(framebuffer, &depth);
+        let (half_width, half_height) = (half.width, half.height);
+        let half_coc: Vec<f32> = half.nearest.iter().map(|&index| coc[index] * 0.5).collect();
+
+        // Desenfoque de disco con el radio del CoC central. Una muestra más cercana que el
+        // centro solo cuenta si su propio CoC la alcanza, así lo enfocado no se derrama sobre lo borroso.
+        let mut blurred = vec![Vec3::zeros(); half_width * half_height];
+        for hy in 0..half_height {
+            for hx in 0..half_width {
+                let center = hy * half_width + hx;
+                let radius = half_coc[center];
+                if radius < 0.5 {
+                    blurred[center] = half.color[center];
+                    continue;
+                }
+
+                let mut sum = half.color[center];
+                let mut weight = 1.0;
+                for &(dx, dy) in &self.disc {
+                    let sx = hx as f32 + dx * radius;
+                    let sy = hy as f32 + dy * radius;
+                    if sx < 0.0 || sy < 0.0 || sx >= half_width as f32 || sy >= half_height as f32 {
+                        continue;
+                    }
+                    let sample = sy as usize * half_width + sx as usize;
+                    let distance = (dx * dx + dy * dy).sqrt() * radius;
+                    let reach = if half.depth[sample] >= half.depth[center] { radius } else { half_coc[sample] };
+                    if reach >= distance {
+                        sum += half.color[sample];
+                        weight += 1.0;
+                    }
+                }
+                blurred[center] = sum / weight;
+            }
+        }
+
+        half.upsample(&blurred, framebuffer, &depth, |index| ((coc[index] - 0.5) / 1.0).clamp(0.0, 1.0));
+    }
+}
+
+impl PostPass for DepthOfField {
+    fn name(&self) -> &'static str {
+        "depth_of_field"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn io(&self) -> PassIo {
+        PassIo { reads: vec![MOTION_BLURRED, SCENE_DEPTH], writes: vec![FOCUSED], scratch: Vec::new() }
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, _targets: &mut PassTargets) {
+        if self.enabled {
+            self.blur(framebuffer, uniforms);
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
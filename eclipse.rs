@@ -0,0 +1,53 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::light::Light;
+use crate::shaders::smoothstep;
+
+// Margen con que un fragmento reconoce el centro de su propio cuerpo (ver `light_visibility`)
+const SELF_TOLERANCE: f32 = 1e-4;
+// Ancho mínimo de la penumbra, para que el borde de la sombra no escalone aunque la luz sea un punto
+const MIN_PENUMBRA: f32 = 0.005;
+
+// Cuerpo que puede tapar la luz: su esfera en espacio de mundo, centrada en el origen de su modelo
+#[derive(Clone, Copy, Debug)]
+pub struct Occluder {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Occluder {
+    // `radius` es el radio medio de la malla; la matriz de modelo lo escala (escala uniforme)
+    pub fn of(matrix: &Mat4, radius: f32) -> Self {
+        let center = (matrix * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+        let scale = matrix.column(0).xyz().magnitude();
+        Occluder { center, radius: radius * scale }
+    }
+}
+
+// Fracción de la luz que llega a `point`: 1 a plena luz y 0 en la umbra. El rayo hacia la luz se
+// compara con la esfera de cada cuerpo donde pasa más cerca de su centro; la penumbra crece con la
+// distancia al cuerpo como la sombra de una luz de radio `light.radius`. Se salta el cuerpo con
+// centro en `own_center` (el que se está sombreando, que si no se taparía a sí mismo en el
+// terminador) y los que contienen la luz, como el sol del sistema solar.
+pub fn light_visibility(point: &Vec3, light: &Light, occluders: &[Occluder], own_center: &Vec3) -> f32 {
+    let to_light = light.position - *point;
+    let light_distance = to_light.magnitude();
+    if light_distance <= f32::EPSILON {
+        return 1.0;
+    }
+    let direction = to_light / light_distance;
+
+    occluders
+        .iter()
+        .filter(|occluder| (occluder.center - *own_center).magnitude() > SELF_TOLERANCE)
+        .filter(|occluder| (occluder.center - light.position).magnitude() > occluder.radius)
+        .map(|occluder| {
+            let along = (occluder.center - *point).dot(&direction);
+            if along <= 0.0 || along >= light_distance {
+                return 1.0;
+            }
+            let miss = (occluder.center - (*point + direction * along)).magnitude();
+            let penumbra = (light.radius * along / light_distance).max(MIN_PENUMBRA);
+            smoothstep(occluder.radius - penumbra, occluder.radius + penumbra, miss)
+        })
+        .fold(1.0, f32::min)
+}
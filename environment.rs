@@ -0,0 +1,128 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::noise::{sphere_noise, NoiseSource};
+use crate::spherical::great_circle_distance;
+use crate::worley::worley_cell;
+
+// El mismo fondo con el que se limpia el framebuffer, para que el cielo reflejado empalme con él
+pub const SPACE_COLOR: Color = Color::from_hex(0x333355);
+
+// Celdas del cielo por unidad de dirección, fracción de ellas que tienen estrella, radio de la
+// estrella relativo a la celda y colores entre los que se elige según la celda
+struct StarLayer {
+    density: f32,
+    chance: f32,
+    size: f32,
+    colors: &'static [Color],
+}
+
+// Puntos pequeños, blancos con un toque azul o amarillo
+const SMALL_STARS: StarLayer = StarLayer {
+    density: 45.0,
+    chance: 0.12,
+    size: 0.09,
+    colors: &[Color::new(200, 215, 255), Color::new(255, 235, 200)],
+};
+// El fondo de la escena se ve a un píxel por estrella: más puntos y algo más gruesos que en los reflejos
+const POINT_STARS: StarLayer = StarLayer {
+    density: 60.0,
+    chance: 0.3,
+    size: 0.16,
+    colors: SMALL_STARS.colors,
+};
+// Pocas estrellas más grandes y con más color
+const LARGE_STARS: StarLayer = StarLayer {
+    density: 14.0,
+    chance: 0.08,
+    size: 0.13,
+    colors: &[Color::new(150, 180, 255), Color::new(255, 190, 120), Color::new(255, 140, 110), Color::new(190, 255, 240)],
+};
+
+// Nebulosa del fondo: zoom del ruido sobre la esfera de direcciones, sus dos tintes y cuánto tiñe
+const NEBULA_ZOOM: f32 = 30.0;
+const NEBULA_COLORS: [Color; 2] = [Color::new(110, 60, 140), Color::new(40, 110, 130)];
+const NEBULA_STRENGTH: f32 = 0.6;
+
+// Cielo estrellado procedural según la dirección de un rayo en espacio de mundo. Cada celda de
+// Worley de la esfera de direcciones puede llevar una estrella en su punto característico.
+pub fn environment(direction: &Vec3) -> Color {
+    let direction = direction.normalize();
+    if !direction.iter().all(|c| c.is_finite()) {
+        return SPACE_COLOR;
+    }
+    stars(&direction, SPACE_COLOR, &SMALL_STARS)
+}
+
+// Fondo de la escena con `--starfield`: el mismo cielo con estrellas grandes y una nebulosa tenue
+// del generador de ruido. Nada depende del tiempo, así que con la cámara quieta no titila.
+pub fn starfield(direction: &Vec3, noise: &dyn NoiseSource) -> Color {
+    let direction = direction.normalize();
+    if !direction.iter().all(|c| c.is_finite()) {
+        return SPACE_COLOR;
+    }
+
+    let density = sphere_noise(noise, &direction, NEBULA_ZOOM, Vec3::zeros()) * 0.5 + 0.5;
+    let hue = sphere_noise(noise, &direction, NEBULA_ZOOM * 0.5, Vec3::new(57.0, 0.0, 0.0)) * 0.5 + 0.5;
+    let tint = NEBULA_COLORS[0].lerp(&NEBULA_COLORS[1], hue.clamp(0.0, 1.0));
+    let nebula = SPACE_COLOR.lerp(&tint, ((density - 0.35) / 0.65).clamp(0.0, 1.0) * NEBULA_STRENGTH);
+
+    let sky = stars(&direction, nebula, &POINT_STARS);
+    stars(&direction, sky, &LARGE_STARS)
+}
+
+// Disco del sol visto desde el suelo: su color, su radio angular y el ancho del halo, en radianes
+const SUN_COLOR: Color = Color::new(255, 244, 214);
+const SUN_ANGULAR_RADIUS: f32 = 0.03;
+const SUN_GLOW: f32 = 0.1;
+
+// Cielo de fondo del observador en la superficie: las estrellas se apagan hacia negro con `glare`,
+// para que de día la atmósfera pinte el cielo sobre negro, y el sol va encima con su halo
+pub fn surface_sky(direction: &Vec3, night: Color, sun: Option<&Vec3>, glare: f32) -> Color {
+    let sky = night.lerp(&Color::new(0, 0, 0), glare);
+    let Some(sun) = sun else {
+        return sky;
+    };
+    let angle = great_circle_distance(direction, sun);
+    if angle < SUN_ANGULAR_RADIUS {
+        return SUN_COLOR;
+    }
+    sky.lerp(&SUN_COLOR, (-(angle - SUN_ANGULAR_RADIUS) / SUN_GLOW).exp() * 0.6)
+}
+
+// `direction` ya normalizada
+fn stars(direction: &Vec3, background: Color, layer: &StarLayer) -> Color {
+    let (f1, _, cell) = worley_cell(&(direction * layer.density), 0.0);
+    if cell.x >= layer.chance {
+        return background;
+    }
+    let brightness = (1.0 - f1 / layer.size).clamp(0.0, 1.0).powi(2) * (0.5 + 0.5 * cell.y);
+    let star = layer.colors[((cell.z * layer.colors.len() as f32) as usize).min(layer.colors.len() - 1)];
+    background.lerp(&star, brightness)
+}
+
+// Copia del color ya dibujado, para los shaders que miran a través de su superficie. Se toma justo
+// antes de dibujar el cuerpo que refracta, así contiene todo lo opaco que quedó detrás.
+pub struct Backdrop {
+    width: usize,
+    height: usize,
+    pixels: Vec<u32>,
+}
+
+impl Backdrop {
+    pub fn capture(framebuffer: &Framebuffer) -> Self {
+        Backdrop {
+            width: framebuffer.width,
+            height: framebuffer.height,
+            pixels: framebuffer.buffer.clone(),
+        }
+    }
+
+    // Color en una posición de pantalla; None si cae fuera de la imagen
+    pub fn sample(&self, x: f32, y: f32) -> Option<Color> {
+        if !(x >= 0.0 && y >= 0.0 && x < self.width as f32 && y < self.height as f32) {
+            return None;
+        }
+        Some(Color::from_hex(self.pixels[y as usize * self.width + x as usize]))
+    }
+}
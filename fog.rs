@@ -0,0 +1,69 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::animation::params;
+use crate::cli::key_values;
+use crate::color::Color;
+use crate::environment::SPACE_COLOR;
+use crate::Uniforms;
+
+// Niebla por distancia: cada fragmento de geometría se mezcla con `color` según cuánto pasa de
+// `start` su profundidad en espacio de vista, con caída exponencial exp(-density · d). El fondo y
+// las estrellas no pasan por aquí, así que no se aplanan a un color.
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    pub enabled: bool,
+    pub color: Color,
+    pub density: f32,
+    pub start: f32,
+    // Fracción de la niebla que reciben los shaders que emiten luz (el sol) y su resplandor:
+    // con 0 atraviesan la niebla sin perder nada, con 1 se apagan como el resto
+    pub emissive: f32,
+}
+
+impl Default for Fog {
+    // Del color del fondo, así lo lejano se funde con él
+    fn default() -> Self {
+        Fog { enabled: false, color: SPACE_COLOR, density: 0.15, start: 4.0, emissive: 0.3 }
+    }
+}
+
+impl Fog {
+    // `--fog [color=333355] [density=0.15] [start=4] [emissive=0.3]` la activa desde el inicio
+    pub fn from_args(args: &[String]) -> Self {
+        let defaults = Fog::default();
+        let Some(pairs) = key_values(args, "--fog") else {
+            return defaults;
+        };
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let number = |key: &str, default: f32| value(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+
+        Fog {
+            enabled: true,
+            color: value("color")
+                .and_then(|hex| u32::from_str_radix(hex.trim_start_matches('#'), 16).ok())
+                .map(Color::from_hex)
+                .unwrap_or(defaults.color),
+            density: number("density", defaults.density).max(0.0),
+            start: number("start", defaults.start).max(0.0),
+            emissive: number("emissive", defaults.emissive).clamp(0.0, 1.0),
+        }
+    }
+
+    // Color y emisión de un fragmento ya sombreado; `emissive` si su shader emite luz (ver
+    // `ShaderKind::emits`). La emisión no se tiñe: solo se atenúa.
+    pub fn apply(&self, uniforms: &Uniforms, vertex_position: &Vec3, emissive: bool, color: Color, emission: Color) -> (Color, Color) {
+        if !self.enabled || self.density <= 0.0 {
+            return (color, emission);
+        }
+        let p = uniforms.view_matrix * uniforms.model_matrix * Vec4::new(vertex_position.x, vertex_position.y, vertex_position.z, 1.0);
+        let distance = (-p.z - self.start).max(0.0);
+        let mut amount = 1.0 - (-self.density * distance).exp();
+        if emissive {
+            amount *= self.emissive;
+        }
+        // La opacidad es la del fragmento: la niebla no vuelve opaco lo transparente
+        let fogged = color.lerp(&self.color.with_alpha(color.alpha()), amount);
+        (fogged, emission * (1.0 - amount))
+    }
+}
+
+params!(Fog { density, start, emissive } colors { color });
@@ -9,6 +9,11 @@ pub struct Fragment {
     pub normal: Vec3,
     pub intensity: f32,
     pub vertex_position: Vec3,
+    // Coordenadas UV interpoladas de la malla; las líneas las dejan en cero
+    pub tex_coords: Vec2,
+    // Lado aproximado del píxel en espacio de objeto, para el nivel de detalle de los shaders;
+    // 0 (las líneas) es detalle completo
+    pub footprint: f32,
 }
 
 impl Fragment {
@@ -19,7 +24,9 @@ impl Fragment {
             depth,
             normal,
             intensity,
-            vertex_position
+            vertex_position,
+            tex_coords: Vec2::zeros(),
+            footprint: 0.0,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,349 @@
+use crate::framebuffer::Framebuffer;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TargetFormat {
+    Color,
+    Depth,
+}
+
+// Un objetivo con nombre: formato y divisor de la resolución del framebuffer (1 completa, 2 mitad)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TargetDesc {
+    pub name: &'static str,
+    pub format: TargetFormat,
+    pub scale: usize,
+}
+
+impl TargetDesc {
+    pub const fn color(name: &'static str) -> Self {
+        TargetDesc { name, format: TargetFormat::Color, scale: 1 }
+    }
+
+    pub const fn depth(name: &'static str) -> Self {
+        TargetDesc { name, format: TargetFormat::Depth, scale: 1 }
+    }
+}
+
+// Lo que dejó el render en el framebuffer; existen antes de la primera pasada
+pub const SCENE_COLOR: TargetDesc = TargetDesc::color("scene");
+pub const SCENE_DEPTH: TargetDesc = TargetDesc::depth("depth");
+// Luz que emitieron los shaders (ver `Shaded::emission`)
+pub const SCENE_EMISSION: TargetDesc = TargetDesc::color("emission");
+const IMPORTED: [TargetDesc; 3] = [SCENE_COLOR, SCENE_DEPTH, SCENE_EMISSION];
+
+// Entradas y salidas de una pasada. La pasada trabaja en el lugar: recibe en el framebuffer su
+// primera lectura de color, que al terminar queda como su primera escritura. Las demás lecturas
+// solo ordenan (la profundidad y la emisión se leen siempre del framebuffer). `scratch` son buffers que la
+// pasada usa solo mientras se ejecuta y que el grafo puede compartir con otras.
+#[derive(Default)]
+pub struct PassIo {
+    pub reads: Vec<TargetDesc>,
+    pub writes: Vec<TargetDesc>,
+    pub scratch: Vec<TargetDesc>,
+}
+
+impl PassIo {
+    fn input(&self) -> Option<&TargetDesc> {
+        self.reads.iter().find(|target| target.format == TargetFormat::Color)
+    }
+}
+
+// Buffer físico 0: el color del framebuffer
+const FRAMEBUFFER: usize = 0;
+
+// Orden de ejecución y buffer físico de cada objetivo, ya validados
+pub struct FramePlan {
+    names: Vec<&'static str>,
+    // Índices de pasada en el orden en que se ejecutan
+    order: Vec<usize>,
+    // (objetivo, buffer físico); los buffers que no son el framebuffer se numeran desde 1
+    assignments: Vec<(&'static str, usize)>,
+    // Escala de cada buffer físico
+    slots: Vec<usize>,
+    // (entrada, salida) física de cada pasada, por índice de pasada
+    routes: Vec<(usize, usize)>,
+    output: usize,
+}
+
+impl FramePlan {
+    // Resuelve las dependencias entre pasadas por los nombres de sus objetivos y asigna buffers.
+    // Falla si algo se lee sin que nadie lo escriba, si dos pasadas escriben lo mismo, si el
+    // formato o la resolución no coinciden o si las dependencias forman un ciclo.
+    pub fn compile(passes: &[(&'static str, PassIo)]) -> Result<Self, String> {
+        let names: Vec<&'static str> = passes.iter().map(|(name, _)| *name).collect();
+        let mut producers: Vec<(TargetDesc, Option<usize>)> = IMPORTED.iter().map(|target| (*target, None)).collect();
+
+        for (index, (name, io)) in passes.iter().enumerate() {
+            let Some(output) = io.writes.first() else {
+                return Err(format!("La pasada '{}' no escribe nada", name));
+            };
+            if output.format != TargetFormat::Color || output.scale != 1 {
+                return Err(format!("La pasada '{}' trabaja en el lugar: su primera escritura ('{}') debe ser color a resolución completa", name, output.name));
+            }
+            if io.input().is_none() {
+                return Err(format!("La pasada '{}' no lee ningún color", name));
+            }
+            for target in io.writes.iter().chain(&io.scratch) {
+                if target.format != TargetFormat::Color {
+                    return Err(format!("La pasada '{}' escribe '{}', pero solo se asignan objetivos de color", name, target.name));
+                }
+                if let Some((_, producer)) = producers.iter().find(|(existing, _)| existing.name == target.name) {
+                    let other = producer.map_or("el framebuffer", |producer| names[producer]);
+                    return Err(format!("'{}' lo escriben '{}' y '{}'", target.name, other, name));
+                }
+                producers.push((*target, Some(index)));
+            }
+        }
+
+        // Dependencias: quien escribe un objetivo va antes que quien lo lee
+        let mut dependencies: Vec<Vec<usize>> = vec![Vec::new(); passes.len()];
+        for (index, (name, io)) in passes.iter().enumerate() {
+            for read in &io.reads {
+                let Some((target, producer)) = producers.iter().find(|(target, _)| target.name == read.name) else {
+                    return Err(format!("La pasada '{}' lee '{}', que nadie escribe", name, read.name));
+                };
+                if io.scratch.iter().any(|scratch| scratch.name == read.name) {
+                    return Err(format!("La pasada '{}' lee '{}', que es su propio buffer temporal", name, read.name));
+                }
+                if target.format != read.format || target.scale != read.scale {
+                    return Err(format!("La pasada '{}' pide '{}' en otro formato o resolución", name, read.name));
+                }
+                if let Some(producer) = producer {
+                    if !dependencies[index].contains(producer) {
+                        dependencies[index].push(*producer);
+                    }
+                }
+            }
+        }
+        let order = execution_order(&names, &dependencies)?;
+
+        // Vida de cada objetivo: desde la posición en que se escribe hasta la última que lo lee
+        let position = |pass: usize| order.iter().position(|&p| p == pass).unwrap_or(0);
+        let last_read = |name: &str| {
+            passes.iter().enumerate()
+                .filter(|(_, (_, io))| io.reads.iter().any(|read| read.name == name))
+                .map(|(pass, _)| position(pass))
+                .max()
+        };
+
+        // El resultado de la cadena es el último color escrito que nadie lee
+        let unread: Vec<&'static str> = order.iter()
+            .flat_map(|&pass| passes[pass].1.writes.iter())
+            .filter(|target| last_read(target.name).is_none())
+            .map(|target| target.name)
+            .collect();
+        let result = match unread.as_slice() {
+            [] if passes.is_empty() => SCENE_COLOR.name,
+            [result] => *result,
+            [] => return Err("Ningún color queda sin leer: la cadena no tiene resultado".to_string()),
+            several => return Err(format!("La cadena tiene más de un resultado: {}", several.join(", "))),
+        };
+
+        // Buffers físicos: (escala, última posición en uso). Un objetivo reutiliza el primero libre de
+        // su misma escala; la salida de una pasada puede ocupar el de su entrada si esta muere ahí.
+        let mut slots: Vec<(usize, Option<usize>)> = vec![(1, last_read(SCENE_COLOR.name))];
+        let mut assignments = vec![(SCENE_COLOR.name, FRAMEBUFFER)];
+        let mut routes = vec![(FRAMEBUFFER, FRAMEBUFFER); passes.len()];
+        for (at, &pass) in order.iter().enumerate() {
+            let io = &passes[pass].1;
+            let input_name = io.input().map(|target| target.name).unwrap_or(SCENE_COLOR.name);
+            let input = assignments.iter().find(|(name, _)| *name == input_name).map_or(FRAMEBUFFER, |(_, slot)| *slot);
+
+            for (nth, target) in io.writes.iter().chain(&io.scratch).enumerate() {
+                let scratch = nth >= io.writes.len();
+                let end = if target.name == result { None } else if scratch { Some(at) } else { last_read(target.name) };
+                let free = |slot: usize, (scale, last_use): (usize, Option<usize>)| {
+                    scale == target.scale
+                        && !(scratch && slot == FRAMEBUFFER)
+                        && match last_use {
+                            Some(last_use) => last_use < at || (nth == 0 && last_use == at && slot == input),
+                            None => false,
+                        }
+                };
+                let slot = match slots.iter().enumerate().position(|(slot, state)| free(slot, *state)) {
+                    Some(slot) => {
+                        slots[slot].1 = end;
+                        slot
+                    }
+                    None => {
+                        slots.push((target.scale, end));
+                        slots.len() - 1
+                    }
+                };
+                assignments.push((target.name, slot));
+                if nth == 0 {
+                    routes[pass] = (input, slot);
+                }
+            }
+        }
+
+        let output = assignments.iter().find(|(name, _)| *name == result).map_or(FRAMEBUFFER, |(_, slot)| *slot);
+        Ok(FramePlan {
+            names,
+            order,
+            assignments,
+            slots: slots.iter().map(|(scale, _)| *scale).collect(),
+            routes,
+            output,
+        })
+    }
+
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    fn slot_of(&self, name: &str) -> Option<usize> {
+        self.assignments.iter().find(|(target, _)| *target == name).map(|(_, slot)| *slot)
+    }
+
+    // Orden de ejecución y buffer de cada objetivo, para `--frame-graph-debug`
+    pub fn describe(&self) -> String {
+        let order: Vec<&str> = self.order.iter().map(|&pass| self.names[pass]).collect();
+        let mut text = format!("Grafo de post-proceso: {}\n", if order.is_empty() { "(sin pasadas)".to_string() } else { order.join(" -> ") });
+        for (slot, scale) in self.slots.iter().enumerate() {
+            let targets: Vec<&str> = self.assignments.iter().filter(|(_, s)| *s == slot).map(|(name, _)| *name).collect();
+            let buffer = if slot == FRAMEBUFFER { "framebuffer".to_string() } else { format!("buffer {} (1/{})", slot, scale) };
+            text.push_str(&format!("  {}: {}\n", buffer, targets.join(", ")));
+        }
+        if self.output != FRAMEBUFFER {
+            text.push_str(&format!("  el resultado se copia del buffer {} al framebuffer\n", self.output));
+        }
+        text
+    }
+}
+
+// Orden topológico estable: entre las pasadas listas va primero la que se agregó antes.
+// Si quedan pasadas sin ordenar hay un ciclo; se nombran las que lo forman, sin las que solo dependen de él.
+fn execution_order(names: &[&'static str], dependencies: &[Vec<usize>]) -> Result<Vec<usize>, String> {
+    let mut order = Vec::new();
+    let mut pending: Vec<usize> = (0..names.len()).collect();
+    while let Some(at) = pending.iter().position(|&pass| dependencies[pass].iter().all(|dependency| order.contains(dependency))) {
+        order.push(pending.remove(at));
+    }
+    if pending.is_empty() {
+        return Ok(order);
+    }
+
+    loop {
+        let before = pending.len();
+        let snapshot = pending.clone();
+        pending.retain(|&pass| snapshot.iter().any(|&other| dependencies[other].contains(&pass)));
+        if pending.len() == before {
+            break;
+        }
+    }
+    let cycle: Vec<&str> = pending.iter().map(|&pass| names[pass]).collect();
+    Err(format!("Las pasadas {} dependen entre sí en un ciclo", cycle.join(", ")))
+}
+
+// Buffers físicos del plan, reutilizados entre cuadros
+#[derive(Default)]
+pub struct FrameTargets {
+    slots: Vec<Vec<u32>>,
+}
+
+impl FrameTargets {
+    // Ajusta cada buffer al tamaño del framebuffer actual (cambia al entrar al modo retro)
+    pub fn prepare(&mut self, plan: &FramePlan, framebuffer: &Framebuffer) {
+        self.slots.resize_with(plan.slots.len(), Vec::new);
+        for (slot, &scale) in self.slots.iter_mut().zip(&plan.slots).skip(1) {
+            let size = framebuffer.width.div_ceil(scale) * framebuffer.height.div_ceil(scale);
+            slot.resize(size, 0);
+        }
+    }
+
+    // Deja en el framebuffer la entrada de la pasada sobre el buffer de su salida
+    pub fn begin(&mut self, plan: &FramePlan, pass: usize, framebuffer: &mut Framebuffer) {
+        let (input, output) = plan.routes[pass];
+        self.copy(framebuffer, input, output);
+        if output != FRAMEBUFFER {
+            std::mem::swap(&mut framebuffer.buffer, &mut self.slots[output]);
+        }
+    }
+
+    pub fn end(&mut self, plan: &FramePlan, pass: usize, framebuffer: &mut Framebuffer) {
+        let (_, output) = plan.routes[pass];
+        if output != FRAMEBUFFER {
+            std::mem::swap(&mut framebuffer.buffer, &mut self.slots[output]);
+        }
+    }
+
+    // Lleva el resultado al framebuffer si el plan lo dejó en otro buffer
+    pub fn finish(&mut self, plan: &FramePlan, framebuffer: &mut Framebuffer) {
+        self.copy(framebuffer, plan.output, FRAMEBUFFER);
+    }
+
+    // Buffer temporal declarado por la pasada en `PassIo::scratch`
+    pub fn scratch<'a>(&'a mut self, plan: &FramePlan, name: &str) -> Option<&'a mut Vec<u32>> {
+        let slot = plan.slot_of(name).filter(|&slot| slot != FRAMEBUFFER)?;
+        self.slots.get_mut(slot)
+    }
+
+    // Lo que hay ahora en un objetivo, para volcarlo (ver `Readback`)
+    pub fn contents<'a>(&'a self, plan: &FramePlan, name: &str, framebuffer: &'a Framebuffer) -> Option<&'a [u32]> {
+        match plan.slot_of(name)? {
+            FRAMEBUFFER => Some(&framebuffer.buffer),
+            slot => self.slots.get(slot).map(Vec::as_slice),
+        }
+    }
+
+    fn copy(&mut self, framebuffer: &mut Framebuffer, from: usize, to: usize) {
+        match (from, to) {
+            _ if from == to => {}
+            (FRAMEBUFFER, _) => self.slots[to].copy_from_slice(&framebuffer.buffer),
+            (_, FRAMEBUFFER) => framebuffer.buffer.copy_from_slice(&self.slots[from]),
+            _ => {
+                let source = std::mem::take(&mut self.slots[from]);
+                self.slots[to].copy_from_slice(&source);
+                self.slots[from] = source;
+            }
+        }
+    }
+}
+
+// Lo que recibe una pasada al ejecutarse para pedir sus buffers temporales
+pub struct PassTargets<'a> {
+    pub(crate) plan: &'a FramePlan,
+    pub(crate) targets: &'a mut FrameTargets,
+}
+
+impl PassTargets<'_> {
+    pub fn scratch(&mut self, name: &str) -> Option<&mut Vec<u32>> {
+        self.targets.scratch(self.plan, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(name: &'static str, reads: &[&'static str], writes: &[&'static str]) -> (&'static str, PassIo) {
+        let targets = |names: &[&'static str]| names.iter().map(|&name| TargetDesc::color(name)).collect();
+        (name, PassIo { reads: targets(reads), writes: targets(writes), scratch: Vec::new() })
+    }
+
+    #[test]
+    fn cycle_names_only_the_passes_in_it() {
+        let passes = [
+            pass("source", &["scene"], &["lit"]),
+            pass("first", &["looped", "lit"], &["graded"]),
+            pass("second", &["graded"], &["looped"]),
+            // Depende del ciclo pero no es parte de él
+            pass("viewer", &["graded"], &["shown"]),
+        ];
+        let Some(error) = FramePlan::compile(&passes).err() else {
+            panic!("el ciclo debía rechazarse");
+        };
+        assert_eq!(error, "Las pasadas first, second dependen entre sí en un ciclo");
+    }
+
+    #[test]
+    fn chain_runs_in_dependency_order() {
+        let passes = [
+            pass("grade", &["bloomed"], &["graded"]),
+            pass("bloom", &["scene", "emission"], &["bloomed"]),
+        ];
+        let plan = FramePlan::compile(&passes).unwrap_or_else(|error| panic!("{}", error));
+        assert_eq!(plan.order(), &[1, 0]);
+    }
+}
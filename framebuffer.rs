@@ -4,6 +4,8 @@ pub struct Framebuffer {
     pub height: usize,
     pub buffer: Vec<u32>,
     pub zbuffer: Vec<f32>,
+    // Lo que emiten los shaders en cada píxel, 0xRRGGBB; negro donde nada brilla por sí mismo
+    pub emission: Vec<u32>,
     background_color: u32,
     current_color: u32,
 }
@@ -15,6 +17,7 @@ impl Framebuffer {
             height,
             buffer: vec![0; width * height],
             zbuffer: vec![f32::INFINITY; width * height],
+            emission: vec![0; width * height],
             background_color: 0x000000,
             current_color: 0xFFFFFF
         }
@@ -27,15 +30,35 @@ impl Framebuffer {
         for depth in self.zbuffer.iter_mut() {
             *depth = f32::INFINITY;
         }
+        self.emission.fill(0);
     }
 
-    pub fn point(&mut self, x: usize, y: usize, depth: f32) {
+    // Devuelve false si el punto quedó fuera o detrás de lo ya dibujado
+    pub fn point(&mut self, x: usize, y: usize, depth: f32) -> bool {
         if x < self.width && y < self.height {
             let index = y * self.width + x;
 
             if self.zbuffer[index] > depth {
                 self.buffer[index] = self.current_color;
                 self.zbuffer[index] = depth;
+                return true;
+            }
+        }
+        false
+    }
+
+    // Escribe sin prueba de profundidad, para elementos que van encima de la escena
+    pub fn overlay_point(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.buffer[y * self.width + x] = self.current_color;
+        }
+    }
+
+    // Rectángulo lleno sin prueba de profundidad, recortado a los bordes
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: u32) {
+        for py in y..(y + height).min(self.height) {
+            for px in x..(x + width).min(self.width) {
+                self.buffer[py * self.width + px] = color;
             }
         }
     }
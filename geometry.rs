@@ -0,0 +1,200 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::cli::key_values;
+use crate::spherical::lat_long_to_dir;
+use crate::vertex::Vertex;
+
+// Radio de las esferas de la escena: el mismo de la esfera .obj que se usaba antes
+pub const SPHERE_RADIUS: f32 = 0.5;
+
+// Radio medio del modelo, para muestrear el terreno sobre la misma superficie que ve el shader
+pub fn mean_radius(vertices: &[Vertex]) -> f32 {
+    if vertices.is_empty() {
+        return 1.0;
+    }
+    vertices.iter().map(|v| v.position.magnitude()).sum::<f32>() / vertices.len() as f32
+}
+
+// Malla de la esfera de los cuerpos cuando no se pasa `--model`
+#[derive(Clone, Copy, Debug)]
+pub enum SphereMesh {
+    Uv { stacks: usize, slices: usize },
+    Ico { subdivisions: u32 },
+}
+
+impl SphereMesh {
+    // `--sphere [type=ico] [subdivisions=3]` o `--sphere type=uv [stacks=24] [slices=48]`
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let Some(pairs) = key_values(args, "--sphere") else {
+            return Ok(SphereMesh::Ico { subdivisions: 3 });
+        };
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let count = |key: &str, default: usize| value(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+        match value("type").unwrap_or("ico") {
+            // Más de 6 subdivisiones son cientos de miles de triángulos
+            "ico" => Ok(SphereMesh::Ico { subdivisions: count("subdivisions", 3).min(6) as u32 }),
+            "uv" => Ok(SphereMesh::Uv { stacks: count("stacks", 24), slices: count("slices", 48) }),
+            other => Err(format!("Esfera desconocida '{}': usa ico o uv", other)),
+        }
+    }
+
+    pub fn vertices(self) -> Vec<Vertex> {
+        match self {
+            SphereMesh::Uv { stacks, slices } => create_uv_sphere(SPHERE_RADIUS, stacks, slices),
+            SphereMesh::Ico { subdivisions } => create_icosphere(SPHERE_RADIUS, subdivisions),
+        }
+    }
+}
+
+// Las UV siguen la proyección esférica de las texturas (`Texture::coordinates`): u es la longitud
+// desde -180 grados y v la colatitud desde el polo norte, así una imagen equirectangular calza igual
+// con cualquiera de las dos.
+fn sphere_uv(direction: &Vec3) -> Vec2 {
+    let longitude = direction.z.atan2(direction.x);
+    let latitude = direction.y.clamp(-1.0, 1.0).asin();
+    Vec2::new((longitude + PI) / (2.0 * PI), (PI / 2.0 - latitude) / PI)
+}
+
+fn sphere_vertex(direction: Vec3, radius: f32, uv: Vec2) -> Vertex {
+    Vertex::new(direction * radius, direction, uv)
+}
+
+// Esfera por paralelos y meridianos, como lista de triángulos con las caras de frente hacia afuera.
+// La columna `slices` repite la posición de la 0 con u = 1 para que la costura no arrastre la textura
+// de un borde al otro, y cada casquete es un abanico: un triángulo por meridiano con su propio
+// vértice del polo, en el centro de su u, en vez de cuadriláteros con un lado de largo cero.
+pub fn create_uv_sphere(radius: f32, stacks: usize, slices: usize) -> Vec<Vertex> {
+    let (stacks, slices) = (stacks.max(2), slices.max(3));
+    let vertex = |stack: f32, slice: f32| {
+        let uv = Vec2::new(slice / slices as f32, stack / stacks as f32);
+        let direction = lat_long_to_dir(PI / 2.0 - uv.y * PI, uv.x * 2.0 * PI - PI);
+        sphere_vertex(direction, radius, uv)
+    };
+
+    let mut vertices = Vec::with_capacity(slices * (stacks - 1) * 6);
+    for slice in 0..slices {
+        let (west, east) = (slice as f32, (slice + 1) as f32);
+        let middle = slice as f32 + 0.5;
+        for stack in 0..stacks {
+            let (north, south) = (stack as f32, (stack + 1) as f32);
+            if stack == 0 {
+                vertices.extend([vertex(south, west), vertex(north, middle), vertex(south, east)]);
+            } else if stack == stacks - 1 {
+                vertices.extend([vertex(south, middle), vertex(north, west), vertex(north, east)]);
+            } else {
+                vertices.extend([vertex(south, west), vertex(north, west), vertex(south, east)]);
+                vertices.extend([vertex(north, west), vertex(north, east), vertex(south, east)]);
+            }
+        }
+    }
+    vertices
+}
+
+// Icosaedro con cada cara partida en cuatro `subdivisions` veces y los vértices llevados a la
+// esfera: triángulos casi iguales en toda la superficie, sin amontonarse en los polos. Como la salida
+// es una lista de triángulos cada uno tiene sus vértices y la costura se arregla cara por cara.
+pub fn create_icosphere(radius: f32, subdivisions: u32) -> Vec<Vertex> {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let corners = [
+        Vec3::new(-1.0, t, 0.0), Vec3::new(1.0, t, 0.0), Vec3::new(-1.0, -t, 0.0), Vec3::new(1.0, -t, 0.0),
+        Vec3::new(0.0, -1.0, t), Vec3::new(0.0, 1.0, t), Vec3::new(0.0, -1.0, -t), Vec3::new(0.0, 1.0, -t),
+        Vec3::new(t, 0.0, -1.0), Vec3::new(t, 0.0, 1.0), Vec3::new(-t, 0.0, -1.0), Vec3::new(-t, 0.0, 1.0),
+    ]
+    .map(|corner| corner.normalize());
+    const FACES: [[usize; 3]; 20] = [
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    let mut triangles: Vec<[Vec3; 3]> = FACES.iter().map(|&[a, b, c]| [corners[a], corners[b], corners[c]]).collect();
+    for _ in 0..subdivisions {
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = ((a + b).normalize(), (b + c).normalize(), (c + a).normalize());
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut vertices = Vec::with_capacity(triangles.len() * 3);
+    for triangle in &triangles {
+        let mut uvs = triangle.map(|direction| sphere_uv(&direction));
+        let pole = triangle.map(|direction| direction.x.abs() < 1e-6 && direction.z.abs() < 1e-6);
+        // Una cara que cruza la costura tiene u cerca de 1 y cerca de 0: se lleva la parte de 0 más
+        // allá de 1 y la textura, que se repite, la muestrea de corrido
+        let (low, high) = (0..3)
+            .filter(|&index| !pole[index])
+            .fold((f32::MAX, f32::MIN), |(low, high), index| (low.min(uvs[index].x), high.max(uvs[index].x)));
+        if high - low > 0.5 {
+            uvs.iter_mut().filter(|uv| uv.x < 0.5).for_each(|uv| uv.x += 1.0);
+        }
+        // En el polo la longitud no está definida: toma la de los otros dos vértices de la cara
+        for index in (0..3).filter(|&index| pole[index]) {
+            uvs[index].x = (uvs[(index + 1) % 3].x + uvs[(index + 2) % 3].x) / 2.0;
+        }
+        vertices.extend((0..3).map(|index| sphere_vertex(triangle[index], radius, uvs[index])));
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meshes() -> Vec<(&'static str, Vec<Vertex>)> {
+        vec![
+            ("uv", create_uv_sphere(SPHERE_RADIUS, 12, 24)),
+            ("ico", create_icosphere(SPHERE_RADIUS, 3)),
+        ]
+    }
+
+    #[test]
+    fn normals_are_unit_and_point_outward() {
+        for (name, vertices) in meshes() {
+            for vertex in &vertices {
+                assert!((vertex.normal.magnitude() - 1.0).abs() < 1e-5, "{}: normal {:?}", name, vertex.normal);
+                assert!((vertex.position.magnitude() - SPHERE_RADIUS).abs() < 1e-5, "{}: posición {:?}", name, vertex.position);
+                assert!(vertex.normal.dot(&vertex.position) > 0.0, "{}: normal hacia adentro", name);
+            }
+        }
+    }
+
+    #[test]
+    fn every_triangle_winds_the_same_way() {
+        for (name, vertices) in meshes() {
+            assert_eq!(vertices.len() % 3, 0);
+            let sides: Vec<f32> = vertices
+                .chunks(3)
+                .map(|t| (t[1].position - t[0].position).cross(&(t[2].position - t[0].position)).dot(&(t[0].position + t[1].position + t[2].position)))
+                .collect();
+            // Ningún triángulo degenerado (los polos son abanicos) y todos del mismo lado
+            assert!(sides.iter().all(|side| side.abs() > 1e-9), "{}: triángulo sin área", name);
+            assert!(sides.iter().all(|side| side.signum() == sides[0].signum()), "{}: orientación mezclada", name);
+        }
+    }
+
+    #[test]
+    fn uvs_cover_the_unit_square() {
+        let range = |values: &mut dyn Iterator<Item = f32>| values.fold((f32::MAX, f32::MIN), |(low, high), v| (low.min(v), high.max(v)));
+
+        // La UV llega justo a los bordes: la costura duplicada tiene u = 1 y los polos v = 0 y 1
+        let uv = create_uv_sphere(SPHERE_RADIUS, 12, 24);
+        assert_eq!(range(&mut uv.iter().map(|v| v.tex_coords.x)), (0.0, 1.0));
+        assert_eq!(range(&mut uv.iter().map(|v| v.tex_coords.y)), (0.0, 1.0));
+
+        // En la icoesfera, la costura deja u un poco más allá de 1 en las caras que la cruzan
+        let ico = create_icosphere(SPHERE_RADIUS, 3);
+        let (low, high) = range(&mut ico.iter().map(|v| v.tex_coords.x));
+        assert!((0.0..0.05).contains(&low) && (0.95..1.5).contains(&high), "u en [{}, {}]", low, high);
+        let (low, high) = range(&mut ico.iter().map(|v| v.tex_coords.y));
+        assert!((0.0..0.05).contains(&low) && (0.95..=1.0).contains(&high), "v en [{}, {}]", low, high);
+        // Ninguna cara se estira a lo ancho de la textura
+        for t in ico.chunks(3) {
+            let (low, high) = range(&mut t.iter().map(|v| v.tex_coords.x));
+            assert!(high - low < 0.5);
+        }
+    }
+}
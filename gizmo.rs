@@ -0,0 +1,179 @@
+use nalgebra_glm::{Vec2, Vec3, Vec4, mat4_to_mat3};
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::line::line;
+use crate::vertex::Vertex;
+use crate::Uniforms;
+
+const AXIS_LENGTH: f32 = 1.0;
+const GRID_RADII: [f32; 4] = [1.0, 2.0, 3.0, 4.0];
+const GRID_SEGMENTS: usize = 64;
+const GRID_SPOKES: usize = 12;
+const CORNER_SIZE: f32 = 40.0;
+const CORNER_MARGIN: f32 = 60.0;
+
+const AXIS_COLORS: [Color; 3] = [
+    Color::new(230, 60, 60),  // X rojo
+    Color::new(60, 210, 60),  // Y verde
+    Color::new(70, 110, 240), // Z azul
+];
+const GRID_COLOR: Color = Color::new(80, 80, 115);
+
+// Ejes del mundo, rejilla del plano de la eclíptica (XZ) y ejes de orientación en la esquina.
+// Los ejes y la rejilla usan el z-buffer; las etiquetas y el gizmo de la esquina se dibujan encima.
+pub struct Gizmo {
+    pub enabled: bool,
+    pub show_grid: bool,
+}
+
+impl Gizmo {
+    pub fn new() -> Self {
+        Gizmo {
+            enabled: false,
+            show_grid: true,
+        }
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.show_grid {
+            draw_grid(framebuffer, uniforms);
+        }
+        draw_world_axes(framebuffer, uniforms);
+        draw_corner_axes(framebuffer, uniforms);
+    }
+}
+
+fn draw_grid(framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+    for &radius in &GRID_RADII {
+        for i in 0..GRID_SEGMENTS {
+            let a = i as f32 / GRID_SEGMENTS as f32 * 2.0 * PI;
+            let b = (i + 1) as f32 / GRID_SEGMENTS as f32 * 2.0 * PI;
+            let start = Vec3::new(radius * a.cos(), 0.0, radius * a.sin());
+            let end = Vec3::new(radius * b.cos(), 0.0, radius * b.sin());
+            world_segment(framebuffer, uniforms, &start, &end, GRID_COLOR);
+        }
+    }
+
+    let outer_radius = GRID_RADII[GRID_RADII.len() - 1];
+    for i in 0..GRID_SPOKES {
+        let angle = i as f32 / GRID_SPOKES as f32 * 2.0 * PI;
+        let direction = Vec3::new(angle.cos(), 0.0, angle.sin());
+        // Los rayos se subdividen para que cada tramo se proyecte sin problemas cerca de la cámara
+        for j in 0..8 {
+            let start = direction * (outer_radius * j as f32 / 8.0);
+            let end = direction * (outer_radius * (j + 1) as f32 / 8.0);
+            world_segment(framebuffer, uniforms, &start, &end, GRID_COLOR);
+        }
+    }
+}
+
+fn draw_world_axes(framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+    let axes = [Vec3::x(), Vec3::y(), Vec3::z()];
+    let origin = Vec3::zeros();
+
+    for (i, axis) in axes.iter().enumerate() {
+        let color = AXIS_COLORS[i];
+        let tip = axis * AXIS_LENGTH;
+        world_segment(framebuffer, uniforms, &origin, &tip, color);
+
+        // Punta de flecha en el plano que forma el eje con el siguiente
+        let side = axes[(i + 1) % 3] * (AXIS_LENGTH * 0.05);
+        let back = tip - axis * (AXIS_LENGTH * 0.15);
+        world_segment(framebuffer, uniforms, &tip, &(back + side), color);
+        world_segment(framebuffer, uniforms, &tip, &(back - side), color);
+
+        if let Some(screen) = project(&(tip * 1.1), uniforms) {
+            draw_label(framebuffer, Vec2::new(screen.x, screen.y), i, color);
+        }
+    }
+}
+
+fn draw_corner_axes(framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+    let rotation = mat4_to_mat3(&uniforms.view_matrix);
+    let center = Vec2::new(CORNER_MARGIN, framebuffer.height as f32 - CORNER_MARGIN);
+
+    // Primero los ejes que se alejan de la cámara, para que los cercanos queden encima
+    let mut axes: Vec<(usize, Vec3)> = [Vec3::x(), Vec3::y(), Vec3::z()]
+        .iter()
+        .enumerate()
+        .map(|(i, axis)| (i, rotation * axis))
+        .collect();
+    axes.sort_by(|a, b| a.1.z.total_cmp(&b.1.z));
+
+    for (i, direction) in axes {
+        let tip = center + Vec2::new(direction.x, -direction.y) * CORNER_SIZE;
+        screen_segment(framebuffer, center, tip, AXIS_COLORS[i]);
+        let label_offset = Vec2::new(direction.x, -direction.y) * 10.0;
+        draw_label(framebuffer, tip + label_offset, i, AXIS_COLORS[i]);
+    }
+}
+
+// Trazo de una letra: de un punto a otro, en unidades de media letra
+type Stroke = ((f32, f32), (f32, f32));
+
+// Letras X, Y, Z como trazos de 8 px centrados en `center`
+fn draw_label(framebuffer: &mut Framebuffer, center: Vec2, axis: usize, color: Color) {
+    let s = 4.0;
+    let strokes: &[Stroke] = match axis {
+        0 => &[((-1.0, -1.0), (1.0, 1.0)), ((-1.0, 1.0), (1.0, -1.0))],
+        1 => &[((-1.0, -1.0), (0.0, 0.0)), ((1.0, -1.0), (0.0, 0.0)), ((0.0, 0.0), (0.0, 1.0))],
+        _ => &[((-1.0, -1.0), (1.0, -1.0)), ((1.0, -1.0), (-1.0, 1.0)), ((-1.0, 1.0), (1.0, 1.0))],
+    };
+
+    for &((x0, y0), (x1, y1)) in strokes {
+        screen_segment(framebuffer, center + Vec2::new(x0, y0) * s, center + Vec2::new(x1, y1) * s, color);
+    }
+}
+
+pub fn project(point: &Vec3, uniforms: &Uniforms) -> Option<Vec3> {
+    let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(point.x, point.y, point.z, 1.0);
+    if clip.w <= 1e-4 {
+        return None;
+    }
+    let screen = uniforms.viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    Some(Vec3::new(screen.x, screen.y, screen.z))
+}
+
+fn is_reasonable(point: &Vec3, framebuffer: &Framebuffer) -> bool {
+    let limit = 4.0 * framebuffer.width.max(framebuffer.height) as f32;
+    point.x.abs() < limit && point.y.abs() < limit
+}
+
+// Segmento en espacio de mundo con prueba de profundidad
+pub fn world_segment(framebuffer: &mut Framebuffer, uniforms: &Uniforms, start: &Vec3, end: &Vec3, color: Color) {
+    let (a, b) = match (project(start, uniforms), project(end, uniforms)) {
+        (Some(a), Some(b)) if is_reasonable(&a, framebuffer) && is_reasonable(&b, framebuffer) => (a, b),
+        _ => return,
+    };
+
+    for fragment in line(&line_vertex(*start, a, color), &line_vertex(*end, b, color)) {
+        if fragment.position.x >= 0.0 && fragment.position.y >= 0.0 {
+            framebuffer.set_current_color(fragment.color.to_hex());
+            framebuffer.point(fragment.position.x as usize, fragment.position.y as usize, fragment.depth);
+        }
+    }
+}
+
+// Segmento en espacio de pantalla dibujado encima de todo
+fn screen_segment(framebuffer: &mut Framebuffer, start: Vec2, end: Vec2, color: Color) {
+    let a = Vec3::new(start.x, start.y, 0.0);
+    let b = Vec3::new(end.x, end.y, 0.0);
+
+    for fragment in line(&line_vertex(a, a, color), &line_vertex(b, b, color)) {
+        if fragment.position.x >= 0.0 && fragment.position.y >= 0.0 {
+            framebuffer.set_current_color(fragment.color.to_hex());
+            framebuffer.overlay_point(fragment.position.x as usize, fragment.position.y as usize);
+        }
+    }
+}
+
+fn line_vertex(position: Vec3, screen: Vec3, color: Color) -> Vertex {
+    let mut vertex = Vertex::new_with_color(position, color);
+    vertex.set_transformed(screen, Vec3::new(0.0, 0.0, 1.0));
+    vertex
+}
@@ -0,0 +1,123 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::framebuffer::Framebuffer;
+use crate::Uniforms;
+
+// Profundidad usada para los píxeles de fondo (sin geometría)
+pub const BACKGROUND_DEPTH: f32 = 1000.0;
+
+// Imagen reducida 2x2 que comparten las pasadas caras (profundidad de campo, desenfoque radial):
+// se trabaja sobre un cuarto de los píxeles y se vuelve con `upsample`, que no mezcla a través
+// de los bordes de profundidad.
+pub struct HalfResolution {
+    pub width: usize,
+    pub height: usize,
+    // Color promedio de cada bloque
+    pub color: Vec<Vec3>,
+    // Profundidad más cercana del bloque
+    pub depth: Vec<f32>,
+    // Píxel de resolución completa del que salió esa profundidad
+    pub nearest: Vec<usize>,
+}
+
+// Profundidad lineal (distancia en espacio de vista) de cada píxel; `None` sin proyección invertible
+pub fn linear_depth(framebuffer: &Framebuffer, uniforms: &Uniforms) -> Option<Vec<f32>> {
+    let inverse_projection = uniforms.projection_matrix.try_inverse()?;
+    Some(framebuffer.zbuffer.iter().map(|&z| {
+        if !z.is_finite() {
+            return BACKGROUND_DEPTH;
+        }
+        let view = inverse_projection * Vec4::new(0.0, 0.0, z, 1.0);
+        (-view.z / view.w).clamp(0.01, BACKGROUND_DEPTH)
+    }).collect())
+}
+
+impl HalfResolution {
+    // Reducción 2x2: color promedio y profundidad más cercana
+    pub fn downsample(framebuffer: &Framebuffer, depth: &[f32]) -> Self {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let half_width = width.div_ceil(2);
+        let half_height = height.div_ceil(2);
+        let mut half = HalfResolution {
+            width: half_width,
+            height: half_height,
+            color: vec![Vec3::zeros(); half_width * half_height],
+            depth: vec![BACKGROUND_DEPTH; half_width * half_height],
+            nearest: vec![0; half_width * half_height],
+        };
+
+        for hy in 0..half_height {
+            for hx in 0..half_width {
+                let mut color = Vec3::zeros();
+                let mut count = 0.0;
+                let half_index = hy * half_width + hx;
+                for (x, y) in [(2 * hx, 2 * hy), (2 * hx + 1, 2 * hy), (2 * hx, 2 * hy + 1), (2 * hx + 1, 2 * hy + 1)] {
+                    if x >= width || y >= height {
+                        continue;
+                    }
+                    let index = y * width + x;
+                    color += unpack(framebuffer.buffer[index]);
+                    count += 1.0;
+                    if depth[index] <= half.depth[half_index] {
+                        half.depth[half_index] = depth[index];
+                        half.nearest[half_index] = index;
+                    }
+                }
+                half.color[half_index] = color / count;
+            }
+        }
+        half
+    }
+
+    // Reescalado bilateral de `filtered` (un color por píxel reducido) sobre el framebuffer: pesos
+    // bilineales modulados por la similitud de profundidad, mezclado con el original según `amount`
+    // de cada píxel (0 lo deja igual)
+    pub fn upsample(&self, filtered: &[Vec3], framebuffer: &mut Framebuffer, depth: &[f32], amount: impl Fn(usize) -> f32) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                let amount = amount(index);
+                if amount <= 0.0 {
+                    continue;
+                }
+
+                let fx = (x as f32 - 0.5) * 0.5;
+                let fy = (y as f32 - 0.5) * 0.5;
+                let x0 = fx.floor().max(0.0) as usize;
+                let y0 = fy.floor().max(0.0) as usize;
+                let tx = (fx - x0 as f32).clamp(0.0, 1.0);
+                let ty = (fy - y0 as f32).clamp(0.0, 1.0);
+
+                let mut sum = Vec3::zeros();
+                let mut weight = 0.0;
+                for (sx, sy, bilinear) in [
+                    (x0, y0, (1.0 - tx) * (1.0 - ty)),
+                    (x0 + 1, y0, tx * (1.0 - ty)),
+                    (x0, y0 + 1, (1.0 - tx) * ty),
+                    (x0 + 1, y0 + 1, tx * ty),
+                ] {
+                    let sx = sx.min(self.width - 1);
+                    let sy = sy.min(self.height - 1);
+                    let sample = sy * self.width + sx;
+                    let similarity = 1.0 / (1.0 + (self.depth[sample] - depth[index]).abs() / (0.05 * depth[index]));
+                    let w = bilinear * similarity + 1e-5;
+                    sum += filtered[sample] * w;
+                    weight += w;
+                }
+
+                let original = unpack(framebuffer.buffer[index]);
+                let result = original + (sum / weight - original) * amount;
+                framebuffer.buffer[index] = pack(&result);
+            }
+        }
+    }
+}
+
+pub fn unpack(color: u32) -> Vec3 {
+    Vec3::new(((color >> 16) & 0xFF) as f32, ((color >> 8) & 0xFF) as f32, (color & 0xFF) as f32)
+}
+
+pub fn pack(color: &Vec3) -> u32 {
+    let channel = |c: f32| c.round().clamp(0.0, 255.0) as u32;
+    (channel(color.x) << 16) | (channel(color.y) << 8) | channel(color.z)
+}
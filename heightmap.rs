@@ -0,0 +1,98 @@
+use crate::noise::NoiseSource;
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::cli::key_values;
+use crate::image_io::{save_png_gray16, save_png_rgb};
+use crate::plates::PlateField;
+use crate::spherical::lat_long_to_dir;
+use crate::terrain::earth_elevation;
+
+pub struct HeightmapOptions {
+    pub entity: String,
+    pub size: usize,
+    pub output: String,
+    pub normal_output: Option<String>,
+    pub time: f32,
+}
+
+impl HeightmapOptions {
+    // `--export-heightmap entity=earth size=1024 out=height.png [normals=normal.png] [time=0]`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--export-heightmap")?;
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+
+        Some(HeightmapOptions {
+            entity: value("entity").unwrap_or_else(|| "earth".to_string()),
+            size: value("size").and_then(|v| v.parse().ok()).filter(|&s| s >= 2).unwrap_or(1024),
+            output: value("out").unwrap_or_else(|| "height.png".to_string()),
+            normal_output: value("normals"),
+            time: value("time").and_then(|v| v.parse().ok()).unwrap_or(0.0),
+        })
+    }
+}
+
+// Evalúa la elevación sobre una malla equirectangular (ancho = size, alto = size / 2)
+pub fn export_heightmap(options: &HeightmapOptions, noise: &dyn NoiseSource, radius: f32, plates: Option<&PlateField>) -> Result<(), String> {
+    let elevation: fn(&dyn NoiseSource, &Vec3, f32, Option<&PlateField>) -> f32 = match options.entity.as_str() {
+        "earth" => earth_elevation,
+        other => return Err(format!("La entidad '{}' no tiene función de terreno", other)),
+    };
+
+    let width = options.size;
+    let height = (options.size / 2).max(1);
+
+    let heights: Vec<f32> = equirect_positions(width, height, radius)
+        .map(|position| elevation(noise, &position, options.time, plates).clamp(-1.0, 1.0))
+        .collect();
+
+    let values: Vec<u16> = heights
+        .iter()
+        .map(|h| ((h * 0.5 + 0.5) * u16::MAX as f32).round() as u16)
+        .collect();
+    save_png_gray16(&options.output, width, height, &values).map_err(|e| e.to_string())?;
+
+    if let Some(normal_output) = &options.normal_output {
+        let normals = derive_normal_map(&heights, width, height);
+        save_png_rgb(normal_output, width, height, &normals).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// Posiciones sobre la esfera en el centro de cada píxel de una malla equirectangular, fila por fila
+// desde el polo norte; la columna 0 empieza en longitud -PI
+pub fn equirect_positions(width: usize, height: usize, radius: f32) -> impl Iterator<Item = Vec3> {
+    (0..height).flat_map(move |row| {
+        let latitude = PI / 2.0 - (row as f32 + 0.5) / height as f32 * PI;
+        (0..width).map(move |column| {
+            let longitude = (column as f32 + 0.5) / width as f32 * 2.0 * PI - PI;
+            lat_long_to_dir(latitude, longitude) * radius
+        })
+    })
+}
+
+// Mapa de normales en espacio tangente por diferencias centrales.
+// La longitud da la vuelta en el borde; la latitud se sujeta en los polos.
+fn derive_normal_map(heights: &[f32], width: usize, height: usize) -> Vec<u32> {
+    let strength = width as f32 / 64.0;
+    let sample = |x: usize, y: usize| heights[y * width + x];
+
+    let mut normals = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let up = y.saturating_sub(1);
+        let down = (y + 1).min(height - 1);
+        for x in 0..width {
+            let left = (x + width - 1) % width;
+            let right = (x + 1) % width;
+
+            let dx = (sample(right, y) - sample(left, y)) * strength;
+            let dy = (sample(x, down) - sample(x, up)) * strength;
+            let normal = Vec3::new(-dx, -dy, 1.0).normalize();
+
+            let encode = |c: f32| ((c * 0.5 + 0.5) * 255.0).round() as u32;
+            normals.push((encode(normal.x) << 16) | (encode(normal.y) << 8) | encode(normal.z));
+        }
+    }
+
+    normals
+}
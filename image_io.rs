@@ -0,0 +1,83 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+// Guarda un buffer de píxeles 0xRRGGBB (filas de arriba hacia abajo) como PNG RGB de 8 bits
+pub fn save_png_rgb(path: &str, width: usize, height: usize, pixels: &[u32]) -> Result<(), png::EncodingError> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut data = Vec::with_capacity(width * height * 3);
+    for &pixel in &pixels[..width * height] {
+        data.push(((pixel >> 16) & 0xFF) as u8);
+        data.push(((pixel >> 8) & 0xFF) as u8);
+        data.push((pixel & 0xFF) as u8);
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)
+}
+
+
+// Guarda valores de 16 bits como PNG en escala de grises (big-endian, como exige el formato)
+pub fn save_png_gray16(path: &str, width: usize, height: usize, values: &[u16]) -> Result<(), png::EncodingError> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Sixteen);
+
+    let mut data = Vec::with_capacity(width * height * 2);
+    for &value in &values[..width * height] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)
+}
+
+
+// Carga un PNG como píxeles 0xRRGGBB; se normaliza a 8 bits por canal y se descarta el alfa
+pub fn load_png_rgb(path: &str) -> Result<(usize, usize, Vec<u32>), png::DecodingError> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data)?;
+    let channels = info.color_type.samples();
+
+    let pixels = data[..info.buffer_size()]
+        .chunks(channels)
+        .map(|p| match channels {
+            1 | 2 => ((p[0] as u32) << 16) | ((p[0] as u32) << 8) | p[0] as u32,
+            _ => ((p[0] as u32) << 16) | ((p[1] as u32) << 8) | p[2] as u32,
+        })
+        .collect();
+
+    Ok((info.width as usize, info.height as usize, pixels))
+}
+
+
+// Como `load_png_rgb` pero conserva el alfa: cada píxel es [r, g, b, a]; sin canal alfa vale 255
+pub fn load_png_rgba(path: &str) -> Result<(usize, usize, Vec<[u8; 4]>), png::DecodingError> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data)?;
+    let channels = info.color_type.samples();
+
+    let pixels = data[..info.buffer_size()]
+        .chunks(channels)
+        .map(|p| match channels {
+            1 => [p[0], p[0], p[0], 255],
+            2 => [p[0], p[0], p[0], p[1]],
+            3 => [p[0], p[1], p[2], 255],
+            _ => [p[0], p[1], p[2], p[3]],
+        })
+        .collect();
+
+    Ok((info.width as usize, info.height as usize, pixels))
+}
@@ -0,0 +1,74 @@
+use std::ops::Range;
+use crate::cli::arg_value;
+use crate::framebuffer::Framebuffer;
+use crate::shaders::smoothstep;
+
+// Franjas de cine: barras arriba y abajo que entran suavemente hasta dejar la relación de aspecto
+// pedida. Se dibujan al final, encima de la escena y de los overlays, sin cambiar el framebuffer.
+pub struct CinematicBars {
+    pub enabled: bool,
+    pub aspect: f32,
+    pub color: u32,
+    pub ease_frames: u32,
+    // Avance de la animación en [0, 1]: 0 sin barras, 1 barras completas
+    progress: f32,
+}
+
+impl CinematicBars {
+    // `--bars 2.39` las deja activas desde el inicio; `--bars-color 000000` y `--bars-ease 30` (cuadros)
+    pub fn from_args(args: &[String]) -> Self {
+        let aspect = arg_value(args, "--bars")
+            .and_then(|value| value.parse().ok())
+            .filter(|&aspect: &f32| aspect > 0.0)
+            .unwrap_or(2.39);
+        let color = arg_value(args, "--bars-color")
+            .and_then(|hex| u32::from_str_radix(hex.trim_start_matches('#'), 16).ok())
+            .unwrap_or(0x000000);
+        let ease_frames = arg_value(args, "--bars-ease")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(30);
+        let enabled = arg_value(args, "--bars").is_some();
+
+        CinematicBars {
+            enabled,
+            aspect,
+            color,
+            ease_frames,
+            progress: if enabled { 1.0 } else { 0.0 },
+        }
+    }
+
+    // Un cuadro de animación hacia el estado actual
+    pub fn update(&mut self) {
+        let target = if self.enabled { 1.0 } else { 0.0 };
+        let step = 1.0 / self.ease_frames.max(1) as f32;
+        self.progress = if self.progress < target {
+            (self.progress + step).min(target)
+        } else {
+            (self.progress - step).max(target)
+        };
+    }
+
+    // Alto de cada barra en píxeles; 0 si la imagen ya es más ancha que el aspecto pedido
+    pub fn bar_height(&self, width: usize, height: usize) -> usize {
+        let visible = width as f32 / self.aspect;
+        let full = ((height as f32 - visible) / 2.0).max(0.0);
+        let eased = smoothstep(0.0, 1.0, self.progress);
+        (full * eased).round() as usize
+    }
+
+    // Filas que quedan entre las barras: las únicas en que el tramado reparte error
+    pub fn visible_rows(&self, width: usize, height: usize) -> Range<usize> {
+        let bar = self.bar_height(width, height);
+        bar..height.saturating_sub(bar)
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer) {
+        let bar = self.bar_height(framebuffer.width, framebuffer.height);
+        if bar == 0 {
+            return;
+        }
+        framebuffer.fill_rect(0, 0, framebuffer.width, bar, self.color);
+        framebuffer.fill_rect(0, framebuffer.height - bar, framebuffer.width, bar, self.color);
+    }
+}
@@ -0,0 +1,81 @@
+use nalgebra_glm::{Vec3, rotate_vec3};
+use crate::animation::params;
+use crate::cli::key_values;
+use crate::color::Color;
+
+// Luz puntual de la escena, en espacio de mundo. Los coeficientes son los del modelo de Phong:
+// ambiente constante, difusa de Lambert y especular de Blinn-Phong con `shininess` como exponente.
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+    pub position: Vec3,
+    pub color: Color,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    // Radianes por segundo que la luz gira alrededor del eje Y, como un sol que da la vuelta al planeta
+    pub orbit_speed: f32,
+    // Tamaño de la fuente: cuanto más grande, más ancha la penumbra de los eclipses (ver `eclipse`)
+    pub radius: f32,
+}
+
+impl Default for Light {
+    // Lejos sobre +Z: ilumina el planeta de frente como la luz fija que había antes
+    fn default() -> Self {
+        Light {
+            position: Vec3::new(0.0, 0.0, 20.0),
+            color: Color::new(255, 255, 255),
+            // En luz lineal: con la codificación gamma de pantalla ya levanta bastante la cara de noche
+            ambient: 0.01,
+            diffuse: 1.0,
+            specular: 0.25,
+            shininess: 32.0,
+            orbit_speed: 0.0,
+            radius: 1.0,
+        }
+    }
+}
+
+impl Light {
+    // `--light position=0,0,20 color=ffffff ambient=0.01 diffuse=1 specular=0.25 shininess=32 orbit=0.005 radius=1`
+    pub fn from_args(args: &[String]) -> Self {
+        let defaults = Light::default();
+        let Some(pairs) = key_values(args, "--light") else {
+            return defaults;
+        };
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let number = |key: &str, default: f32| value(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+
+        let position = value("position")
+            .and_then(|text| {
+                let coords: Vec<f32> = text.split(',').filter_map(|c| c.trim().parse().ok()).collect();
+                (coords.len() == 3).then(|| Vec3::new(coords[0], coords[1], coords[2]))
+            })
+            .unwrap_or(defaults.position);
+        let color = value("color")
+            .and_then(|hex| u32::from_str_radix(hex.trim_start_matches('#'), 16).ok())
+            .map(Color::from_hex)
+            .unwrap_or(defaults.color);
+
+        Light {
+            position,
+            color,
+            ambient: number("ambient", defaults.ambient).max(0.0),
+            diffuse: number("diffuse", defaults.diffuse).max(0.0),
+            specular: number("specular", defaults.specular).max(0.0),
+            shininess: number("shininess", defaults.shininess).max(1.0),
+            orbit_speed: number("orbit", defaults.orbit_speed),
+            radius: number("radius", defaults.radius).max(0.0),
+        }
+    }
+
+    // La luz en un instante de la simulación; como los relojes locales, se recalcula y no se acumula
+    pub fn at(&self, sim_time: f32) -> Light {
+        Light {
+            position: rotate_vec3(&self.position, self.orbit_speed * sim_time, &Vec3::y()),
+            ..*self
+        }
+    }
+}
+
+params!(Light { ambient, diffuse, specular, shininess, orbit_speed, radius } colors { color });
\ No newline at end of file
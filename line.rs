@@ -1,6 +1,5 @@
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
-use crate::color::Color;
 
 pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
     let mut fragments = Vec::new();
@@ -21,9 +20,16 @@ pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
 
     let mut err = if dx > dy { dx / 2 } else { -dy / 2 };
 
+    // La profundidad se interpola por pasos a lo largo del eje dominante, así también funciona en líneas verticales
+    let steps = dx.max(dy).max(1) as f32;
+    let mut step = 0;
+
     loop {
-        let z = start.z + (end.z - start.z) * (x0 - start.x as i32) as f32 / (end.x - start.x) as f32;
-        fragments.push(Fragment::new(x0 as f32, y0 as f32, Color::new(255, 255, 255), z));
+        let t = step as f32 / steps;
+        let z = start.z + (end.z - start.z) * t;
+        let color = a.color.lerp(&b.color, t);
+        let position = a.position + (b.position - a.position) * t;
+        fragments.push(Fragment::new(x0 as f32, y0 as f32, color, z, a.normal, 1.0, position));
 
         if x0 == x1 && y0 == y1 { break; }
 
@@ -36,7 +42,8 @@ pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
             err += dx;
             y0 += sy;
         }
+        step += 1;
     }
 
     fragments
-}
\ No newline at end of file
+}
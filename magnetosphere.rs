@@ -0,0 +1,88 @@
+use nalgebra_glm::Vec3;
+
+// Forma del frente de choque: una cónica con el planeta en el foco. Con excentricidad cerca de 1 el
+// lado de la estrella queda comprimido a `standoff` y el lado de atrás se abre en una cola larga.
+const ECCENTRICITY: f32 = 0.8;
+// Radios del planeta hasta la nariz del frente, del lado de la estrella
+pub const STANDOFF_RADII: f32 = 3.0;
+// Qué fracción del frente hay que atravesar para salir despedido en dirección opuesta al planeta
+const FULL_PUSH_DEPTH: f32 = 0.25;
+
+// Campo magnético de un planeta visto por el viento solar: dentro del frente las partículas pierden
+// la velocidad hacia el planeta y se las empuja hacia afuera, así se abre un hueco delante de él
+#[derive(Clone, Copy, Debug)]
+pub struct Magnetosphere {
+    pub center: Vec3,
+    pub standoff: f32,
+    // Hacia la estrella, desde el planeta
+    sunward: Vec3,
+}
+
+impl Magnetosphere {
+    // `None` si el planeta está sobre la estrella y no hay de qué lado comprimir el frente
+    pub fn facing(center: Vec3, standoff: f32, star: Vec3) -> Option<Self> {
+        let sunward = (star - center).try_normalize(1e-6)?;
+        Some(Magnetosphere { center, standoff, sunward })
+    }
+
+    // Distancia al frente desde el centro en la dirección de `offset`: `standoff` hacia la estrella,
+    // el doble casi de costado y cada vez más lejos hacia atrás
+    pub fn boundary(&self, offset: &Vec3) -> f32 {
+        let cos_angle = offset.try_normalize(1e-6).map_or(1.0, |direction| direction.dot(&self.sunward));
+        self.standoff * (1.0 + ECCENTRICITY) / (1.0 + ECCENTRICITY * cos_angle)
+    }
+
+    // Velocidad de una partícula en `position` después de pasar por el campo; fuera del frente no cambia.
+    // Conserva la rapidez: solo gira la dirección, más cuanto más adentro está.
+    pub fn deflect(&self, position: &Vec3, velocity: Vec3) -> Vec3 {
+        let offset = position - self.center;
+        let distance = offset.magnitude();
+        let boundary = self.boundary(&offset);
+        if distance >= boundary {
+            return velocity;
+        }
+
+        let normal = offset.try_normalize(1e-6).unwrap_or(self.sunward);
+        let speed = velocity.magnitude();
+        let inward = velocity.dot(&normal).min(0.0);
+        let push = ((1.0 - distance / boundary) / FULL_PUSH_DEPTH).min(1.0);
+        let deflected = velocity - normal * inward + normal * (speed * push);
+        deflected.try_normalize(1e-6).unwrap_or(normal) * speed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn earth() -> Magnetosphere {
+        Magnetosphere::facing(Vec3::new(3.0, 0.0, 0.0), 0.5, Vec3::zeros()).unwrap()
+    }
+
+    #[test]
+    fn sunward_side_is_compressed() {
+        let field = earth();
+        let nose = field.boundary(&Vec3::new(-1.0, 0.0, 0.0));
+        let flank = field.boundary(&Vec3::new(0.0, 0.0, 1.0));
+        let tail = field.boundary(&Vec3::new(1.0, 0.0, 0.0));
+        assert!((nose - 0.5).abs() < 1e-5, "nariz en {}", nose);
+        assert!(nose < flank && flank < tail, "nariz {}, costado {}, cola {}", nose, flank, tail);
+    }
+
+    #[test]
+    fn wind_inside_the_front_turns_away_from_the_planet() {
+        let field = earth();
+        let velocity = Vec3::new(1.0, 0.0, 0.1);
+        let deflected = field.deflect(&Vec3::new(2.7, 0.0, 0.05), velocity);
+        let normal = (Vec3::new(2.7, 0.0, 0.05) - field.center).normalize();
+        assert!(deflected.dot(&normal) > 0.0, "sigue hacia el planeta: {:?}", deflected);
+        assert!((deflected.magnitude() - velocity.magnitude()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn wind_outside_the_front_is_untouched() {
+        let field = earth();
+        let velocity = Vec3::new(1.0, 0.0, 0.0);
+        assert_eq!(field.deflect(&Vec3::new(2.0, 0.0, 0.0), velocity), velocity);
+    }
+}
@@ -1,7 +1,12 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+// Con alguna feature apagada quedan sin usar partes del núcleo que solo llaman los módulos opcionales
+#![cfg_attr(not(all(feature = "post", feature = "io", feature = "overlay")), allow(dead_code))]
+
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat4, look_at, perspective};
+use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
+use std::time::{Duration, Instant};
 use std::f32::consts::PI;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 
 mod framebuffer;
 mod triangle;
@@ -11,13 +16,155 @@ mod color;
 mod fragment;
 mod shaders;
 mod camera;
+mod clock;
+mod cli;
+#[cfg(feature = "io")]
+mod image_io;
+#[cfg(feature = "io")]
+mod slitscan;
+mod terrain;
+#[cfg(feature = "io")]
+mod heightmap;
+mod worley;
+mod spherical;
+mod post;
+#[cfg(feature = "post")]
+mod motion_blur;
+#[cfg(feature = "post")]
+mod dof;
+#[cfg(feature = "post")]
+mod half_resolution;
+#[cfg(feature = "post")]
+mod curves;
+mod shader_override;
+mod line;
+mod gizmo;
+mod retro;
+mod seed_browser;
+#[cfg(feature = "overlay")]
+mod overlay;
+mod bookmarks;
+#[cfg(feature = "overlay")]
+mod scopes;
+#[cfg(feature = "overlay")]
+mod decals;
+mod stream;
+mod magnetosphere;
+mod solar_wind;
+mod noise;
+#[cfg(feature = "post")]
+mod radial_blur;
+#[cfg(feature = "post")]
+mod bloom;
+mod stats;
+mod letterbox;
+#[cfg(feature = "io")]
+mod control_map;
+mod atmosphere;
+mod light;
+mod animation;
+mod orbits;
+mod environment;
+mod displacement;
+mod naming;
+mod materials;
+mod clouds;
+mod frame_graph;
+mod comet;
+mod solar_system;
+mod seasons;
+mod plates;
+mod culling;
+mod clipping;
+mod observer;
+#[cfg(feature = "io")]
+mod texture;
+mod readback;
+mod supersample;
+#[cfg(feature = "io")]
+mod capture;
+mod render_mode;
+mod shader_params;
+mod eclipse;
+mod fog;
+mod dither;
+mod geometry;
+mod shader_registry;
+mod toon;
 
 use framebuffer::Framebuffer;
 use vertex::Vertex;
+use fragment::Fragment;
+use color::Color;
 use obj::Obj;
 use camera::Camera;
+use clock::{Clock, FRAME_SECONDS};
 use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
+use shaders::{vertex_shader, fragment_shader, default_ripple_sources, CrystalMaterial, GasGiantPalette, RippleSource, ShaderKind, RING_INNER_RADIUS, RING_OUTER_RADIUS};
+#[cfg(feature = "io")]
+use slitscan::{SlitScan, SlitScanOptions};
+#[cfg(feature = "io")]
+use heightmap::{HeightmapOptions, export_heightmap};
+use post::PostChain;
+#[cfg(feature = "post")]
+use motion_blur::MotionBlur;
+#[cfg(feature = "post")]
+use dof::DepthOfField;
+#[cfg(feature = "post")]
+use radial_blur::RadialBlur;
+#[cfg(feature = "post")]
+use bloom::Bloom;
+use stats::{RenderStats, StatsConfig, StatsReport};
+use letterbox::CinematicBars;
+#[cfg(feature = "io")]
+use control_map::{ControlMap, ControlMapBakeOptions, bake_control_map};
+use atmosphere::Atmosphere;
+use light::Light;
+use animation::{Animations, ParamRoot, Params};
+use orbits::Barycenter;
+use environment::{starfield, surface_sky, Backdrop};
+use displacement::Displacement;
+use naming::{describe, BodyTraits, NameStyle, SceneNames};
+use materials::{BlendMode, Material, MaterialMap};
+use clouds::CloudShell;
+use seasons::Seasons;
+use plates::{PlateField, Plates};
+use culling::{faces_away, BoundingSphere, Mesh};
+use clipping::{clip_triangle, project, Clip};
+use observer::{surface_distance, Observer, Sky};
+#[cfg(feature = "io")]
+use texture::Texture;
+use readback::{DumpOptions, Readback};
+use supersample::Supersampling;
+#[cfg(feature = "io")]
+use capture::RecordOptions;
+use render_mode::{draw_wireframe, RenderMode};
+use shader_params::ShaderParams;
+use eclipse::Occluder;
+use fog::Fog;
+use dither::OutputDither;
+use geometry::{mean_radius, SphereMesh};
+#[cfg(feature = "io")]
+use geometry::SPHERE_RADIUS;
+use shader_registry::ShaderRegistry;
+use toon::ToonShader;
+use frame_graph::{SCENE_COLOR, SCENE_DEPTH, SCENE_EMISSION};
+use comet::{Comet, CometRide, RideStatus};
+use solar_system::SolarSystem;
+#[cfg(feature = "post")]
+use curves::ColorCurves;
+use shader_override::ShaderOverrideStack;
+use gizmo::Gizmo;
+use retro::{RetroMode, DitherMode};
+use seed_browser::SeedBrowser;
+#[cfg(feature = "overlay")]
+use scopes::Scopes;
+#[cfg(feature = "overlay")]
+use decals::Decal;
+use stream::{FrameStream, StreamOptions};
+use solar_wind::SolarWind;
+use noise::{NoiseSource, NoiseBackend, NoiseRegistry, GradientNoise};
+use bookmarks::{Bookmark, Bookmarks, BookmarkPanel, CameraTransition, SLOT_KEYS};
 use fastnoise_lite::{FastNoiseLite, NoiseType};
 
 pub struct Uniforms {
@@ -25,16 +172,650 @@ pub struct Uniforms {
     view_matrix: Mat4,
     projection_matrix: Mat4,
     viewport_matrix: Mat4,
-    time: u32,
-    noise: FastNoiseLite
+    camera_position: Vec3,
+    // Tiempo local del cuerpo que se dibuja (ver `LocalTime`), en segundos
+    time: f32,
+    noise: Box<dyn NoiseSource>,
+    // `--noise-config`: generadores propios de algunos shaders (ver `noise_for`)
+    noises: Vec<(ShaderKind, Box<dyn NoiseSource>)>,
+    ripple_sources: Vec<RippleSource>,
+    gas_giant: GasGiantPalette,
+    crystal: CrystalMaterial,
+    // Copia del color para los shaders que refractan; solo existe mientras se dibuja uno de ellos
+    backdrop: Option<Arc<Backdrop>>,
+    // `--displace`: relieve de vértices según el shader de cada cuerpo (ver `Displacement::resolve`)
+    displacement: Option<Displacement>,
+    #[cfg(feature = "overlay")]
+    decals: Arc<Vec<Decal>>,
+    #[cfg(feature = "io")]
+    control_map: Option<Arc<ControlMap>>,
+    // `--texture`: imagen para el shader `textured`
+    #[cfg(feature = "io")]
+    texture: Option<Arc<Texture>>,
+    light: Light,
+    // Exposición del mapeo de tonos con que se escribe cada fragmento (ver `Color::tone_map`)
+    exposure: f32,
+    // Hilos entre los que se reparte el sombreado de fragmentos (ver `shade_fragments`)
+    shading_threads: usize,
+    // Las nubes de la Tierra van en su propia capa (`--cloud-shell`) y no en el shader de superficie
+    cloud_shell: bool,
+    // `--seasons`: año de la Tierra que mueve la línea de nieve y la vegetación
+    seasons: Option<Seasons>,
+    // `--plates`: placas tectónicas de la Tierra, generadas con la semilla del ruido de este cuadro
+    plates: Option<PlateField>,
+    // `--starfield`: el fondo es el cielo estrellado (ver `draw_starfield`) y no un color plano
+    starfield: bool,
+    // Descarte de cuerpos fuera de la vista y de caras traseras; `--no-cull` lo apaga para comparar
+    culling: bool,
+    // Con el observador en la superficie: el sol y cuánto de día hay para el fondo (ver `draw_starfield`)
+    sky: Option<Sky>,
+    // Relleno, aristas o las dos cosas (ver `render_blended`)
+    render_mode: RenderMode,
+    // Colores y umbrales de los shaders procedurales (ver `ShaderParams`)
+    shader_params: ShaderParams,
+    // Esferas de los cuerpos del cuadro que dan sombra a los demás (ver `light_visibility`)
+    occluders: Vec<Occluder>,
+    // Niebla por distancia sobre los fragmentos de geometría (ver `shade_band`)
+    fog: Fog,
+    // Multiplica el tamaño del píxel con que los shaders deciden cuánto detalle de ruido sobrevive
+    // (ver `detail`): más alto suaviza antes, 0 muestrea siempre todo el detalle
+    lod_bias: f32,
+    // Segundos que avanzó la simulación desde el cuadro anterior (ver `Clock`); 0 en pausa
+    delta_time: f32,
+    // Tramado con que se redondea a 8 bits cada color que se escribe (ver `OutputDither`)
+    output_dither: OutputDither,
+}
+
+impl Uniforms {
+    // El generador configurado para `shader` o, si no tiene, el general del cuadro
+    fn noise_for(&self, shader: ShaderKind) -> &dyn NoiseSource {
+        self.noises.iter().find(|(kind, _)| *kind == shader).map_or(self.noise.as_ref(), |(_, noise)| noise.as_ref())
+    }
+}
+
+// Reloj propio de cada cuerpo para que dos con el mismo shader no se animen al unísono.
+// Se recalcula desde el tiempo de simulación en cada cuadro, nunca se acumula.
+#[derive(Clone, Copy)]
+struct LocalTime {
+    offset: f32,
+    scale: f32,
+}
+
+impl LocalTime {
+    // `--time-offset 2 --time-scale 0.5` (segundos); por defecto (0, 1), el tiempo de simulación tal cual
+    fn from_args(args: &[String]) -> Self {
+        let value = |flag: &str, default: f32| cli::arg_value(args, flag).and_then(|v| v.parse().ok()).unwrap_or(default);
+        LocalTime {
+            offset: value("--time-offset", 0.0),
+            scale: value("--time-scale", 1.0),
+        }
+    }
+
+    fn at(&self, sim_time: f32) -> f32 {
+        self.offset + self.scale * sim_time
+    }
+}
+
+// Un cuerpo a dibujar: su malla, el shader que le corresponde y su reloj
+struct Model {
+    vertices: Vec<Vertex>,
+    shader: ShaderKind,
+    time: LocalTime,
+    // Radio medio de la malla, para efectos que tratan al cuerpo como una esfera
+    radius: f32,
+    // Los mismos triángulos agrupados por material del .obj (ver `render_model`)
+    batches: Vec<(Option<String>, Vec<Vertex>)>,
+    bounds: BoundingSphere,
+    double_sided: bool,
+}
+
+impl Model {
+    fn load(path: &str, shader: ShaderKind, time: LocalTime) -> Self {
+        let obj = Obj::load(path).unwrap_or_else(|error| panic!("Failed to load {}: {:?}", path, error));
+        let vertices = obj.get_vertex_array();
+        Model {
+            radius: mean_radius(&vertices),
+            bounds: BoundingSphere::of(&vertices),
+            vertices,
+            shader,
+            time,
+            batches: obj.get_material_batches(),
+            double_sided: false,
+        }
+    }
+
+    // Malla armada en el programa (ver `geometry`): un solo grupo sin material, como un .obj sin `usemtl`
+    fn from_vertices(vertices: Vec<Vertex>, shader: ShaderKind, time: LocalTime) -> Self {
+        Model {
+            radius: mean_radius(&vertices),
+            bounds: BoundingSphere::of(&vertices),
+            batches: vec![(None, vertices.clone())],
+            vertices,
+            shader,
+            time,
+            double_sided: false,
+        }
+    }
+
+    fn mesh(&self) -> Mesh<'_> {
+        Mesh { vertices: &self.vertices, bounds: self.bounds, double_sided: self.double_sided }
+    }
+}
+
+// Un modelo con materiales asignados se dibuja por grupos, cada uno con su shader y su mezcla:
+// primero los opacos, después los transparentes y al final los aditivos, que no escriben profundidad. Los grupos sin entrada
+// en el mapa usan el shader del modelo. Sin ningún material asignado es un solo `render`, como siempre.
+fn render_model(framebuffer: &mut Framebuffer, uniforms: &Uniforms, entity: &str, model: &Model, shader: ShaderKind, materials: &MaterialMap, resolve: &dyn Fn(ShaderKind) -> ShaderKind) -> RenderStats {
+    if !model.batches.iter().any(|(name, _)| materials.get(name.as_deref()).is_some()) {
+        return render(framebuffer, uniforms, entity, model.mesh(), shader);
+    }
+
+    let mut stats = RenderStats::default();
+    for pass in 0..=BlendMode::Additive.draw_order() {
+        for (name, vertices) in &model.batches {
+            let material = materials
+                .get(name.as_deref())
+                .map(|material| Material { shader: resolve(material.shader), ..material })
+                .unwrap_or(Material::opaque(shader));
+            if material.blend.draw_order() == pass {
+                stats.add(&render_blended(framebuffer, uniforms, entity, Mesh { vertices, ..model.mesh() }, material));
+            }
+        }
+    }
+    stats
+}
+
+// Inclinación de los anillos respecto al ecuador del planeta (la de Saturno)
+const RING_TILT: f32 = 0.47;
+const RING_SEGMENTS: usize = 128;
+
+// Anillo plano en el plano XZ, centrado en el origen y con normales hacia +Y.
+// La coordenada de textura u va de 0 en el borde interior a 1 en el exterior; v da la vuelta.
+fn create_ring(inner_radius: f32, outer_radius: f32, segments: usize) -> Vec<Vertex> {
+    let segments = segments.max(3);
+    let normal = Vec3::new(0.0, 1.0, 0.0);
+    let vertex = |radial: f32, index: usize| {
+        let radius = inner_radius + (outer_radius - inner_radius) * radial;
+        let around = index as f32 / segments as f32;
+        let angle = around * 2.0 * PI;
+        Vertex::new(Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin()), normal, Vec2::new(radial, around))
+    };
+
+    let mut vertices = Vec::with_capacity(segments * 6);
+    for index in 0..segments {
+        let (inner_a, inner_b) = (vertex(0.0, index), vertex(0.0, index + 1));
+        let (outer_a, outer_b) = (vertex(1.0, index), vertex(1.0, index + 1));
+        vertices.extend([inner_a.clone(), outer_a, outer_b.clone(), inner_a, outer_b, inner_b]);
+    }
+    vertices
+}
+
+// Anillos de un planeta: la malla va en radios del planeta y comparte su reloj
+fn ring_model(planet: &Model) -> Model {
+    let vertices = create_ring(RING_INNER_RADIUS, RING_OUTER_RADIUS, RING_SEGMENTS);
+    Model {
+        bounds: BoundingSphere::of(&vertices),
+        vertices,
+        shader: ShaderKind::Ring,
+        time: planet.time,
+        radius: RING_OUTER_RADIUS * planet.radius,
+        batches: Vec::new(),
+        double_sided: true,
+    }
+}
+
+// Los anillos usan la matriz del planeta, escalada a su radio e inclinada. Se dibujan después del
+// planeta: la prueba de profundidad esconde la parte de atrás y los huecos dejan verlo.
+fn render_rings(framebuffer: &mut Framebuffer, uniforms: &mut Uniforms, rings: &Model, planet_radius: f32, shader: ShaderKind) -> RenderStats {
+    let planet_matrix = uniforms.model_matrix;
+    uniforms.model_matrix = planet_matrix * create_model_matrix(Vec3::zeros(), planet_radius, Vec3::new(RING_TILT, 0.0, 0.0));
+    let stats = render(framebuffer, uniforms, "rings", rings.mesh(), shader);
+    uniforms.model_matrix = planet_matrix;
+    stats
+}
+
+// El secundario del planeta doble va en el lado opuesto del baricentro, escalado según su masa
+fn render_companion(framebuffer: &mut Framebuffer, uniforms: &mut Uniforms, companion: &Model, barycenter: &Barycenter, sim_time: f32, shader: ShaderKind) -> RenderStats {
+    let planet_matrix = uniforms.model_matrix;
+    let (_, position) = barycenter.positions(sim_time);
+    uniforms.model_matrix = create_model_matrix(position, barycenter.secondary_scale(), Vec3::zeros());
+    let stats = render(framebuffer, uniforms, "companion", companion.mesh(), shader);
+    uniforms.model_matrix = planet_matrix;
+    stats
+}
+
+// Cada cuerpo del sistema con su propia matriz de modelo y su shader; el sol va primero
+fn draw_solar_system(framebuffer: &mut Framebuffer, uniforms: &mut Uniforms, system: &SolarSystem, sphere: &Model, resolve: &dyn Fn(ShaderKind) -> ShaderKind) -> Vec<(&'static str, ShaderKind, RenderStats)> {
+    let previous_matrix = uniforms.model_matrix;
+    let mut drawn = Vec::new();
+    for (object, matrix) in system.objects.iter().zip(system.model_matrices(uniforms.time)) {
+        let shader = resolve(object.shader);
+        uniforms.model_matrix = matrix;
+        drawn.push((object.name, shader, render(framebuffer, uniforms, object.name, sphere.mesh(), shader)));
+    }
+    uniforms.model_matrix = previous_matrix;
+    drawn
+}
+
+fn render_comet(framebuffer: &mut Framebuffer, uniforms: &mut Uniforms, model: &Model, comet: &Comet, sim_time: f32, shader: ShaderKind) -> RenderStats {
+    let planet_matrix = uniforms.model_matrix;
+    let (position, _) = comet.state(sim_time);
+    uniforms.model_matrix = create_model_matrix(position, comet.size, Vec3::zeros());
+    let stats = render(framebuffer, uniforms, "comet", model.mesh(), shader);
+    uniforms.model_matrix = planet_matrix;
+    stats
+}
+
+// Los cuerpos que se dibujan en cada cuadro
+struct Bodies<'a> {
+    planet: &'a Model,
+    rings: &'a Model,
+    companion: Option<&'a Model>,
+    comet: Option<&'a Model>,
+    // Esfera con la que se dibujan los cuerpos de `--solar-system`
+    system: Option<&'a Model>,
+}
+
+// Planeta con su atmósfera, anillos y compañero, cada uno con el shader que devuelve `resolve`.
+// Un planeta que refracta mira a través de su superficie, así que va después de lo opaco y con una
+// copia del color ya dibujado. Devuelve las estadísticas de cada cuerpo, el planeta primero.
+fn draw_bodies(framebuffer: &mut Framebuffer, uniforms: &mut Uniforms, scene: &SceneSetup, bodies: &Bodies, sim_time: f32, resolve: &dyn Fn(ShaderKind) -> ShaderKind) -> Vec<(&'static str, ShaderKind, RenderStats)> {
+    // Las vistas de depuración del modo de render mandan también sobre la pila de "N"
+    let resolve = &|shader| scene.render_mode.debug_shader().unwrap_or_else(|| resolve(shader));
+    let planet_shader = resolve(bodies.planet.shader);
+    let refracts = planet_shader.reads_backdrop();
+    let mut drawn = Vec::new();
+    uniforms.occluders = occluders(uniforms, scene, bodies, sim_time);
+
+    if scene.starfield || uniforms.sky.is_some() {
+        draw_starfield(framebuffer, uniforms);
+    }
+    if let (Some(system), Some(sphere)) = (&scene.solar_system, bodies.system) {
+        return draw_solar_system(framebuffer, uniforms, system, sphere, resolve);
+    }
+    if !refracts {
+        drawn.push(("planet", planet_shader, render_planet(framebuffer, uniforms, scene, bodies.planet, planet_shader, resolve)));
+    }
+    if scene.rings {
+        let shader = resolve(bodies.rings.shader);
+        drawn.push(("rings", shader, render_rings(framebuffer, uniforms, bodies.rings, bodies.planet.radius, shader)));
+    }
+    if let (Some(barycenter), Some(companion)) = (&scene.double_planet, bodies.companion) {
+        let shader = resolve(companion.shader);
+        drawn.push(("companion", shader, render_companion(framebuffer, uniforms, companion, barycenter, sim_time, shader)));
+        barycenter.draw_orbits(framebuffer, uniforms, sim_time);
+    }
+    if let (Some(comet), Some(model)) = (&scene.comet, bodies.comet) {
+        let shader = resolve(model.shader);
+        drawn.push(("comet", shader, render_comet(framebuffer, uniforms, model, comet, sim_time, shader)));
+    }
+    if refracts {
+        uniforms.backdrop = Some(Arc::new(Backdrop::capture(framebuffer)));
+        drawn.insert(0, ("planet", planet_shader, render_planet(framebuffer, uniforms, scene, bodies.planet, planet_shader, resolve)));
+        uniforms.backdrop = None;
+    }
+    // Lo transparente va al final, sobre todo lo opaco ya dibujado
+    if let Some(shell) = scene.cloud_shell.as_ref().filter(|_| planet_shader == ShaderKind::EarthClouds) {
+        drawn.push(("clouds", ShaderKind::CloudLayer, render_cloud_shell(framebuffer, uniforms, bodies.planet, shell)));
+    }
+    drawn
+}
+
+// Los cuerpos esféricos del cuadro, donde están en este instante, para los eclipses. Los anillos y
+// la capa de nubes no dan sombra.
+fn occluders(uniforms: &Uniforms, scene: &SceneSetup, bodies: &Bodies, sim_time: f32) -> Vec<Occluder> {
+    if let (Some(system), Some(sphere)) = (&scene.solar_system, bodies.system) {
+        return system.model_matrices(uniforms.time).iter().map(|matrix| Occluder::of(matrix, sphere.radius)).collect();
+    }
+    let mut occluders = vec![Occluder::of(&uniforms.model_matrix, bodies.planet.radius)];
+    if let (Some(barycenter), Some(companion)) = (&scene.double_planet, bodies.companion) {
+        let (_, position) = barycenter.positions(sim_time);
+        occluders.push(Occluder::of(&create_model_matrix(position, barycenter.secondary_scale(), Vec3::zeros()), companion.radius));
+    }
+    if let (Some(comet), Some(model)) = (&scene.comet, bodies.comet) {
+        let (position, _) = comet.state(sim_time);
+        occluders.push(Occluder::of(&create_model_matrix(position, comet.size, Vec3::zeros()), model.radius));
+    }
+    occluders
+}
+
+// Fondo antes que los cuerpos: cada píxel toma la dirección de su rayo de vista, reconstruida con la
+// inversa de viewport, proyección y vista. La traslación de la cámara se cancela al restar los puntos
+// cercano y lejano, así que las estrellas giran con ella pero no tienen paralaje. Desde la superficie
+// se suma el sol y las estrellas se apagan de día (ver `surface_sky`).
+fn draw_starfield(framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+    let Some(inverse) = (uniforms.viewport_matrix * uniforms.projection_matrix * uniforms.view_matrix).try_inverse() else {
+        return;
+    };
+    let width = framebuffer.width;
+    let threads = uniforms.shading_threads.clamp(1, framebuffer.height.max(1));
+    let rows_per_band = framebuffer.height.div_ceil(threads).max(1);
+
+    let sky_band = |colors: &mut [u32], first_row: usize| {
+        for (index, pixel) in colors.iter_mut().enumerate() {
+            let (column, row) = (index % width, first_row + index / width);
+            let (x, y) = (column as f32 + 0.5, row as f32 + 0.5);
+            let near = inverse * Vec4::new(x, y, -1.0, 1.0);
+            let far = inverse * Vec4::new(x, y, 1.0, 1.0);
+            let direction = far.xyz() / far.w - near.xyz() / near.w;
+            let night = starfield(&direction, uniforms.noise.as_ref());
+            *pixel = match &uniforms.sky {
+                Some(sky) => surface_sky(&direction.normalize(), night, sky.sun.as_ref(), sky.glare),
+                None => night,
+            }
+            .to_hex_dithered(uniforms.output_dither.offset(column, row));
+        }
+    };
+    if threads == 1 {
+        sky_band(&mut framebuffer.buffer, 0);
+        return;
+    }
+    let sky_band = &sky_band;
+    std::thread::scope(|scope| {
+        for (band, colors) in framebuffer.buffer.chunks_mut(rows_per_band * width).enumerate() {
+            scope.spawn(move || sky_band(colors, band * rows_per_band));
+        }
+    });
+}
+
+// La capa de nubes usa la malla y la matriz del planeta, agrandada y girada a su propio ritmo
+fn render_cloud_shell(framebuffer: &mut Framebuffer, uniforms: &mut Uniforms, planet: &Model, shell: &CloudShell) -> RenderStats {
+    let planet_matrix = uniforms.model_matrix;
+    let rotation = Vec3::new(0.0, shell.speed * uniforms.time, 0.0);
+    uniforms.model_matrix = planet_matrix * create_model_matrix(Vec3::zeros(), 1.0 + shell.height, rotation);
+    let material = Material { shader: ShaderKind::CloudLayer, blend: BlendMode::Alpha { write_depth: shell.write_depth } };
+    let stats = render_blended(framebuffer, uniforms, "clouds", planet.mesh(), material);
+    uniforms.model_matrix = planet_matrix;
+    stats
+}
+
+fn render_planet(framebuffer: &mut Framebuffer, uniforms: &Uniforms, scene: &SceneSetup, planet: &Model, shader: ShaderKind, resolve: &dyn Fn(ShaderKind) -> ShaderKind) -> RenderStats {
+    let stats = render_model(framebuffer, uniforms, "planet", planet, shader, &scene.materials, resolve);
+    scene.atmosphere.draw(framebuffer, uniforms, planet.radius);
+    stats
+}
+
+// Lo que se configura una vez desde la línea de comandos y comparten todos los modos de render
+struct SceneSetup {
+    noise_backend: NoiseBackend,
+    noise_configs: NoiseRegistry,
+    planet_time: LocalTime,
+    atmosphere: Atmosphere,
+    light: Light,
+    animations: Animations,
+    // `--rings`: el planeta lleva anillos ("R" los alterna en la ventana)
+    rings: bool,
+    // `--double-planet`: el planeta y un compañero orbitan un baricentro común
+    double_planet: Option<Barycenter>,
+    crystal: CrystalMaterial,
+    displacement: Option<Displacement>,
+    // `--exposure 1.5`
+    exposure: f32,
+    // `--lod-bias 1`
+    lod_bias: f32,
+    // `--output-dither blue`
+    output_dither: OutputDither,
+    // `--capture-dither`: con "diffusion" (o sin la opción) lo que se guarda o se transmite se
+    // redondea con difusión de error al resolver el supermuestreo; sin supermuestreo cada fragmento
+    // ya se redondea al escribirse y no queda resto que difundir
+    capture_diffusion: bool,
+    shading_threads: usize,
+    cloud_shell: Option<CloudShell>,
+    seasons: Option<Seasons>,
+    plates: Option<Plates>,
+    starfield: bool,
+    culling: bool,
+    // `--comet`: un cometa en órbita excéntrica; "Tab" monta la cámara en él
+    comet: Option<Comet>,
+    // `--solar-system`: se dibuja el sistema en vez del planeta y sus acompañantes
+    solar_system: Option<SolarSystem>,
+    // `--dump-buffers`: cuadro y carpeta donde volcar los buffers intermedios
+    dump: Option<DumpOptions>,
+    // `--observer`: la cámara parada en la superficie del planeta ("/" lo alterna en la ventana)
+    observer: Observer,
+    name_style: NameStyle,
+    names: SceneNames,
+    // `--model`: malla .obj del planeta, por defecto la esfera de `--sphere`; `--materials` asigna
+    // shaders a sus materiales
+    model_path: Option<String>,
+    sphere: SphereMesh,
+    materials: MaterialMap,
+    #[cfg(feature = "overlay")]
+    decals: Arc<Vec<Decal>>,
+    #[cfg(feature = "io")]
+    control_map: Option<Arc<ControlMap>>,
+    #[cfg(feature = "io")]
+    texture: Option<Arc<Texture>>,
+    // `--supersample 2|4`: muestras por eje del antialiasing (F3 lo cambia en la ventana)
+    supersample: usize,
+    // `--render-mode`: aristas y vistas de depuración para todos los cuerpos (F4 los recorre)
+    render_mode: RenderMode,
+    // `--shader-params look.toml`: archivo del que salen `shader_params` (F5 lo vuelve a leer)
+    shader_params_path: Option<String>,
+    shader_params: ShaderParams,
+    // `--fog`: los cuerpos lejanos se funden con el color de la niebla (F6 la alterna en la ventana)
+    fog: Fog,
+}
+
+impl SceneSetup {
+    // Relee `--shader-params`; si el archivo ya no se puede leer quedan los valores que había
+    fn reload_shader_params(&mut self) {
+        let Some(path) = &self.shader_params_path else {
+            eprintln!("No hay archivo de parámetros de shaders (--shader-params)");
+            return;
+        };
+        match ShaderParams::load(path) {
+            Ok(params) => {
+                self.shader_params = params;
+                eprintln!("Parámetros de shaders recargados desde {}", path);
+            }
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    // Vuelve a nombrar los cuerpos a partir de la semilla maestra
+    fn rename(&mut self, seed: i32) {
+        self.names = SceneNames::generate(seed, self.name_style, self.double_planet.is_some());
+    }
+
+    fn describe_planet(&self, planet: &Model, shader: ShaderKind) -> String {
+        describe(&BodyTraits {
+            shader,
+            radius: planet.radius,
+            orbit_distance: self.double_planet.as_ref().map(|barycenter| barycenter.orbit_radius).filter(|radius| *radius > 0.0),
+            rings: self.rings,
+            atmosphere: self.atmosphere.enabled,
+        })
+    }
+
+    fn describe_companion(&self, companion: &Model) -> Option<String> {
+        let barycenter = self.double_planet.as_ref()?;
+        Some(describe(&BodyTraits {
+            shader: companion.shader,
+            radius: companion.radius * barycenter.secondary_scale(),
+            orbit_distance: Some(barycenter.separation),
+            rings: false,
+            atmosphere: false,
+        }))
+    }
+
+    // Nombres y descripciones de los cuerpos en el informe de estadísticas
+    fn label_report(&self, report: &mut StatsReport, planet: &Model, shader: ShaderKind, companion: Option<&Model>) {
+        report.label("planet", &self.names.planet, self.describe_planet(planet, shader));
+        if let (Some(name), Some(companion)) = (&self.names.companion, companion) {
+            if let Some(description) = self.describe_companion(companion) {
+                report.label("companion", name, description);
+            }
+        }
+    }
+
+    // Comparte las calcomanías y el mapa de control con los uniformes de un cuadro y
+    // coloca la luz (y el planeta, si es doble) donde están en ese instante de la simulación
+    fn attach(&self, uniforms: &mut Uniforms, sim_time: f32) {
+        #[cfg(feature = "overlay")]
+        {
+            uniforms.decals = Arc::clone(&self.decals);
+        }
+        #[cfg(feature = "io")]
+        {
+            uniforms.control_map = self.control_map.clone();
+            uniforms.texture = self.texture.clone();
+        }
+        uniforms.light = self.light.at(sim_time);
+        uniforms.crystal = self.crystal;
+        uniforms.displacement = self.displacement;
+        uniforms.exposure = self.exposure;
+        uniforms.lod_bias = self.lod_bias;
+        uniforms.output_dither = self.output_dither;
+        uniforms.shading_threads = self.shading_threads;
+        uniforms.cloud_shell = self.cloud_shell.is_some();
+        uniforms.seasons = self.seasons;
+        uniforms.plates = self.plates.map(|plates| plates.generate(uniforms.noise.as_ref()));
+        uniforms.starfield = self.starfield;
+        uniforms.culling = self.culling;
+        uniforms.render_mode = self.render_mode;
+        uniforms.shader_params = self.shader_params;
+        uniforms.fog = self.fog;
+        uniforms.noises = self.noise_configs.build(uniforms.noise.seed());
+        // Con el observador en la superficie el planeta gira sobre su eje, así hay días y noches
+        if self.double_planet.is_some() || self.observer.enabled {
+            let primary = self.double_planet.as_ref().map_or(Vec3::zeros(), |barycenter| barycenter.positions(sim_time).0);
+            let spin = if self.observer.enabled { self.observer.spin(sim_time) } else { 0.0 };
+            uniforms.model_matrix = create_model_matrix(primary, 1.0, Vec3::new(0.0, spin, 0.0));
+        }
+    }
+
+    // Sube la cámara al observador sobre su cuerpo tal como `attach` lo dejó en este cuadro y vuelve a
+    // apuntar los uniformes con ella. En `--solar-system` el cuerpo es el de `body=`, dibujado con `sphere`.
+    fn observe(&self, camera: &mut Camera, uniforms: &mut Uniforms, planet: &Model, sphere: Option<&Model>, window: (usize, usize)) {
+        let observer = &self.observer;
+        let host = self.solar_system.as_ref().zip(sphere).map(|(system, sphere)| {
+            let index = system.objects.iter().position(|object| object.name == observer.body).unwrap_or(0);
+            (system.model_matrices(uniforms.time)[index], sphere, system.objects[index].shader)
+        });
+        let (matrix, model, shader) = host.unwrap_or((uniforms.model_matrix, planet, planet.shader));
+
+        // El suelo es el de la malla más el relieve si lo hay, así el observador no queda enterrado
+        let direction = observer.direction();
+        let mesh_ground = surface_distance(&model.vertices, &direction).unwrap_or(model.radius);
+        let ground = uniforms.displacement.and_then(|displacement| displacement.resolve(shader)).map_or(mesh_ground, |params| {
+            let surface = direction * mesh_ground;
+            params.displace(uniforms.noise_for(shader), uniforms.plates.as_ref(), &surface, &direction, uniforms.time).0.magnitude()
+        });
+        observer.place(camera, &matrix, model.radius, ground);
+
+        uniforms.view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
+        uniforms.projection_matrix = create_perspective_matrix(window.0 as f32, window.1 as f32, camera.near);
+        uniforms.camera_position = camera.eye;
+        let air = self.atmosphere.enabled && self.solar_system.is_none();
+        uniforms.sky = Some(observer.sky(camera, &uniforms.light.position, self.solar_system.is_none(), air));
+    }
+
+    fn planet_model(&self, shader: ShaderKind) -> Model {
+        match &self.model_path {
+            Some(path) => Model::load(path, shader, self.planet_time),
+            None => self.sphere_model(shader),
+        }
+    }
+
+    // La esfera de todos los demás cuerpos, aunque el planeta venga de un .obj
+    fn sphere_model(&self, shader: ShaderKind) -> Model {
+        Model::from_vertices(self.sphere.vertices(), shader, self.planet_time)
+    }
+
+    // El compañero del planeta doble, si lo hay: la misma esfera con el shader de luna
+    fn companion(&self) -> Option<Model> {
+        self.double_planet.as_ref().map(|_| self.sphere_model(ShaderKind::Moon))
+    }
+
+    fn dumps_frame(&self, frame: u32) -> bool {
+        self.dump.as_ref().is_some_and(|dump| dump.frame == Some(frame))
+    }
+
+    fn save_dump(&self, readback: &Readback, frame: u32) {
+        let dir = self.dump.as_ref().map_or("dump", |dump| dump.dir.as_str());
+        match readback.save(dir, frame) {
+            Ok(folder) => eprintln!("Buffers del cuadro {} volcados en {}", frame, folder),
+            Err(error) => eprintln!("Error al volcar los buffers: {}", error),
+        }
+    }
+
+    fn system_model(&self) -> Option<Model> {
+        self.solar_system.as_ref().map(|_| self.sphere_model(ShaderKind::Sun))
+    }
+
+    fn comet_model(&self) -> Option<Model> {
+        self.comet.as_ref().map(|_| self.sphere_model(ShaderKind::Moon))
+    }
+
+    // Próximo cuerpo que sobrevuela el cometa: su nombre y dónde está en `time`
+    fn flyby_target(&self, comet: &Comet, time: f32) -> (String, Vec3) {
+        let primary = |t: f32| self.double_planet.as_ref().map_or(Vec3::zeros(), |barycenter| barycenter.positions(t).0);
+        let secondary = |t: f32| self.double_planet.as_ref().map_or(Vec3::zeros(), |barycenter| barycenter.positions(t).1);
+        let mut names = vec![self.names.planet.clone()];
+        let mut bodies: Vec<&dyn Fn(f32) -> Vec3> = vec![&primary];
+        if let (Some(_), Some(name)) = (&self.double_planet, &self.names.companion) {
+            names.push(name.clone());
+            bodies.push(&secondary);
+        }
+        let index = comet.next_flyby(time, &bodies).unwrap_or(0);
+        (names.swap_remove(index), bodies[index](time))
+    }
+
+    fn ride_pose(&self, ride: &CometRide, comet: &Comet, time: f32) -> (Vec3, Vec3) {
+        let (position, velocity) = comet.state(time);
+        let (_, target) = self.flyby_target(comet, time);
+        ride.chase_pose(&position, &velocity, &target)
+    }
+
+    fn ride_status(&self, comet: &Comet, time: f32) -> RideStatus {
+        let (position, velocity) = comet.state(time);
+        let (target, target_position) = self.flyby_target(comet, time);
+        RideStatus { speed: velocity.magnitude(), target, distance: (target_position - position).magnitude() }
+    }
+
+    // Avanza las curvas de `--animate` al tiempo de simulación dado
+    fn tick(&mut self, sim_time: f32) {
+        let animations = std::mem::take(&mut self.animations);
+        animations.apply(self, sim_time);
+        self.animations = animations;
+    }
 }
 
-fn create_noise() -> FastNoiseLite {
-    create_cloud_noise()
+impl ParamRoot for SceneSetup {
+    fn group(&mut self, name: &str) -> Option<&mut dyn Params> {
+        match name {
+            "light" => Some(&mut self.light),
+            "atmosphere" => Some(&mut self.atmosphere),
+            "fog" => Some(&mut self.fog),
+            "crystal" => Some(&mut self.crystal),
+            "clouds" => self.cloud_shell.as_mut().map(|shell| shell as &mut dyn Params),
+            "seasons" => self.seasons.as_mut().map(|seasons| seasons as &mut dyn Params),
+            "plates" => self.plates.as_mut().map(|plates| plates as &mut dyn Params),
+            // "earth.cloud_threshold", "sun.spot_threshold"...
+            _ => self.shader_params.group(name),
+        }
+    }
 }
 
-fn create_cloud_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
+// Semilla maestra del ruido; el explorador de semillas deriva las demás de esta
+const DEFAULT_SEED: i32 = 1337;
+// Cuadros que tarda la cámara en subirse al cometa o en bajarse
+const RIDE_TRANSITION_FRAMES: u32 = 45;
+// Con 1.5 una superficie blanca de frente a la luz queda cerca del blanco de pantalla
+const DEFAULT_EXPOSURE: f32 = 1.5;
+// Con 1 el detalle se apaga cuando un rasgo del ruido ya no cubre un par de píxeles
+const DEFAULT_LOD_BIAS: f32 = 1.0;
+
+fn create_noise(backend: NoiseBackend, seed: i32) -> Box<dyn NoiseSource> {
+    match backend {
+        NoiseBackend::FastNoise => Box::new(create_cloud_noise(seed)),
+        NoiseBackend::Gradient => Box::new(GradientNoise::new(seed)),
+    }
+}
+
+fn create_cloud_noise(seed: i32) -> FastNoiseLite {
+    let mut noise = FastNoiseLite::with_seed(seed);
     noise.set_noise_type(Some(NoiseType::OpenSimplex2));
     noise
 }
@@ -81,13 +862,12 @@ fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
     look_at(&eye, &center, &up)
 }
 
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
+fn create_perspective_matrix(window_width: f32, window_height: f32, near: f32) -> Mat4 {
     let fov = 45.0 * PI / 180.0;
     let aspect_ratio = window_width / window_height;
-    let near = 0.1;
     let far = 1000.0;
 
-    perspective(fov, aspect_ratio, near, far)
+    perspective(aspect_ratio, fov, near, far)
 }
 
 fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
@@ -99,6 +879,63 @@ fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
     )
 }
 
+fn create_uniforms(camera: &Camera, window_width: usize, window_height: usize, framebuffer_width: usize, framebuffer_height: usize, time: f32, noise: Box<dyn NoiseSource>) -> Uniforms {
+    let model_matrix = create_model_matrix(Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0));
+    let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
+    let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32, camera.near);
+    let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
+
+    Uniforms {
+        model_matrix,
+        view_matrix,
+        projection_matrix,
+        viewport_matrix,
+        camera_position: camera.eye,
+        time,
+        noise,
+        ripple_sources: default_ripple_sources(),
+        gas_giant: GasGiantPalette::default(),
+        crystal: CrystalMaterial::default(),
+        backdrop: None,
+        displacement: None,
+        #[cfg(feature = "overlay")]
+        decals: Arc::new(Vec::new()),
+        #[cfg(feature = "io")]
+        control_map: None,
+        #[cfg(feature = "io")]
+        texture: None,
+        light: Light::default(),
+        exposure: DEFAULT_EXPOSURE,
+        shading_threads: available_threads(),
+        cloud_shell: false,
+        seasons: None,
+        plates: None,
+        starfield: false,
+        culling: true,
+        sky: None,
+        render_mode: RenderMode::Filled,
+        shader_params: ShaderParams::default(),
+        noises: Vec::new(),
+        occluders: Vec::new(),
+        fog: Fog::default(),
+        lod_bias: DEFAULT_LOD_BIAS,
+        delta_time: 0.0,
+        output_dither: OutputDither::Off,
+    }
+}
+
+// `--threads N` reparte el sombreado en N hilos; por defecto uno por núcleo, y 1 lo deja en serie
+fn shading_threads(args: &[String]) -> usize {
+    cli::arg_value(args, "--threads")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(available_threads)
+        .max(1)
+}
+
+fn available_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
 fn main() {
     let window_width = 800;
     let window_height = 600;
@@ -106,6 +943,177 @@ fn main() {
     let framebuffer_height = 600;
     let frame_delay = Duration::from_millis(16);
 
+    // Antes de leer los argumentos, que ya eligen shaders por nombre. Un shader de otro módulo se
+    // agrega aquí con `shaders.register(Box::new(MiShader))`.
+    let mut shaders = ShaderRegistry::builtin();
+    if let Err(error) = shaders.register(Box::new(ToonShader::default())).and_then(|_| shaders.install()) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+
+    let args: Vec<String> = std::env::args().collect();
+    let noise_backend = NoiseBackend::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let noise_configs = NoiseRegistry::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    // Los exportadores ven las mismas placas y el mismo ruido de la Tierra que la escena con la
+    // semilla por defecto
+    #[cfg(feature = "io")]
+    {
+        let export_plates = Plates::from_args(&args).map(|plates| plates.generate(create_noise(noise_backend, DEFAULT_SEED).as_ref()));
+        let export_noise = || -> Box<dyn NoiseSource> {
+            match noise_configs.get(ShaderKind::EarthClouds) {
+                Some(config) => config.build(DEFAULT_SEED),
+                None => create_noise(noise_backend, DEFAULT_SEED),
+            }
+        };
+        if let Some(options) = HeightmapOptions::from_args(&args) {
+            if let Err(error) = export_heightmap(&options, export_noise().as_ref(), SPHERE_RADIUS, export_plates.as_ref()) {
+                eprintln!("Error al exportar el heightmap: {}", error);
+                std::process::exit(1);
+            }
+            return;
+        }
+        if let Some(options) = ControlMapBakeOptions::from_args(&args) {
+            if let Err(error) = bake_control_map(&options, export_noise().as_ref(), SPHERE_RADIUS, export_plates.as_ref()) {
+                eprintln!("Error al hornear el mapa de control: {}", error);
+                std::process::exit(1);
+            }
+            println!("Mapa de control guardado en {}", options.output);
+            return;
+        }
+    }
+    // "--bookmark <nombre|ranura>" arranca desde un marcador guardado, también en los modos sin ventana
+    let mut bookmarks = Bookmarks::load(bookmarks::STATE_FILE);
+    let start_bookmark = cli::arg_value(&args, "--bookmark").map(|name| {
+        bookmarks.find(&name).cloned().unwrap_or_else(|| {
+            eprintln!("No existe el marcador '{}' en {}", name, bookmarks::STATE_FILE);
+            std::process::exit(1);
+        })
+    });
+    #[cfg(feature = "io")]
+    let control_map = ControlMap::from_args(&args).map(|control_map| {
+        control_map.map(Arc::new).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        })
+    });
+    #[cfg(feature = "io")]
+    let texture = Texture::from_args(&args).map(|texture| {
+        texture.map(Arc::new).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        })
+    });
+    let name_style = NameStyle::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let double_planet = Barycenter::from_args(&args);
+    let names = SceneNames::generate(DEFAULT_SEED, name_style, double_planet.is_some());
+    let materials = MaterialMap::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let supersample = supersample::factor_from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let sphere = SphereMesh::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let render_mode = RenderMode::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let output_dither = OutputDither::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let shader_params_path = cli::arg_value(&args, "--shader-params");
+    let shader_params = shader_params_path.as_deref().map_or(Ok(ShaderParams::default()), ShaderParams::load).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let mut scene = SceneSetup {
+        noise_backend,
+        noise_configs,
+        planet_time: LocalTime::from_args(&args),
+        atmosphere: Atmosphere::from_args(&args),
+        light: Light::from_args(&args),
+        animations: Animations::default(),
+        rings: args.iter().any(|arg| arg == "--rings"),
+        double_planet,
+        crystal: CrystalMaterial::from_args(&args),
+        displacement: Displacement::from_args(&args),
+        exposure: cli::arg_value(&args, "--exposure").and_then(|value| value.parse().ok()).filter(|exposure: &f32| *exposure > 0.0).unwrap_or(DEFAULT_EXPOSURE),
+        lod_bias: cli::arg_value(&args, "--lod-bias").and_then(|value| value.parse().ok()).filter(|bias: &f32| *bias >= 0.0).unwrap_or(DEFAULT_LOD_BIAS),
+        output_dither,
+        capture_diffusion: cli::arg_value(&args, "--capture-dither").is_none_or(|value| value == "diffusion"),
+        shading_threads: shading_threads(&args),
+        cloud_shell: CloudShell::from_args(&args),
+        seasons: Seasons::from_args(&args),
+        plates: Plates::from_args(&args),
+        starfield: args.iter().any(|arg| arg == "--starfield"),
+        culling: !args.iter().any(|arg| arg == "--no-cull"),
+        comet: Comet::from_args(&args),
+        solar_system: SolarSystem::from_args(&args),
+        dump: DumpOptions::from_args(&args),
+        observer: Observer::from_args(&args),
+        name_style,
+        names,
+        model_path: cli::arg_value(&args, "--model"),
+        sphere,
+        materials,
+        #[cfg(feature = "overlay")]
+        decals: Arc::new(Decal::from_args(&args)),
+        #[cfg(feature = "io")]
+        control_map,
+        #[cfg(feature = "io")]
+        texture,
+        supersample,
+        render_mode,
+        shader_params_path,
+        shader_params,
+        fog: Fog::from_args(&args),
+    };
+    scene.animations = Animations::from_args(&args, &mut scene).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    if let Some(system) = scene.solar_system.as_ref().filter(|_| scene.observer.enabled) {
+        if !system.objects.iter().any(|object| object.name == scene.observer.body) {
+            eprintln!("No hay un cuerpo '{}' en el sistema solar", scene.observer.body);
+            std::process::exit(1);
+        }
+    }
+    let stats_out = cli::arg_value(&args, "--stats-out");
+    #[cfg(feature = "io")]
+    if let Some(options) = SlitScanOptions::from_args(&args) {
+        run_slitscan(&options, framebuffer_width, framebuffer_height, start_bookmark.as_ref(), &mut scene, stats_out.as_deref());
+        return;
+    }
+    #[cfg(feature = "io")]
+    if let Some(options) = RecordOptions::from_args(&args) {
+        run_record(&options, framebuffer_width, framebuffer_height, start_bookmark.as_ref(), &mut scene, &mut build_post_chain(&args), &CinematicBars::from_args(&args));
+        return;
+    }
+    let stream_options = StreamOptions::from_args(&args).map(|options| {
+        options.unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        })
+    });
+    if let (Some(options), true) = (&stream_options, args.iter().any(|arg| arg == "--headless")) {
+        run_stream(options, framebuffer_width, framebuffer_height, start_bookmark.as_ref(), &mut scene, stats_out.as_deref(), &CinematicBars::from_args(&args));
+        return;
+    }
+
     let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
     let mut window = Window::new(
         "Shader Switcher",
@@ -116,55 +1124,494 @@ fn main() {
     .unwrap();
 
     window.set_position(500, 500);
+    window.set_title(&window_title(&scene.names.planet, None));
     window.update();
 
     framebuffer.set_background_color(0x333355);
 
-    // Parámetros del Sol o cualquier objeto
-    let mut current_shader = 0; // Shader inicial
     let mut camera = Camera::new(
         Vec3::new(0.0, 0.0, 5.0),
         Vec3::new(0.0, 0.0, 0.0),
         Vec3::new(0.0, 1.0, 0.0)
     );
+    let mut last_mouse = None;
 
-    let planet_obj = Obj::load("assets/models/sphere.obj").expect("Failed to load sphere.obj");
-    let planet_vertex_array = planet_obj.get_vertex_array();
-    let mut time = 0;
+    // El cuerpo arranca con el Sol; "S" cambia su shader
+    let mut planet = scene.planet_model(ShaderKind::Sun);
+    let rings = ring_model(&planet);
+    let companion = scene.companion();
+    let comet = scene.comet_model();
+    let system = scene.system_model();
+    let mut clock = Clock::real(0.0);
+    let mut time = clock.seconds();
+    let mut post_chain = build_post_chain(&args);
+    let mut shader_overrides = ShaderOverrideStack::new();
+    let mut gizmo = Gizmo::new();
+    let mut retro = RetroMode::from_args(&args);
+    let mut supersampling = Supersampling::new(scene.supersample);
+    let mut noise_seed = DEFAULT_SEED;
+    let mut seed_browser = SeedBrowser::new(framebuffer_width, framebuffer_height);
+    #[cfg(feature = "overlay")]
+    let mut scopes = Scopes::new();
+    let mut solar_wind = SolarWind::new();
+    let mut bookmark_panel = BookmarkPanel::new();
+    let mut bars = CinematicBars::from_args(&args);
+    let mut camera_transition: Option<CameraTransition> = None;
+    let mut ride = CometRide::new();
+    // Vista a la que vuelve la cámara al bajarse del cometa o al dejar la superficie
+    let mut ride_return: Option<Bookmark> = None;
+    let mut observer_return: Option<Bookmark> = None;
+    // Contadores de cada cuerpo del último cuadro dibujado y su resolución y shader, para la tecla "I"
+    let mut last_drawn: Vec<(&'static str, ShaderKind, RenderStats)> = Vec::new();
+    let mut last_frame = (framebuffer_width, framebuffer_height, planet.shader);
+    // Cuadros dibujados desde que se abrió la ventana, para `--dump-buffers frame=N`; F9 vuelca el próximo
+    let mut frames_drawn = 0;
+    let mut dump_requested = false;
+    let mut screenshot_requested = false;
+    #[cfg(feature = "io")]
+    let screenshot_dir = capture::screenshot_dir(&args);
+    let mut frame_stream = stream_options.map(|options| FrameStream::open(&options, framebuffer_width, framebuffer_height));
+    let mut stream_frame = Framebuffer::new(framebuffer_width, framebuffer_height);
+    if let Some(bookmark) = &start_bookmark {
+        bookmark.apply(&mut camera);
+        clock.set(bookmark.time);
+        clock.set_scale(bookmark.time_scale);
+        time = clock.seconds();
+        planet.shader = bookmark.shader;
+    }
 
     while window.is_open() {
-        if window.is_key_down(Key::Escape) {
+        // Explorador de semillas: flechas para moverse, Enter aplica la semilla, Escape sale sin cambios
+        if seed_browser.active {
+            if window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No) {
+                seed_browser.close();
+                continue;
+            }
+            if window.is_key_pressed(Key::Enter, minifb::KeyRepeat::No) {
+                noise_seed = seed_browser.selected_seed();
+                scene.rename(noise_seed);
+                window.set_title(&window_title(&scene.names.planet, shader_overrides.label().as_deref()));
+                seed_browser.close();
+                continue;
+            }
+            for (key, dx, dy) in [(Key::Left, -1, 0), (Key::Right, 1, 0), (Key::Up, 0, -1), (Key::Down, 0, 1)] {
+                if window.is_key_pressed(key, minifb::KeyRepeat::Yes) {
+                    seed_browser.navigate(dx, dy);
+                }
+            }
+
+            let shader = shader_overrides.resolve(planet.shader);
+            let browser_time = seed_browser.time;
+            seed_browser.render_pending(2, |cell, seed| {
+                let mut uniforms = create_uniforms(&camera, cell.width, cell.height, cell.width, cell.height, planet.time.at(browser_time), create_noise(noise_backend, seed));
+                scene.attach(&mut uniforms, browser_time);
+                render(cell, &uniforms, "planet", planet.mesh(), shader);
+            });
+            seed_browser.compose(&mut framebuffer);
+
+            window
+                .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+                .unwrap();
+            std::thread::sleep(frame_delay);
+            continue;
+        }
+
+        // Renombrar un marcador: la escena queda en pausa y las teclas escriben el nombre
+        if bookmark_panel.is_editing() {
+            bookmark_panel.handle_text_input(&window, &mut bookmarks);
+            #[cfg(feature = "overlay")]
+            bookmark_panel.draw(&mut framebuffer, &bookmarks, bars.bar_height(framebuffer_width, framebuffer_height));
+
+            window
+                .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+                .unwrap();
+            std::thread::sleep(frame_delay);
+            continue;
+        }
+
+        if window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No) {
             break;
         }
 
+        // Marcadores: Ctrl+1..9 guarda la vista actual, 1..9 la recupera con una transición suave
+        let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+        for (slot, key) in SLOT_KEYS.iter().enumerate() {
+            if !window.is_key_pressed(*key, minifb::KeyRepeat::No) {
+                continue;
+            }
+            if ctrl {
+                let name = format!("{} {}", scene.names.planet, slot + 1);
+                bookmarks.store(slot, Bookmark::capture(name, &camera, time, clock.scale, planet.shader));
+                if let Err(error) = bookmarks.save() {
+                    eprintln!("No se pudieron guardar los marcadores: {}", error);
+                }
+                bookmark_panel.current = Some(slot);
+            } else if let Some(bookmark) = bookmarks.get(slot) {
+                camera_transition = Some(CameraTransition::new(&camera, bookmark, 45));
+                ride.active = false;
+                scene.observer.enabled = false;
+                camera.near = camera::DEFAULT_NEAR;
+                clock.set(bookmark.time);
+                clock.set_scale(bookmark.time_scale);
+                time = clock.seconds();
+                planet.shader = bookmark.shader;
+                bookmark_panel.current = Some(slot);
+            }
+        }
+
+        // "L" muestra la lista de marcadores y "T" renombra el actual
+        #[cfg(feature = "overlay")]
+        {
+            if window.is_key_pressed(Key::L, minifb::KeyRepeat::No) {
+                bookmark_panel.visible = !bookmark_panel.visible;
+            }
+            if window.is_key_pressed(Key::T, minifb::KeyRepeat::No) {
+                bookmark_panel.start_editing(&bookmarks);
+            }
+        }
+
+        // "B" abre el explorador de semillas para el shader actual
+        if window.is_key_pressed(Key::B, minifb::KeyRepeat::No) {
+            seed_browser.open(noise_seed, time);
+            continue;
+        }
+
         // Cambiar el shader al presionar "S"
         if window.is_key_pressed(Key::S, minifb::KeyRepeat::No) {
-            current_shader = (current_shader + 1) % 5; // Cambia entre 0, 1, 2, 3, 4
+            planet.shader = planet.shader.next();
         }
 
-        time += 1;
+        // "N" pone la vista de normales encima del shader actual, "J" la del ruido crudo; "Backspace" quita la última
+        let mut overrides_changed = false;
+        if window.is_key_pressed(Key::N, minifb::KeyRepeat::No) {
+            shader_overrides.push(ShaderKind::NormalsDebug);
+            overrides_changed = true;
+        }
+        if window.is_key_pressed(Key::J, minifb::KeyRepeat::No) {
+            shader_overrides.push(ShaderKind::NoiseDebug);
+            overrides_changed = true;
+        }
+        if window.is_key_pressed(Key::Backspace, minifb::KeyRepeat::No) {
+            overrides_changed = shader_overrides.pop().is_some();
+        }
+        if overrides_changed {
+            window.set_title(&window_title(&scene.names.planet, shader_overrides.label().as_deref()));
+        }
 
-        handle_input(&window, &mut camera);
+        // "G" muestra los ejes y el gizmo de orientación, "H" la rejilla de la eclíptica
+        if window.is_key_pressed(Key::G, minifb::KeyRepeat::No) {
+            gizmo.enabled = !gizmo.enabled;
+        }
+        if window.is_key_pressed(Key::H, minifb::KeyRepeat::No) {
+            gizmo.show_grid = !gizmo.show_grid;
+        }
 
-        framebuffer.clear();
+        // "P" alterna el modo retro de baja resolución, "O" su tramado ordenado
+        if window.is_key_pressed(Key::P, minifb::KeyRepeat::No) {
+            retro.enabled = !retro.enabled;
+        }
+        if window.is_key_pressed(Key::O, minifb::KeyRepeat::No) {
+            retro.dither = match retro.dither {
+                DitherMode::Off => DitherMode::Ordered,
+                _ => DitherMode::Off,
+            };
+        }
+
+        // Viento solar con "X"
+        if window.is_key_pressed(Key::X, minifb::KeyRepeat::No) {
+            solar_wind.enabled = !solar_wind.enabled;
+        }
+
+        // "I" guarda las estadísticas del último cuadro en el archivo de `--stats-out` (stats.json si no se dio)
+        if window.is_key_pressed(Key::I, minifb::KeyRepeat::No) {
+            let mut report = StatsReport::new(stats_config("interactive", last_frame.0, last_frame.1, noise_seed, noise_backend, scene.shading_threads));
+            for (entity, shader, stats) in &last_drawn {
+                report.record(entity, shader.name(), stats);
+            }
+            scene.label_report(&mut report, &planet, last_frame.2, None);
+            report.frames = 1;
+            save_stats(&report, stats_out.as_deref().unwrap_or("stats.json"));
+        }
+
+        // "F9" vuelca los buffers intermedios del próximo cuadro (ver `--dump-buffers`)
+        if window.is_key_pressed(Key::F9, minifb::KeyRepeat::No) {
+            dump_requested = true;
+        }
+
+        // Captura de la imagen limpia, sin overlays, con F12
+        if window.is_key_pressed(Key::F12, minifb::KeyRepeat::No) {
+            screenshot_requested = true;
+        }
+
+        // Franjas de cine con "K"
+        if window.is_key_pressed(Key::K, minifb::KeyRepeat::No) {
+            bars.enabled = !bars.enabled;
+        }
+
+        // Anillos del planeta con "R"
+        if window.is_key_pressed(Key::R, minifb::KeyRepeat::No) {
+            scene.rings = !scene.rings;
+        }
+
+        // Órbitas del planeta doble con "Y"
+        if window.is_key_pressed(Key::Y, minifb::KeyRepeat::No) {
+            if let Some(barycenter) = scene.double_planet.as_mut() {
+                barycenter.show_orbits = !barycenter.show_orbits;
+            }
+        }
+
+        // Atmósfera alrededor del planeta con "U"
+        if window.is_key_pressed(Key::U, minifb::KeyRepeat::No) {
+            scene.atmosphere.enabled = !scene.atmosphere.enabled;
+        }
+
+        // Histograma y forma de onda de luminancia con "V"
+        #[cfg(feature = "overlay")]
+        if window.is_key_pressed(Key::V, minifb::KeyRepeat::No) {
+            scopes.enabled = !scopes.enabled;
+        }
+
+        // Paseo en el cometa con "Tab": la cámara llega en transición a la persecución y "Tab" otra vez
+        // la devuelve a donde estaba
+        if let Some(comet) = scene.comet.as_ref().filter(|_| window.is_key_pressed(Key::Tab, minifb::KeyRepeat::No)) {
+            if ride.active {
+                ride.active = false;
+                if let Some(view) = ride_return.take() {
+                    camera_transition = Some(CameraTransition::new(&camera, &view, RIDE_TRANSITION_FRAMES));
+                }
+            } else {
+                // La transición apunta a donde estará el cometa cuando termine, y ahí arranca el resorte
+                let ahead = if clock.paused { 0.0 } else { RIDE_TRANSITION_FRAMES as f32 * FRAME_SECONDS * clock.scale };
+                let pose = scene.ride_pose(&ride, comet, time + ahead);
+                let arrival = Bookmark::capture(String::new(), &Camera::new(pose.0, pose.1, Vec3::y()), time, clock.scale, planet.shader);
+                ride_return = Some(Bookmark::capture(String::new(), &camera, time, clock.scale, planet.shader));
+                camera_transition = Some(CameraTransition::new(&camera, &arrival, RIDE_TRANSITION_FRAMES));
+                ride.enter(pose);
+            }
+        }
+
+        // Observador en la superficie con "/": la cámara se planta en el planeta y "/" otra vez la
+        // devuelve a donde estaba
+        if window.is_key_pressed(Key::Slash, minifb::KeyRepeat::No) {
+            scene.observer.enabled = !scene.observer.enabled;
+            if scene.observer.enabled {
+                observer_return = Some(Bookmark::capture(String::new(), &camera, time, clock.scale, planet.shader));
+                ride.active = false;
+                camera_transition = None;
+            } else {
+                if let Some(view) = observer_return.take() {
+                    view.apply(&mut camera);
+                }
+                camera.near = camera::DEFAULT_NEAR;
+            }
+        }
+
+        // Activar o desactivar el desenfoque de movimiento con "M"
+        if window.is_key_pressed(Key::M, minifb::KeyRepeat::No) {
+            post_chain.toggle("motion_blur");
+        }
+
+        // Resplandor del sol con F2
+        if window.is_key_pressed(Key::F2, minifb::KeyRepeat::No) {
+            post_chain.toggle("bloom");
+        }
+
+        // Supermuestreo con F3: apagado, 2x2 y 4x4 muestras por píxel
+        if window.is_key_pressed(Key::F3, minifb::KeyRepeat::No) {
+            supersampling.cycle();
+            eprintln!("Supermuestreo: x{}", supersampling.factor);
+        }
+
+        // F4 recorre los modos de render: relleno, aristas, los dos y las vistas de normales, profundidad y UV
+        if window.is_key_pressed(Key::F4, minifb::KeyRepeat::No) {
+            scene.render_mode = scene.render_mode.next();
+            eprintln!("Modo de render: {}", scene.render_mode.name());
+        }
+
+        // F5 vuelve a leer los parámetros de los shaders, para ajustarlos con la ventana abierta
+        if window.is_key_pressed(Key::F5, minifb::KeyRepeat::No) {
+            scene.reload_shader_params();
+        }
+
+        // Niebla por distancia con F6
+        if window.is_key_pressed(Key::F6, minifb::KeyRepeat::No) {
+            scene.fog.enabled = !scene.fog.enabled;
+            eprintln!("Niebla: {}", if scene.fog.enabled { "activada" } else { "desactivada" });
+        }
+
+        // F10 recorre el tramado de la salida: apagado, Bayer 8x8 y ruido azul
+        if window.is_key_pressed(Key::F10, minifb::KeyRepeat::No) {
+            scene.output_dither = scene.output_dither.next();
+            eprintln!("Tramado de salida: {}", scene.output_dither.name());
+        }
+
+        // F7 congela el tiempo (la cámara se sigue moviendo) y F8 lo avanza un cuadro mientras tanto;
+        // "PageDown" / "PageUp" lo hacen correr a la mitad o al doble, de 0.25x a 8x
+        if window.is_key_pressed(Key::F7, minifb::KeyRepeat::No) {
+            clock.toggle_pause();
+            eprintln!("Tiempo: {}", if clock.paused { "en pausa" } else { "corriendo" });
+        }
+        if window.is_key_pressed(Key::F8, minifb::KeyRepeat::Yes) {
+            clock.step();
+        }
+        if window.is_key_pressed(Key::PageDown, minifb::KeyRepeat::No) {
+            clock.slower();
+            eprintln!("Escala de tiempo: x{}", clock.scale);
+        }
+        if window.is_key_pressed(Key::PageUp, minifb::KeyRepeat::No) {
+            clock.faster();
+            eprintln!("Escala de tiempo: x{}", clock.scale);
+        }
+
+        // Curvas de color con "C"
+        if window.is_key_pressed(Key::C, minifb::KeyRepeat::No) {
+            post_chain.toggle("curves");
+        }
+
+        // Profundidad de campo: "F" la activa, "[" / "]" cambian la apertura y "," / "." el foco
+        if window.is_key_pressed(Key::F, minifb::KeyRepeat::No) {
+            post_chain.toggle("depth_of_field");
+        }
+        #[cfg(feature = "post")]
+        if let Some(dof) = post_chain.get_mut::<DepthOfField>() {
+            if window.is_key_down(Key::LeftBracket) {
+                dof.aperture = (dof.aperture - 0.5).max(0.0);
+            }
+            if window.is_key_down(Key::RightBracket) {
+                dof.aperture += 0.5;
+            }
+            if window.is_key_down(Key::Comma) {
+                dof.focus_offset -= 0.05;
+            }
+            if window.is_key_down(Key::Period) {
+                dof.focus_offset += 0.05;
+            }
+        }
+
+        // Desde la superficie el tiempo corre más rápido, para ver pasar un día en segundos
+        time = clock.tick(if scene.observer.enabled { scene.observer.speed as f32 } else { 1.0 });
+        scene.tick(time);
+        bars.update();
+
+        // En modo retro el ratón sobre los bordes negros no toca la escena: un arrastre no empieza ahí
+        // y se corta al salir de la imagen
+        let pointer = window
+            .get_mouse_pos(MouseMode::Pass)
+            .filter(|&(x, y)| !retro.enabled || retro.to_internal(x, y, framebuffer.width, framebuffer.height).is_some());
+        if scene.observer.enabled {
+            scene.observer.handle_input(&window, pointer, &mut last_mouse);
+        } else {
+            handle_input(&window, &mut camera, pointer, &mut last_mouse);
+        }
+        if let Some(transition) = camera_transition.as_mut() {
+            if transition.step(&mut camera) {
+                camera_transition = None;
+            }
+        }
+        if let Some(comet) = scene.comet.as_ref().filter(|_| ride.active && camera_transition.is_none()) {
+            ride.follow(&mut camera, scene.ride_pose(&ride, comet, time));
+        }
+
+        // En modo retro se renderiza en el framebuffer interno de baja resolución, y con
+        // supermuestreo en uno más grande que después se promedia sobre ese
+        let target = if retro.enabled { &mut retro.framebuffer } else { &mut framebuffer };
+        target.clear();
+        let output = (target.width, target.height);
+        let raster = if supersampling.enabled() { supersampling.begin(output.0, output.1) } else { target };
 
         // Uniformes de transformación y tiempo
-        let model_matrix = create_model_matrix(Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0));
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-        let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
-
-        let uniforms = Uniforms {
-            model_matrix,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            noise: create_noise(),
-        };
+        let mut uniforms = create_uniforms(&camera, window_width, window_height, raster.width, raster.height, planet.time.at(time), create_noise(noise_backend, noise_seed));
+        uniforms.delta_time = clock.delta();
+        scene.attach(&mut uniforms, time);
+        if scene.observer.enabled {
+            scene.observe(&mut camera, &mut uniforms, &planet, system.as_ref(), (window_width, window_height));
+        }
 
         // Renderizar con el shader actual
-        render(&mut framebuffer, &uniforms, &planet_vertex_array, current_shader);
+        let bodies = Bodies { planet: &planet, rings: &rings, companion: companion.as_ref(), comet: comet.as_ref(), system: system.as_ref() };
+        let drawn = draw_bodies(raster, &mut uniforms, &scene, &bodies, time, &|shader| shader_overrides.resolve(shader));
+        last_frame = (raster.width, raster.height, drawn[0].1);
+        last_drawn = drawn;
+        let target = if retro.enabled { &mut retro.framebuffer } else { &mut framebuffer };
+        // Un cuadro que se guarda o se transmite se redondea con difusión de error entre las franjas;
+        // en modo retro lo cuantiza `present_with`
+        let capturing = (frame_stream.is_some() || screenshot_requested) && !retro.enabled;
+        let diffusion = (capturing && scene.capture_diffusion).then(|| bars.visible_rows(target.width, target.height));
+        resolve_supersampling(&supersampling, target, &mut uniforms, diffusion);
+        let magnetospheres = scene.solar_system.as_ref().zip(system.as_ref()).map_or_else(Vec::new, |(solar, sphere)| solar.magnetospheres(uniforms.time, sphere.radius));
+        solar_wind.update(uniforms.delta_time, &magnetospheres);
+        solar_wind.draw(target, &uniforms);
+        frames_drawn += 1;
+        let mut readback = (dump_requested || scene.dumps_frame(frames_drawn)).then(|| capture_scene(target));
+
+        // El desenfoque radial acompaña la transición hacia un marcador, centrado en su destino
+        #[cfg(feature = "post")]
+        if let Some(blur) = post_chain.get_mut::<RadialBlur>() {
+            blur.strength = 0.0;
+            if let Some(transition) = camera_transition.as_ref() {
+                if let Some(focus) = gizmo::project(&transition.target_center(), &uniforms) {
+                    blur.strength = transition.speed();
+                    blur.focal_point = (focus.x, focus.y);
+                }
+            }
+        }
+        post_chain.run(target, &uniforms, readback.as_mut());
+        if let Some(readback) = readback {
+            scene.save_dump(&readback, frames_drawn);
+            dump_requested = false;
+        }
+
+        // La transmisión y las capturas reciben la imagen limpia, sin el gizmo ni los overlays
+        if frame_stream.is_some() || screenshot_requested {
+            // Las franjas se graban tal como se ven; al final se vuelven a dibujar encima de los overlays
+            let clean = if retro.enabled {
+                let visible = bars.visible_rows(stream_frame.width, stream_frame.height);
+                retro.present_with(&mut stream_frame, retro.capture_dither, visible);
+                bars.draw(&mut stream_frame);
+                &stream_frame
+            } else {
+                bars.draw(&mut framebuffer);
+                &framebuffer
+            };
+            if let Some(stream) = frame_stream.as_mut() {
+                if !stream.send(clean) {
+                    eprintln!("El consumidor de la transmisión se cerró");
+                    frame_stream = None;
+                }
+            }
+            #[cfg(feature = "io")]
+            if screenshot_requested {
+                let path = capture::screenshot_path(&screenshot_dir);
+                match capture::save_frame(&path, clean) {
+                    Ok(()) => eprintln!("Captura guardada en {}", path),
+                    Err(error) => eprintln!("Error al guardar la captura {}: {}", path, error),
+                }
+            }
+            #[cfg(not(feature = "io"))]
+            if screenshot_requested {
+                eprintln!("Sin la feature `io` no se guardan capturas");
+            }
+            screenshot_requested = false;
+        }
+
+        let target = if retro.enabled { &mut retro.framebuffer } else { &mut framebuffer };
+        gizmo.draw(target, &uniforms);
+
+        if retro.enabled {
+            retro.present(&mut framebuffer);
+        }
+        // Los paneles y los HUD de texto, por debajo de las franjas
+        #[cfg(feature = "overlay")]
+        {
+            let inset = bars.bar_height(framebuffer_width, framebuffer_height);
+            scopes.draw(&mut framebuffer, inset);
+            bookmark_panel.draw(&mut framebuffer, &bookmarks, inset);
+            if let Some(comet) = scene.comet.as_ref().filter(|_| ride.active) {
+                ride.draw_hud(&mut framebuffer, &scene.ride_status(comet, time), inset);
+            }
+            scene.observer.draw_hud(&mut framebuffer, &camera, &uniforms.light.position, inset);
+        }
+        bars.draw(&mut framebuffer);
 
         window
             .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
@@ -172,53 +1619,491 @@ fn main() {
 
         std::thread::sleep(frame_delay);
     }
+
+    if let Some(stream) = frame_stream.as_mut() {
+        stream.close();
+    }
+}
+
+// Cámara, reloj y shader iniciales de los modos sin ventana: la vista por defecto o un marcador.
+// El reloj avanza un cuadro de referencia por iteración, así la salida no depende de cuánto tarda.
+fn headless_view(bookmark: Option<&Bookmark>) -> (Camera, Clock, ShaderKind) {
+    let mut camera = Camera::new(
+        Vec3::new(0.0, 0.0, 5.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0)
+    );
+    match bookmark {
+        Some(bookmark) => {
+            bookmark.apply(&mut camera);
+            let mut clock = Clock::fixed(bookmark.time);
+            clock.set_scale(bookmark.time_scale);
+            (camera, clock, bookmark.shader)
+        }
+        None => (camera, Clock::fixed(0.0), ShaderKind::Sun),
+    }
+}
+
+// Modo sin ventana: avanza un cuadro fijo por iteración y acumula la columna central hasta llenar la imagen
+#[cfg(feature = "io")]
+fn run_slitscan(options: &SlitScanOptions, framebuffer_width: usize, framebuffer_height: usize, bookmark: Option<&Bookmark>, scene: &mut SceneSetup, stats_out: Option<&str>) {
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    framebuffer.set_background_color(0x333355);
+    let mut supersampling = Supersampling::new(scene.supersample);
+
+    let (mut camera, mut clock, shader) = headless_view(bookmark);
+
+    let planet = scene.planet_model(shader);
+    let rings = ring_model(&planet);
+    let companion = scene.companion();
+    let comet = scene.comet_model();
+    let system = scene.system_model();
+
+    let mut slitscan = SlitScan::new(options.columns, framebuffer_height);
+    let mut report = StatsReport::new(stats_config("slitscan", framebuffer_width, framebuffer_height, DEFAULT_SEED, scene.noise_backend, scene.shading_threads));
+
+    while !slitscan.is_complete() {
+        let time = clock.tick(if scene.observer.enabled { scene.observer.speed as f32 } else { 1.0 });
+        scene.tick(time);
+
+        framebuffer.clear();
+        let raster = if supersampling.enabled() { supersampling.begin(framebuffer_width, framebuffer_height) } else { &mut framebuffer };
+        let mut uniforms = create_uniforms(&camera, framebuffer_width, framebuffer_height, raster.width, raster.height, planet.time.at(time), create_noise(scene.noise_backend, DEFAULT_SEED));
+        uniforms.delta_time = clock.delta();
+        scene.attach(&mut uniforms, time);
+        if scene.observer.enabled {
+            scene.observe(&mut camera, &mut uniforms, &planet, system.as_ref(), (framebuffer_width, framebuffer_height));
+        }
+        let bodies = Bodies { planet: &planet, rings: &rings, companion: companion.as_ref(), comet: comet.as_ref(), system: system.as_ref() };
+        for (entity, shader, stats) in draw_bodies(raster, &mut uniforms, scene, &bodies, time, &|shader| shader) {
+            report.record(entity, shader.name(), &stats);
+        }
+        resolve_supersampling(&supersampling, &mut framebuffer, &mut uniforms, scene.capture_diffusion.then_some(0..framebuffer_height));
+        report.frames += 1;
+
+        slitscan.capture(&framebuffer);
+    }
+
+    if let Some(path) = stats_out {
+        scene.label_report(&mut report, &planet, planet.shader, companion.as_ref());
+        save_stats(&report, path);
+    }
+
+    slitscan.save(&options.output).expect("Failed to save slit-scan image");
+    println!("Slit-scan guardado en {} ({}x{})", options.output, slitscan.width, slitscan.height);
 }
 
-// Modifica `render` para aceptar `current_shader`:
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], current_shader: u32) {
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
+// Transmisión sin ventana: paso de tiempo fijo y sin espera entre cuadros; el consumidor marca el ritmo
+fn run_stream(options: &StreamOptions, framebuffer_width: usize, framebuffer_height: usize, bookmark: Option<&Bookmark>, scene: &mut SceneSetup, stats_out: Option<&str>, bars: &CinematicBars) {
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    framebuffer.set_background_color(0x333355);
+    let mut supersampling = Supersampling::new(scene.supersample);
+
+    let (mut camera, mut clock, shader) = headless_view(bookmark);
+
+    let planet = scene.planet_model(shader);
+    let rings = ring_model(&planet);
+    let companion = scene.companion();
+    let comet = scene.comet_model();
+    let system = scene.system_model();
+
+    let mut stream = FrameStream::open(options, framebuffer_width, framebuffer_height);
+    let mut frames = 0;
+    let mut report = StatsReport::new(stats_config("stream", framebuffer_width, framebuffer_height, DEFAULT_SEED, scene.noise_backend, scene.shading_threads));
+
+    while options.frames.is_none_or(|limit| frames < limit) {
+        let time = clock.tick(if scene.observer.enabled { scene.observer.speed as f32 } else { 1.0 });
+        frames += 1;
+        scene.tick(time);
+
+        framebuffer.clear();
+        let raster = if supersampling.enabled() { supersampling.begin(framebuffer_width, framebuffer_height) } else { &mut framebuffer };
+        let mut uniforms = create_uniforms(&camera, framebuffer_width, framebuffer_height, raster.width, raster.height, planet.time.at(time), create_noise(scene.noise_backend, DEFAULT_SEED));
+        uniforms.delta_time = clock.delta();
+        scene.attach(&mut uniforms, time);
+        if scene.observer.enabled {
+            scene.observe(&mut camera, &mut uniforms, &planet, system.as_ref(), (framebuffer_width, framebuffer_height));
+        }
+        let bodies = Bodies { planet: &planet, rings: &rings, companion: companion.as_ref(), comet: comet.as_ref(), system: system.as_ref() };
+        for (entity, shader, stats) in draw_bodies(raster, &mut uniforms, scene, &bodies, time, &|shader| shader) {
+            report.record(entity, shader.name(), &stats);
+        }
+        resolve_supersampling(&supersampling, &mut framebuffer, &mut uniforms, scene.capture_diffusion.then(|| bars.visible_rows(framebuffer_width, framebuffer_height)));
+        report.frames += 1;
+        if scene.dumps_frame(frames) {
+            scene.save_dump(&capture_scene(&framebuffer), frames);
+        }
+
+        bars.draw(&mut framebuffer);
+        if !stream.send(&framebuffer) {
+            break;
+        }
+    }
+
+    stream.close();
+    if let Some(path) = stats_out {
+        scene.label_report(&mut report, &planet, planet.shader, companion.as_ref());
+        save_stats(&report, path);
+    }
+}
+
+// Con supermuestreo promedia el framebuffer interno sobre `target` y deja la matriz de viewport a la
+// resolución de salida, que es la que usan el post-proceso y los overlays que proyectan puntos.
+// `diffusion` son las filas que se redondean con difusión de error, si el cuadro se guarda.
+fn resolve_supersampling(supersampling: &Supersampling, target: &mut Framebuffer, uniforms: &mut Uniforms, diffusion: Option<Range<usize>>) {
+    if supersampling.enabled() {
+        supersampling.resolve(target, uniforms.output_dither, diffusion);
+        uniforms.viewport_matrix = create_viewport_matrix(target.width as f32, target.height as f32);
+    }
+}
+
+// Modo sin ventana: `count` cuadros a PNG con el post-proceso y las franjas, como en la transmisión.
+// El primero es el del instante inicial (la vista por defecto o el marcador).
+#[cfg(feature = "io")]
+fn run_record(options: &RecordOptions, framebuffer_width: usize, framebuffer_height: usize, bookmark: Option<&Bookmark>, scene: &mut SceneSetup, post_chain: &mut PostChain, bars: &CinematicBars) {
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    framebuffer.set_background_color(0x333355);
+    let mut supersampling = Supersampling::new(scene.supersample);
+
+    let (mut camera, mut clock, shader) = headless_view(bookmark);
+
+    let planet = scene.planet_model(shader);
+    let rings = ring_model(&planet);
+    let companion = scene.companion();
+    let comet = scene.comet_model();
+    let system = scene.system_model();
+
+    for index in 0..options.count {
+        let time = clock.seconds();
+        scene.tick(time);
+
+        framebuffer.clear();
+        let raster = if supersampling.enabled() { supersampling.begin(framebuffer_width, framebuffer_height) } else { &mut framebuffer };
+        let mut uniforms = create_uniforms(&camera, framebuffer_width, framebuffer_height, raster.width, raster.height, planet.time.at(time), create_noise(scene.noise_backend, DEFAULT_SEED));
+        uniforms.delta_time = clock.delta();
+        scene.attach(&mut uniforms, time);
+        if scene.observer.enabled {
+            scene.observe(&mut camera, &mut uniforms, &planet, system.as_ref(), (framebuffer_width, framebuffer_height));
+        }
+        let bodies = Bodies { planet: &planet, rings: &rings, companion: companion.as_ref(), comet: comet.as_ref(), system: system.as_ref() };
+        draw_bodies(raster, &mut uniforms, scene, &bodies, time, &|shader| shader);
+        resolve_supersampling(&supersampling, &mut framebuffer, &mut uniforms, scene.capture_diffusion.then(|| bars.visible_rows(framebuffer_width, framebuffer_height)));
+        post_chain.run(&mut framebuffer, &uniforms, None);
+        bars.draw(&mut framebuffer);
+
+        let path = options.frame_path(index);
+        if let Err(error) = capture::save_frame(&path, &framebuffer) {
+            eprintln!("Error al guardar {}: {}", path, error);
+            std::process::exit(1);
+        }
+        clock.tick(options.step as f32);
+    }
+    eprintln!("{} cuadros guardados en {}", options.count, options.dir);
+}
+
+// Las pasadas de post-proceso en orden, con el grafo ya compilado; `--frame-graph-debug` imprime el plan.
+// Sin la feature `post` la cadena queda vacía y el cuadro sale tal como se rasterizó.
+fn build_post_chain(args: &[String]) -> PostChain {
+    let mut post_chain = PostChain::new();
+    #[cfg(feature = "post")]
+    {
+        post_chain.push(Box::new(MotionBlur::new()));
+        post_chain.push(Box::new(DepthOfField::new()));
+        post_chain.push(Box::new(Bloom::from_args(args)));
+        post_chain.push(Box::new(RadialBlur::new()));
+        post_chain.push(Box::new(ColorCurves::from_args(args)));
+    }
+    match post_chain.compile() {
+        Ok(plan) if args.iter().any(|arg| arg == "--frame-graph-debug") => print!("{}", plan.describe()),
+        Ok(_) => {}
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+    post_chain
+}
+
+fn stats_config(mode: &'static str, width: usize, height: usize, seed: i32, noise_backend: NoiseBackend, threads: usize) -> StatsConfig {
+    StatsConfig {
+        mode,
+        width,
+        height,
+        seed,
+        noise: noise_backend.name(),
+        threads,
+    }
+}
+
+// El nombre del planeta y, si hay, la pila de shaders forzados
+fn window_title(planet_name: &str, overrides: Option<&str>) -> String {
+    match overrides {
+        Some(label) => format!("Shader Switcher - {} - {}", planet_name, label),
+        None => format!("Shader Switcher - {}", planet_name),
+    }
+}
+
+// Mensajes a stderr: en la transmisión stdout lleva el video
+// Color, profundidad y emisión de la escena tal como los recibe el post-proceso
+fn capture_scene(framebuffer: &Framebuffer) -> Readback {
+    let mut readback = Readback::new();
+    readback.color(SCENE_COLOR.name, "render", true, framebuffer.width, framebuffer.height, &framebuffer.buffer);
+    readback.depth(SCENE_DEPTH.name, "render", framebuffer.width, framebuffer.height, &framebuffer.zbuffer);
+    readback.color(SCENE_EMISSION.name, "render", true, framebuffer.width, framebuffer.height, &framebuffer.emission);
+    readback
+}
+
+fn save_stats(report: &StatsReport, path: &str) {
+    match report.save(path) {
+        Ok(()) => eprintln!("Estadísticas guardadas en {}", path),
+        Err(error) => eprintln!("Error al guardar las estadísticas: {}", error),
+    }
+}
+
+fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, entity: &str, mesh: Mesh, shader: ShaderKind) -> RenderStats {
+    render_blended(framebuffer, uniforms, entity, mesh, Material::opaque(shader))
+}
+
+fn render_blended(framebuffer: &mut Framebuffer, uniforms: &Uniforms, entity: &str, mesh: Mesh, material: Material) -> RenderStats {
+    let mut stats = RenderStats::default();
+    let submitted = (mesh.vertices.len() / 3) as u64;
+
+    // Un cuerpo entero fuera de la vista no llega a transformar sus vértices. El relieve puede sacar
+    // la superficie de la esfera envolvente, así que la agranda en su amplitud.
+    let displacement = uniforms.displacement.and_then(|displacement| displacement.resolve(material.shader));
+    let clip = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix;
+    let margin = displacement.map_or(0.0, |params| params.amplitude.abs());
+    if uniforms.culling && !mesh.bounds.in_frustum(&clip, margin) {
+        stats.triangles_submitted = submitted;
+        stats.triangles_frustum = submitted;
+        return stats;
+    }
+
+    let start = Instant::now();
+    let mut transformed_vertices = Vec::with_capacity(mesh.vertices.len());
+    for vertex in mesh.vertices {
+        let transformed = vertex_shader(vertex, uniforms, material.shader, displacement.as_ref());
         transformed_vertices.push(transformed);
     }
+    stats.vertices = transformed_vertices.len() as u64;
+    stats.vertex_time = start.elapsed();
 
-    let mut triangles = Vec::new();
+    // Un vértice con posición no finita (matriz degenerada, desplazamiento absurdo) descarta su
+    // triángulo antes de llegar al rasterizador. El resto se recorta contra los planos cercano y
+    // lejano, se proyecta y, salvo en mallas de dos caras, se descarta si da la espalda.
+    let cull_backfaces = uniforms.culling && !mesh.double_sided;
+    let mut triangles: Vec<Vec<[Vertex; 3]>> = Vec::new();
+    let mut first_invalid = None;
     for i in (0..transformed_vertices.len()).step_by(3) {
         if i + 2 < transformed_vertices.len() {
-            triangles.push([
+            let triangle = [
                 transformed_vertices[i].clone(),
                 transformed_vertices[i + 1].clone(),
                 transformed_vertices[i + 2].clone(),
-            ]);
+            ];
+            if !triangle.iter().all(is_finite_vertex) {
+                first_invalid.get_or_insert(i / 3);
+                stats.triangles_invalid += 1;
+                continue;
+            }
+            let mut pieces = match clip_triangle(&triangle) {
+                Clip::Inside => vec![triangle],
+                Clip::Outside => {
+                    stats.triangles_near += 1;
+                    continue;
+                }
+                Clip::Clipped(pieces) => {
+                    stats.triangles_clipped += 1;
+                    pieces
+                }
+            };
+            pieces.iter_mut().flatten().for_each(|vertex| project(vertex, &uniforms.viewport_matrix));
+            // Los pedazos de un triángulo recortado son coplanares y giran igual que él
+            let [a, b, c] = &pieces[0];
+            if !pieces.iter().flatten().all(is_projected_vertex) {
+                first_invalid.get_or_insert(i / 3);
+                stats.triangles_invalid += 1;
+            } else if cull_backfaces && faces_away(&a.transformed_position, &b.transformed_position, &c.transformed_position) {
+                stats.triangles_backface += 1;
+            } else {
+                triangles.push(pieces);
+            }
         }
     }
+    if let Some(index) = first_invalid {
+        warn_invalid_triangles(entity, index, stats.triangles_invalid);
+    }
+
+    if !uniforms.render_mode.fills() {
+        draw_wireframe(framebuffer, &triangles);
+        stats.triangles_submitted = submitted;
+        return stats;
+    }
 
+    let start = Instant::now();
     let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+    for pieces in &triangles {
+        let mut rasterized = false;
+        for tri in pieces {
+            let emitted = triangle(&tri[0], &tri[1], &tri[2], framebuffer.width, framebuffer.height);
+            rasterized |= !emitted.is_empty();
+            fragments.extend(emitted);
+        }
+        if rasterized {
+            stats.triangles_rasterized += 1;
+        }
     }
+    stats.triangles_submitted = submitted;
+    stats.fragments_emitted = fragments.len() as u64;
+    stats.raster_time = start.elapsed();
+
+    let start = Instant::now();
+    let shaded = shade_fragments(framebuffer, uniforms, fragments, material);
+    stats.fragments_shaded = shaded.fragments_shaded;
+    stats.fragments_discarded = shaded.fragments_discarded;
+    stats.fragments_depth_rejected = shaded.fragments_depth_rejected;
+    stats.fragment_time = start.elapsed();
+    if uniforms.render_mode.draws_wireframe() {
+        draw_wireframe(framebuffer, &triangles);
+    }
+
+    stats
+}
+
+// Sombrea en franjas horizontales, una por hilo. Cada franja recibe sus fragmentos en el orden en
+// que salieron del rasterizador y es la única que escribe en sus filas, así la prueba de profundidad
+// da lo mismo que en un solo hilo y la imagen es idéntica píxel a píxel.
+fn shade_fragments(framebuffer: &mut Framebuffer, uniforms: &Uniforms, fragments: Vec<Fragment>, material: Material) -> RenderStats {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let threads = uniforms.shading_threads.clamp(1, height.max(1));
+    let rows_per_band = height.div_ceil(threads).max(1);
 
+    let mut bands: Vec<Vec<Fragment>> = (0..threads).map(|_| Vec::new()).collect();
     for fragment in fragments {
         let x = fragment.position.x as usize;
         let y = fragment.position.y as usize;
+        if x < width && y < height {
+            bands[y / rows_per_band].push(fragment);
+        }
+    }
+
+    if threads == 1 {
+        let fragments = bands.pop().unwrap_or_default();
+        let band = Band { colors: &mut framebuffer.buffer, depths: &mut framebuffer.zbuffer, emission: &mut framebuffer.emission, offset: 0 };
+        return shade_band(uniforms, material, fragments, band, width);
+    }
 
-        if x < framebuffer.width && y < framebuffer.height {
-            let shaded_color = fragment_shader(&fragment, &uniforms, current_shader);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+    let band_size = rows_per_band * width;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = bands
+            .into_iter()
+            .zip(framebuffer.buffer.chunks_mut(band_size).zip(framebuffer.zbuffer.chunks_mut(band_size)))
+            .zip(framebuffer.emission.chunks_mut(band_size))
+            .enumerate()
+            .map(|(band, ((fragments, (colors, depths)), emission))| {
+                let band = Band { colors, depths, emission, offset: band * band_size };
+                scope.spawn(move || shade_band(uniforms, material, fragments, band, width))
+            })
+            .collect();
+
+        let mut stats = RenderStats::default();
+        for handle in handles {
+            let band = handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            stats.add(&band);
+        }
+        stats
+    })
+}
+
+// Las filas de una franja en cada buffer; `offset` es el índice de su primer píxel
+struct Band<'a> {
+    colors: &'a mut [u32],
+    depths: &'a mut [f32],
+    emission: &'a mut [u32],
+    offset: usize,
+}
+
+// La emisión se mezcla igual que el color, así una capa transparente delante del sol tapa su brillo
+fn shade_band(uniforms: &Uniforms, material: Material, fragments: Vec<Fragment>, band: Band, width: usize) -> RenderStats {
+    let Band { colors, depths, emission, offset } = band;
+    let mut stats = RenderStats::default();
+    for fragment in fragments {
+        let index = fragment.position.y as usize * width + fragment.position.x as usize - offset;
+        let Some(shaded) = fragment_shader(&fragment, uniforms, material.shader) else {
+            stats.fragments_discarded += 1;
+            continue;
+        };
+        stats.fragments_shaded += 1;
+        if depths[index] <= fragment.depth {
+            stats.fragments_depth_rejected += 1;
+            continue;
+        }
+        // La niebla va después del mapeo de tonos, así su color es el que se pidió; las vistas de
+        // depuración no llevan ninguno de los dos
+        let (color, glow) = if material.shader.is_debug_view() {
+            (shaded.color, shaded.emission)
+        } else {
+            uniforms.fog.apply(uniforms, &fragment.vertex_position, material.shader.emits(), shaded.color.tone_map(uniforms.exposure), shaded.emission)
+        };
+        let (color, glow) = (&color, &glow);
+        let dither = uniforms.output_dither.offset(fragment.position.x as usize, fragment.position.y as usize);
+        match material.blend {
+            BlendMode::Opaque => {
+                colors[index] = color.to_hex_dithered(dither);
+                emission[index] = glow.to_hex();
+                depths[index] = fragment.depth;
+            }
+            BlendMode::Alpha { write_depth } => {
+                let alpha = color.alpha();
+                colors[index] = Color::from_hex(colors[index]).lerp(color, alpha).to_hex_dithered(dither);
+                emission[index] = Color::from_hex(emission[index]).lerp(glow, alpha).to_hex();
+                if write_depth && alpha > 0.0 {
+                    depths[index] = fragment.depth;
+                }
+            }
+            BlendMode::Additive => {
+                colors[index] = Color::from_hex(colors[index]).blend_add(color).to_hex_dithered(dither);
+                emission[index] = Color::from_hex(emission[index]).blend_add(glow).to_hex();
+            }
         }
     }
+    stats
+}
+
+fn is_finite_vertex(vertex: &Vertex) -> bool {
+    vertex.clip_position.iter().all(|c| c.is_finite())
+}
+
+fn is_projected_vertex(vertex: &Vertex) -> bool {
+    vertex.transformed_position.iter().all(|c| c.is_finite()) && vertex.inverse_w.is_finite()
 }
 
-fn switch_shader(current_shader: &mut u32, total_shaders: u32) {
-    *current_shader = (*current_shader + 1) % total_shaders;
+// Como mucho un aviso por segundo, para no inundar la consola con un cuerpo roto en cada cuadro
+fn warn_invalid_triangles(entity: &str, first_index: usize, count: u64) {
+    static LAST_WARNING: Mutex<Option<Instant>> = Mutex::new(None);
+    let Ok(mut last) = LAST_WARNING.lock() else {
+        return;
+    };
+    if last.is_some_and(|instant| instant.elapsed() < Duration::from_secs(1)) {
+        return;
+    }
+    *last = Some(Instant::now());
+    eprintln!(
+        "Se descartaron {} triángulos con posiciones no finitas en '{}' (el primero es el #{})",
+        count, entity, first_index
+    );
 }
 
-fn handle_input(window: &Window, camera: &mut Camera) {
-    let movement_speed = 1.0;
+// Teclado: flechas y W/S orbitan y acercan, A/D/Q/E desplazan. Ratón: arrastrar con el botón
+// izquierdo orbita, con el derecho desplaza, y la rueda acerca. `pointer` es la posición del ratón
+// sobre la imagen y `last_mouse` la del cuadro anterior mientras hay un botón apretado.
+fn handle_input(window: &Window, camera: &mut Camera, pointer: Option<(f32, f32)>, last_mouse: &mut Option<(f32, f32)>) {
+    let pan_speed = 0.1;
     let rotation_speed = PI / 50.0;
     let zoom_speed = 0.1;
+    let drag_rotation = 0.01; // Radianes por píxel arrastrado
+    let drag_pan = 0.002; // Unidades de mundo por píxel, por unidad de distancia al centro
+    let wheel_zoom = 0.05;
    
     // Controles de órbita de la cámara
     if window.is_key_down(Key::Left) {
@@ -234,22 +2119,18 @@ fn handle_input(window: &Window, camera: &mut Camera) {
         camera.orbit(0.0, rotation_speed);
     }
 
-    // Controles de movimiento de la cámara
-    let mut movement = Vec3::new(0.0, 0.0, 0.0);
+    // Controles de desplazamiento de la cámara
     if window.is_key_down(Key::A) {
-        movement.x -= movement_speed;
+        camera.pan(-pan_speed, 0.0);
     }
     if window.is_key_down(Key::D) {
-        movement.x += movement_speed;
+        camera.pan(pan_speed, 0.0);
     }
     if window.is_key_down(Key::Q) {
-        movement.y += movement_speed;
+        camera.pan(0.0, pan_speed);
     }
     if window.is_key_down(Key::E) {
-        movement.y -= movement_speed;
-    }
-    if movement.magnitude() > 0.0 {
-        camera.move_center(movement);
+        camera.pan(0.0, -pan_speed);
     }
 
     // Controles de zoom de la cámara
@@ -259,4 +2140,84 @@ fn handle_input(window: &Window, camera: &mut Camera) {
     if window.is_key_down(Key::Down) {
         camera.zoom(-zoom_speed);
     }
-}
\ No newline at end of file
+
+    let orbiting = window.get_mouse_down(MouseButton::Left);
+    let panning = window.get_mouse_down(MouseButton::Right);
+    if let (Some((x, y)), Some((last_x, last_y))) = (pointer, *last_mouse) {
+        let (dx, dy) = (x - last_x, y - last_y);
+        if orbiting {
+            camera.orbit(-dx * drag_rotation, dy * drag_rotation);
+        } else if panning {
+            let distance = (camera.eye - camera.center).magnitude();
+            camera.pan(-dx * drag_pan * distance, dy * drag_pan * distance);
+        }
+    }
+    *last_mouse = pointer.filter(|_| orbiting || panning);
+
+    if let Some((_, scroll)) = window.get_scroll_wheel() {
+        camera.zoom(scroll * wheel_zoom);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_sphere(vertices: &[Vertex], shader: ShaderKind, threads: usize) -> (Framebuffer, RenderStats) {
+        let (width, height) = (80, 60);
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 2.2), Vec3::zeros(), Vec3::y());
+        let mut uniforms = create_uniforms(&camera, width, height, width, height, 0.0, Box::new(create_cloud_noise(DEFAULT_SEED)));
+        uniforms.shading_threads = threads;
+        let mut framebuffer = Framebuffer::new(width, height);
+        let mesh = Mesh { vertices, bounds: BoundingSphere::of(vertices), double_sided: false };
+        let stats = render(&mut framebuffer, &uniforms, "test", mesh, shader);
+        (framebuffer, stats)
+    }
+
+    // Los contadores de `RenderStats`, sin los tiempos
+    fn counts(stats: &RenderStats) -> [u64; 12] {
+        [
+            stats.vertices, stats.triangles_submitted, stats.triangles_rasterized, stats.triangles_invalid,
+            stats.triangles_frustum, stats.triangles_backface, stats.triangles_near, stats.triangles_clipped,
+            stats.fragments_emitted, stats.fragments_shaded, stats.fragments_discarded, stats.fragments_depth_rejected,
+        ]
+    }
+
+    #[test]
+    fn nan_vertices_drop_their_triangles_only() {
+        let sphere = SphereMesh::Uv { stacks: 8, slices: 16 }.vertices();
+        // Uno de cada siete triángulos con un vértice NaN o infinito
+        let poisoned = |triangle: usize| triangle % 7 == 3;
+        let mut broken = sphere.clone();
+        for (triangle, vertices) in broken.chunks_mut(3).enumerate().filter(|(triangle, _)| poisoned(*triangle)) {
+            vertices[triangle % 3].position.x = if triangle % 2 == 0 { f32::NAN } else { f32::INFINITY };
+        }
+        let healthy: Vec<Vertex> = sphere
+            .chunks(3)
+            .enumerate()
+            .filter(|(triangle, _)| !poisoned(*triangle))
+            .flat_map(|(_, vertices)| vertices.to_vec())
+            .collect();
+
+        let (expected, expected_stats) = render_sphere(&healthy, ShaderKind::Default, 1);
+        let (frame, stats) = render_sphere(&broken, ShaderKind::Default, 1);
+        assert_eq!(stats.triangles_invalid, (0..sphere.len() / 3).filter(|&t| poisoned(t)).count() as u64);
+        assert_eq!(expected_stats.triangles_invalid, 0);
+        assert!(expected.buffer.iter().any(|&pixel| pixel != 0), "la esfera sana no dibujó nada");
+        // El resto de la esfera sale igual, sin manchas de los triángulos rotos
+        assert_eq!(frame.buffer, expected.buffer);
+        assert_eq!(frame.zbuffer, expected.zbuffer);
+    }
+
+    #[test]
+    fn threaded_shading_matches_a_single_thread() {
+        let sphere = SphereMesh::Uv { stacks: 16, slices: 32 }.vertices();
+        // 7 hilos no dividen las 60 filas: franjas de 9 y la última de 6
+        let (expected, expected_stats) = render_sphere(&sphere, ShaderKind::Sun, 1);
+        let (frame, stats) = render_sphere(&sphere, ShaderKind::Sun, 7);
+        assert!(expected.emission.iter().any(|&pixel| pixel != 0), "el sol no emitió nada");
+        assert_eq!(frame.buffer, expected.buffer);
+        assert_eq!(frame.zbuffer, expected.zbuffer);
+        assert_eq!(frame.emission, expected.emission);
+        assert_eq!(counts(&stats), counts(&expected_stats));
+    }
+}
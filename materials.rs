@@ -0,0 +1,74 @@
+use crate::cli::key_values;
+use crate::shaders::ShaderKind;
+
+// Cómo se combina un fragmento con lo que ya hay en el framebuffer
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendMode {
+    // Prueba y escribe la profundidad; reemplaza el color
+    Opaque,
+    // Mezcla src_alpha * src + (1 - src_alpha) * dst con la opacidad del color del shader;
+    // la profundidad se prueba siempre y se escribe solo si se pide
+    Alpha { write_depth: bool },
+    // Prueba la profundidad sin escribirla y suma su color: brillos de motores, luces
+    Additive,
+}
+
+impl BlendMode {
+    // Orden de dibujo: lo opaco primero, después lo transparente y al final lo aditivo
+    pub fn draw_order(self) -> u8 {
+        match self {
+            BlendMode::Opaque => 0,
+            BlendMode::Alpha { .. } => 1,
+            BlendMode::Additive => 2,
+        }
+    }
+}
+
+// Con qué se dibuja un grupo de triángulos
+#[derive(Clone, Copy, Debug)]
+pub struct Material {
+    pub shader: ShaderKind,
+    pub blend: BlendMode,
+}
+
+impl Material {
+    pub fn opaque(shader: ShaderKind) -> Self {
+        Material { shader, blend: BlendMode::Opaque }
+    }
+}
+
+// Shader y mezcla de cada material (`usemtl`) del modelo del planeta
+#[derive(Default)]
+pub struct MaterialMap {
+    entries: Vec<(String, Material)>,
+}
+
+impl MaterialMap {
+    // `--materials hull=moon window=crystal glow=sun:additive`; un material sin entrada usa el
+    // shader del modelo. Las mezclas son opaque, alpha, alpha+depth y additive.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let Some(pairs) = key_values(args, "--materials") else {
+            return Ok(MaterialMap::default());
+        };
+
+        let mut entries = Vec::new();
+        for (name, value) in pairs {
+            let (shader, blend) = value.split_once(':').unwrap_or((value.as_str(), "opaque"));
+            let shader = ShaderKind::parse(shader).ok_or_else(|| format!("Shader desconocido '{}' para el material '{}'", shader, name))?;
+            let blend = match blend {
+                "opaque" => BlendMode::Opaque,
+                "alpha" => BlendMode::Alpha { write_depth: false },
+                "alpha+depth" => BlendMode::Alpha { write_depth: true },
+                "additive" => BlendMode::Additive,
+                other => return Err(format!("Mezcla desconocida '{}' para el material '{}' (opaque, alpha, alpha+depth o additive)", other, name)),
+            };
+            entries.push((name, Material { shader, blend }));
+        }
+        Ok(MaterialMap { entries })
+    }
+
+    pub fn get(&self, name: Option<&str>) -> Option<Material> {
+        let name = name?;
+        self.entries.iter().find(|(entry, _)| entry == name).map(|(_, material)| *material)
+    }
+}
@@ -0,0 +1,138 @@
+use nalgebra_glm::{Mat4, Vec2, Vec4};
+use std::any::Any;
+use crate::frame_graph::{PassIo, PassTargets, TargetDesc, SCENE_COLOR, SCENE_DEPTH};
+use crate::framebuffer::Framebuffer;
+use crate::post::PostPass;
+use crate::Uniforms;
+
+// Desenfoque de movimiento en espacio de pantalla.
+// Cada píxel cubierto se reproyecta con la MVP del cuadro anterior para obtener su velocidad,
+// así que cubre tanto el movimiento del modelo como la rotación/traslación de la cámara.
+pub struct MotionBlur {
+    pub enabled: bool,
+    pub samples: usize,
+    pub max_radius: f32,
+    previous_mvp: Option<Mat4>,
+}
+
+pub const MOTION_BLURRED: TargetDesc = TargetDesc::color("motion_blurred");
+// Copia de la imagen sin desenfocar de la que se toman las muestras
+const SOURCE: TargetDesc = TargetDesc::color("motion_blur.source");
+
+impl MotionBlur {
+    pub fn new() -> Self {
+        MotionBlur {
+            enabled: false,
+            samples: 8,
+            max_radius: 24.0,
+            previous_mvp: None,
+        }
+    }
+
+    fn reproject(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, targets: &mut PassTargets) {
+        let mvp = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix;
+        // Se guarda la MVP aunque el efecto esté apagado, para que al activarlo no haya un salto
+        let previous_mvp = self.previous_mvp.replace(mvp);
+
+        let previous_mvp = match previous_mvp {
+            Some(previous) if self.enabled && self.samples > 1 => previous,
+            _ => return,
+        };
+
+        let (inverse_mvp, inverse_viewport) = match (mvp.try_inverse(), uniforms.viewport_matrix.try_inverse()) {
+            (Some(mvp), Some(viewport)) => (mvp, viewport),
+            _ => return,
+        };
+
+        let Some(source) = targets.scratch(SOURCE.name) else {
+            return;
+        };
+        source.copy_from_slice(&framebuffer.buffer);
+
+        for y in 0..framebuffer.height {
+            for x in 0..framebuffer.width {
+                let index = y * framebuffer.width + x;
+                let depth = framebuffer.zbuffer[index];
+                if !depth.is_finite() {
+                    continue;
+                }
+
+                let screen = Vec4::new(x as f32 + 0.5, y as f32 + 0.5, depth, 1.0);
+                let object = inverse_mvp * (inverse_viewport * screen);
+                if object.w.abs() < f32::EPSILON {
+                    continue;
+                }
+
+                let previous_clip = previous_mvp * (object / object.w);
+                if previous_clip.w <= 0.0 {
+                    continue;
+                }
+                let previous_screen = uniforms.viewport_matrix * (previous_clip / previous_clip.w);
+
+                let mut velocity = Vec2::new(screen.x - previous_screen.x, screen.y - previous_screen.y);
+                let speed = velocity.magnitude();
+                if speed < 0.5 {
+                    continue;
+                }
+                if speed > self.max_radius {
+                    velocity *= self.max_radius / speed;
+                }
+
+                framebuffer.buffer[index] = self.sample_along(source, framebuffer, x as f32 + 0.5, y as f32 + 0.5, velocity);
+            }
+        }
+    }
+
+    // Promedia `samples` lecturas centradas en el píxel a lo largo del vector de velocidad
+    fn sample_along(&self, source: &[u32], framebuffer: &Framebuffer, x: f32, y: f32, velocity: Vec2) -> u32 {
+        let (mut r, mut g, mut b) = (0u32, 0u32, 0u32);
+        let mut count = 0;
+
+        for i in 0..self.samples {
+            let t = i as f32 / (self.samples - 1) as f32 - 0.5;
+            let sx = (x + velocity.x * t).floor();
+            let sy = (y + velocity.y * t).floor();
+            if sx < 0.0 || sy < 0.0 || sx >= framebuffer.width as f32 || sy >= framebuffer.height as f32 {
+                continue;
+            }
+
+            let color = source[sy as usize * framebuffer.width + sx as usize];
+            r += (color >> 16) & 0xFF;
+            g += (color >> 8) & 0xFF;
+            b += color & 0xFF;
+            count += 1;
+        }
+
+        if count == 0 {
+            return source[y as usize * framebuffer.width + x as usize];
+        }
+        ((r / count) << 16) | ((g / count) << 8) | (b / count)
+    }
+}
+
+
+impl PostPass for MotionBlur {
+    fn name(&self) -> &'static str {
+        "motion_blur"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn io(&self) -> PassIo {
+        PassIo { reads: vec![SCENE_COLOR, SCENE_DEPTH], writes: vec![MOTION_BLURRED], scratch: vec![SOURCE] }
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, targets: &mut PassTargets) {
+        self.reproject(framebuffer, uniforms, targets);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
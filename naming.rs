@@ -0,0 +1,170 @@
+use crate::cli::arg_value;
+use crate::noise::hash_to_unit;
+use crate::shaders::ShaderKind;
+
+const MYTHIC_START: [&str; 16] = ["Vel", "Tha", "Kor", "Ar", "Myr", "Ely", "Zan", "Ori", "Cal", "Ner", "Lys", "Dra", "Hel", "Ix", "Per", "Sol"];
+const MYTHIC_MIDDLE: [&str; 10] = ["a", "e", "i", "o", "u", "ae", "y", "ar", "en", "or"];
+const MYTHIC_END: [&str; 10] = ["ra", "dos", "nis", "thea", "ron", "lia", "mir", "tus", "phe", "xis"];
+const SURVEYS: [&str; 7] = ["KV", "HD", "GJ", "TOI", "WASP", "KOI", "HIP"];
+
+// Estilo de los nombres; `--names mythic|catalog`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NameStyle {
+    // Sílabas al estilo de la mitología: "Thaenis", y las lunas con romanos: "Thaenis II"
+    Mythic,
+    // Designaciones de catálogo: "KV-2731 b", y las lunas con la letra siguiente: "KV-2731 c"
+    Catalog,
+}
+
+impl NameStyle {
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        match arg_value(args, "--names").as_deref() {
+            None | Some("mythic") => Ok(NameStyle::Mythic),
+            Some("catalog") => Ok(NameStyle::Catalog),
+            Some(other) => Err(format!("Estilo de nombres desconocido '{}' (mythic o catalog)", other)),
+        }
+    }
+}
+
+// Generador de nombres determinista: cada sorteo consume el siguiente valor del flujo de la semilla,
+// así con la misma semilla maestra salen los mismos nombres. Un nombre repetido se vuelve a sortear.
+pub struct Namer {
+    seed: i32,
+    draws: u32,
+    used: Vec<String>,
+}
+
+impl Namer {
+    pub fn new(seed: i32) -> Self {
+        Namer { seed, draws: 0, used: Vec::new() }
+    }
+
+    fn next(&mut self) -> f32 {
+        let value = hash_to_unit(self.draws, self.seed);
+        self.draws += 1;
+        value
+    }
+
+    fn pick<'a>(&mut self, table: &[&'a str]) -> &'a str {
+        table[((self.next() * table.len() as f32) as usize).min(table.len() - 1)]
+    }
+
+    pub fn name(&mut self, style: NameStyle) -> String {
+        loop {
+            let candidate = match style {
+                NameStyle::Mythic => {
+                    let middle = if self.next() < 0.5 { self.pick(&MYTHIC_MIDDLE) } else { "" };
+                    format!("{}{}{}", self.pick(&MYTHIC_START), middle, self.pick(&MYTHIC_END))
+                }
+                NameStyle::Catalog => {
+                    let number = 1000 + (self.next() * 9000.0) as u32;
+                    format!("{}-{} b", self.pick(&SURVEYS), number)
+                }
+            };
+            if let Some(name) = self.claim(candidate) {
+                return name;
+            }
+        }
+    }
+
+    // Nombre de la luna número `index` (desde 0) de `parent`; si ya existe se sortea uno nuevo
+    pub fn moon_name(&mut self, parent: &str, index: usize, style: NameStyle) -> String {
+        let candidate = match style {
+            NameStyle::Mythic => format!("{} {}", parent, roman(index + 1)),
+            NameStyle::Catalog => match parent.strip_suffix(" b") {
+                Some(system) => format!("{} {}", system, (b'c' + (index % 24) as u8) as char),
+                None => format!("{} {}", parent, roman(index + 1)),
+            },
+        };
+        self.claim(candidate).unwrap_or_else(|| self.name(style))
+    }
+
+    fn claim(&mut self, candidate: String) -> Option<String> {
+        if self.used.contains(&candidate) {
+            return None;
+        }
+        self.used.push(candidate.clone());
+        Some(candidate)
+    }
+}
+
+fn roman(mut value: usize) -> String {
+    const NUMERALS: [(usize, &str); 9] = [(100, "C"), (90, "XC"), (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I")];
+    let mut text = String::new();
+    for (amount, numeral) in NUMERALS {
+        while value >= amount {
+            text.push_str(numeral);
+            value -= amount;
+        }
+    }
+    text
+}
+
+// Nombres de los cuerpos de la escena, todos del mismo generador para que no se repitan
+pub struct SceneNames {
+    pub planet: String,
+    pub companion: Option<String>,
+}
+
+impl SceneNames {
+    pub fn generate(seed: i32, style: NameStyle, has_companion: bool) -> Self {
+        let mut namer = Namer::new(seed);
+        let planet = namer.name(style);
+        let companion = has_companion.then(|| namer.moon_name(&planet, 0, style));
+        SceneNames { planet, companion }
+    }
+}
+
+// Lo que se sabe de un cuerpo para describirlo
+pub struct BodyTraits {
+    pub shader: ShaderKind,
+    // Radio en unidades de la escena, ya escalado
+    pub radius: f32,
+    // Distancia al cuerpo alrededor del que gira, si gira alrededor de algo
+    pub orbit_distance: Option<f32>,
+    pub rings: bool,
+    pub atmosphere: bool,
+}
+
+// Una línea armada con las propiedades del cuerpo: "Luna rocosa pequeña en órbita cercana"
+pub fn describe(traits: &BodyTraits) -> String {
+    // (sustantivo, es femenino)
+    let (noun, feminine) = match traits.shader {
+        ShaderKind::Sun | ShaderKind::Cellular => ("Estrella", true),
+        ShaderKind::EarthClouds => ("Planeta templado", false),
+        ShaderKind::GasGiant => ("Gigante gaseoso", false),
+        ShaderKind::Moon => ("Luna rocosa", true),
+        ShaderKind::Europa => ("Luna helada", true),
+        ShaderKind::Crystal => ("Cuerpo cristalino", false),
+        ShaderKind::Noise | ShaderKind::Ripple => ("Mundo activo", false),
+        _ => ("Cuerpo", false),
+    };
+    let size = match (traits.radius, feminine) {
+        (r, false) if r < 0.3 => "pequeño",
+        (r, true) if r < 0.3 => "pequeña",
+        (r, false) if r < 0.7 => "mediano",
+        (r, true) if r < 0.7 => "mediana",
+        _ => "grande",
+    };
+
+    let mut text = format!("{} {}", noun, size);
+    if let Some(distance) = traits.orbit_distance {
+        let class = if distance < 1.5 {
+            "cercana"
+        } else if distance < 4.0 {
+            "media"
+        } else {
+            "lejana"
+        };
+        text.push_str(&format!(" en órbita {}", class));
+    }
+
+    let features: Vec<&str> = [(traits.rings, "anillos"), (traits.atmosphere, "atmósfera")]
+        .into_iter()
+        .filter_map(|(present, feature)| present.then_some(feature))
+        .collect();
+    if !features.is_empty() {
+        text.push_str(&format!(" con {}", features.join(" y ")));
+    }
+    text
+}
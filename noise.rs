@@ -0,0 +1,358 @@
+use fastnoise_lite::{FastNoiseLite, FractalType, NoiseType};
+use nalgebra_glm::Vec3;
+use crate::cli::{all_key_values, arg_value};
+use crate::shaders::ShaderKind;
+
+// Fuente de ruido que usan los shaders; permite cambiar de implementación sin tocarlos.
+// noise2 y noise3 devuelven valores en [-1, 1]; hash en [0, 1).
+// Sync: el sombreado en paralelo comparte el mismo generador entre hilos
+pub trait NoiseSource: Sync {
+    fn noise2(&self, x: f32, y: f32) -> f32;
+    fn noise3(&self, x: f32, y: f32, z: f32) -> f32;
+    fn seed(&self) -> i32;
+    // Rasgos por unidad de las coordenadas que recibe, para el nivel de detalle de los shaders
+    fn frequency(&self) -> f32;
+
+    fn hash(&self, value: u32) -> f32 {
+        hash_to_unit(value, self.seed())
+    }
+}
+
+impl NoiseSource for FastNoiseLite {
+    fn noise2(&self, x: f32, y: f32) -> f32 {
+        self.get_noise_2d(x, y)
+    }
+
+    fn noise3(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.get_noise_3d(x, y, z)
+    }
+
+    fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    fn frequency(&self) -> f32 {
+        self.frequency
+    }
+}
+
+// Ruido 3D sobre la posición en espacio de objeto: a diferencia de muestrear solo (x, y) no se
+// refleja de frente hacia atrás ni se estira en los polos. `offset` se suma en espacio de ruido
+// (después de `zoom`) y es lo que anima el patrón.
+pub fn sphere_noise(noise: &dyn NoiseSource, position: &Vec3, zoom: f32, offset: Vec3) -> f32 {
+    let p = position * zoom + offset;
+    noise.noise3(p.x, p.y, p.z)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NoiseBackend {
+    FastNoise,
+    Gradient,
+}
+
+impl NoiseBackend {
+    // `--noise fastnoise` (por defecto) o `--noise gradient`: el ruido general del cuadro. Cada
+    // shader puede elegir otro con `--noise-config shader=<nombre> backend=<ruido>`
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        match arg_value(args, "--noise") {
+            None => Ok(NoiseBackend::FastNoise),
+            Some(name) => NoiseBackend::parse(&name).ok_or_else(|| format!("Ruido desconocido '{}': usa fastnoise o gradient", name)),
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "fastnoise" => Some(NoiseBackend::FastNoise),
+            "gradient" => Some(NoiseBackend::Gradient),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoiseBackend::FastNoise => "fastnoise",
+            NoiseBackend::Gradient => "gradient",
+        }
+    }
+}
+
+// Generador propio de un shader. Sin `seed` usa la semilla maestra del cuadro, así el explorador
+// de semillas también lo varía; con ella queda fijo y sale el mismo planeta en cada corrida.
+#[derive(Clone, Copy, Debug)]
+pub struct NoiseConfig {
+    pub seed: Option<i32>,
+    // `gradient` usa solo la semilla y la frecuencia; el tipo y el fractal son de FastNoiseLite
+    pub backend: NoiseBackend,
+    pub noise_type: NoiseType,
+    // Multiplica al zoom propio de cada shader: con 0.01 sale la escala de siempre
+    pub frequency: f32,
+    // Sin fractal, `fbm` suma octavas y `ridged` las pliega en crestas
+    pub fractal: FractalType,
+    pub fractal_octaves: i32,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+// Los de FastNoiseLite; con un solo campo cambiado el resto se comporta como el ruido de siempre
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        NoiseConfig {
+            seed: None,
+            backend: NoiseBackend::FastNoise,
+            noise_type: NoiseType::OpenSimplex2,
+            frequency: 0.01,
+            fractal: FractalType::None,
+            fractal_octaves: 3,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+}
+
+const NOISE_TYPES: [(&str, NoiseType); 6] = [
+    ("opensimplex2", NoiseType::OpenSimplex2),
+    ("opensimplex2s", NoiseType::OpenSimplex2S),
+    ("perlin", NoiseType::Perlin),
+    ("value", NoiseType::Value),
+    ("valuecubic", NoiseType::ValueCubic),
+    ("cellular", NoiseType::Cellular),
+];
+
+const FRACTAL_TYPES: [(&str, FractalType); 3] = [
+    ("none", FractalType::None),
+    ("fbm", FractalType::FBm),
+    ("ridged", FractalType::Ridged),
+];
+
+impl NoiseConfig {
+    pub fn build(&self, master_seed: i32) -> Box<dyn NoiseSource> {
+        let seed = self.seed.unwrap_or(master_seed);
+        if self.backend == NoiseBackend::Gradient {
+            return Box::new(GradientNoise::with_frequency(seed, self.frequency));
+        }
+        let mut noise = FastNoiseLite::with_seed(seed);
+        noise.set_noise_type(Some(self.noise_type));
+        noise.set_frequency(Some(self.frequency));
+        noise.set_fractal_type(Some(self.fractal));
+        noise.set_fractal_octaves(Some(self.fractal_octaves));
+        noise.set_fractal_lacunarity(Some(self.lacunarity));
+        noise.set_fractal_gain(Some(self.gain));
+        Box::new(noise)
+    }
+}
+
+// Generadores configurados por shader; los que no aparecen usan el ruido general del cuadro
+#[derive(Clone, Default)]
+pub struct NoiseRegistry {
+    entries: Vec<(ShaderKind, NoiseConfig)>,
+}
+
+impl NoiseRegistry {
+    // Uno por shader, repetible:
+    // `--noise-config shader=cellular type=cellular [backend=gradient] [seed=7] [frequency=0.01] [fractal=fbm] [octaves=3] [lacunarity=2] [gain=0.5]`
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut entries: Vec<(ShaderKind, NoiseConfig)> = Vec::new();
+        for pairs in all_key_values(args, "--noise-config") {
+            let mut shader = None;
+            let mut config = NoiseConfig::default();
+            for (key, value) in &pairs {
+                let invalid = || format!("--noise-config: valor inválido para {}: {}", key, value);
+                match key.as_str() {
+                    "shader" => shader = Some(ShaderKind::parse(value).ok_or_else(|| format!("--noise-config: shader desconocido '{}'", value))?),
+                    "seed" => config.seed = Some(value.parse().map_err(|_| invalid())?),
+                    "backend" => config.backend = NoiseBackend::parse(value).ok_or_else(invalid)?,
+                    "type" => config.noise_type = lookup(&NOISE_TYPES, value).ok_or_else(invalid)?,
+                    "frequency" => config.frequency = value.parse().map_err(|_| invalid())?,
+                    "fractal" => config.fractal = lookup(&FRACTAL_TYPES, value).ok_or_else(invalid)?,
+                    "octaves" => config.fractal_octaves = value.parse().ok().filter(|octaves| *octaves > 0).ok_or_else(invalid)?,
+                    "lacunarity" => config.lacunarity = value.parse().map_err(|_| invalid())?,
+                    "gain" => config.gain = value.parse().map_err(|_| invalid())?,
+                    _ => return Err(format!("--noise-config: campo desconocido {}", key)),
+                }
+            }
+            let shader = shader.ok_or("--noise-config necesita shader=<nombre>")?;
+            // El último gana, como con las demás opciones repetidas
+            entries.retain(|(kind, _)| *kind != shader);
+            entries.push((shader, config));
+        }
+        Ok(NoiseRegistry { entries })
+    }
+
+    pub fn get(&self, shader: ShaderKind) -> Option<&NoiseConfig> {
+        self.entries.iter().find(|(kind, _)| *kind == shader).map(|(_, config)| config)
+    }
+
+    // Los generadores de un cuadro con semilla maestra `master_seed` (ver `Uniforms::noise_for`)
+    pub fn build(&self, master_seed: i32) -> Vec<(ShaderKind, Box<dyn NoiseSource>)> {
+        self.entries
+            .iter()
+            .map(|(kind, config)| (*kind, config.build(master_seed)))
+            .collect()
+    }
+}
+
+fn lookup<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table.iter().find(|(entry, _)| *entry == name).map(|(_, value)| *value)
+}
+
+// Misma frecuencia por defecto que FastNoiseLite, para que las escalas de los shaders sirvan igual
+const GRADIENT_FREQUENCY: f32 = 0.01;
+// Llevan el máximo teórico de Perlin (sqrt(N)/2 con gradientes unitarios) a 1
+const SCALE_2D: f32 = std::f32::consts::SQRT_2;
+const SCALE_3D: f32 = 1.154_700_5;
+
+// Ruido de gradiente (Perlin clásico) sin dependencias: los gradientes salen de un hash de la celda
+pub struct GradientNoise {
+    seed: i32,
+    frequency: f32,
+}
+
+impl GradientNoise {
+    pub fn new(seed: i32) -> Self {
+        GradientNoise::with_frequency(seed, GRADIENT_FREQUENCY)
+    }
+
+    pub fn with_frequency(seed: i32, frequency: f32) -> Self {
+        GradientNoise { seed, frequency }
+    }
+
+    fn cell_hash(&self, x: i32, y: i32, z: i32) -> u32 {
+        let h = (x as u32).wrapping_mul(0x8da6_b343)
+            ^ (y as u32).wrapping_mul(0xd816_3841)
+            ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+        mix(h ^ self.seed as u32)
+    }
+
+    fn gradient2(&self, x: i32, y: i32, dx: f32, dy: f32) -> f32 {
+        let angle = (self.cell_hash(x, y, 0) >> 8) as f32 / (1u32 << 24) as f32 * std::f32::consts::TAU;
+        angle.cos() * dx + angle.sin() * dy
+    }
+
+    fn gradient3(&self, x: i32, y: i32, z: i32, dx: f32, dy: f32, dz: f32) -> f32 {
+        // Las 12 aristas de un cubo, normalizadas
+        let (gx, gy, gz) = match self.cell_hash(x, y, z) % 12 {
+            0 => (1.0, 1.0, 0.0),
+            1 => (-1.0, 1.0, 0.0),
+            2 => (1.0, -1.0, 0.0),
+            3 => (-1.0, -1.0, 0.0),
+            4 => (1.0, 0.0, 1.0),
+            5 => (-1.0, 0.0, 1.0),
+            6 => (1.0, 0.0, -1.0),
+            7 => (-1.0, 0.0, -1.0),
+            8 => (0.0, 1.0, 1.0),
+            9 => (0.0, -1.0, 1.0),
+            10 => (0.0, 1.0, -1.0),
+            _ => (0.0, -1.0, -1.0),
+        };
+        (gx * dx + gy * dy + gz * dz) * std::f32::consts::FRAC_1_SQRT_2
+    }
+}
+
+impl NoiseSource for GradientNoise {
+    fn noise2(&self, x: f32, y: f32) -> f32 {
+        let (x, y) = (x * self.frequency, y * self.frequency);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (ix, iy) = (x0 as i32, y0 as i32);
+
+        let (u, v) = (fade(fx), fade(fy));
+        let bottom = lerp(self.gradient2(ix, iy, fx, fy), self.gradient2(ix + 1, iy, fx - 1.0, fy), u);
+        let top = lerp(self.gradient2(ix, iy + 1, fx, fy - 1.0), self.gradient2(ix + 1, iy + 1, fx - 1.0, fy - 1.0), u);
+        (lerp(bottom, top, v) * SCALE_2D).clamp(-1.0, 1.0)
+    }
+
+    fn noise3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (x, y, z) = (x * self.frequency, y * self.frequency, z * self.frequency);
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (fx, fy, fz) = (x - x0, y - y0, z - z0);
+        let (ix, iy, iz) = (x0 as i32, y0 as i32, z0 as i32);
+        let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+
+        let corner = |cx: i32, cy: i32, cz: i32| {
+            self.gradient3(ix + cx, iy + cy, iz + cz, fx - cx as f32, fy - cy as f32, fz - cz as f32)
+        };
+        let near = lerp(lerp(corner(0, 0, 0), corner(1, 0, 0), u), lerp(corner(0, 1, 0), corner(1, 1, 0), u), v);
+        let far = lerp(lerp(corner(0, 0, 1), corner(1, 0, 1), u), lerp(corner(0, 1, 1), corner(1, 1, 1), u), v);
+        (lerp(near, far, w) * SCALE_3D).clamp(-1.0, 1.0)
+    }
+
+    fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    fn frequency(&self) -> f32 {
+        self.frequency
+    }
+}
+
+// Curva de suavizado de Perlin: 6t^5 - 15t^4 + 10t^3
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn mix(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^ (h >> 16)
+}
+
+// Hash barato de un entero a [0, 1), dependiente de la semilla
+pub fn hash_to_unit(value: u32, seed: i32) -> f32 {
+    (mix(value ^ (seed as u32).wrapping_mul(0x9E37_79B9)) >> 8) as f32 / (1u32 << 24) as f32
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: u32 = 20_000;
+
+    // Rango y media sobre muchos puntos repartidos en un cubo de cientos de celdas de ruido
+    fn assert_statistics(noise: &dyn NoiseSource) {
+        let point = |index: u32, channel: u32| hash_to_unit(index * 3 + channel, 99) * 50_000.0 - 25_000.0;
+        let (mut sum2, mut sum3, mut square3) = (0.0f64, 0.0f64, 0.0f64);
+        for index in 0..SAMPLES {
+            let (x, y, z) = (point(index, 0), point(index, 1), point(index, 2));
+            let (value2, value3) = (noise.noise2(x, y), noise.noise3(x, y, z));
+            assert!((-1.0..=1.0).contains(&value2), "noise2({}, {}) = {}", x, y, value2);
+            assert!((-1.0..=1.0).contains(&value3), "noise3({}, {}, {}) = {}", x, y, z, value3);
+            sum2 += value2 as f64;
+            sum3 += value3 as f64;
+            square3 += (value3 * value3) as f64;
+        }
+        let (mean2, mean3) = (sum2 / SAMPLES as f64, sum3 / SAMPLES as f64);
+        assert!(mean2.abs() < 0.02, "media de noise2: {}", mean2);
+        assert!(mean3.abs() < 0.02, "media de noise3: {}", mean3);
+        // Que no sea una constante cerca de 0
+        assert!((square3 / SAMPLES as f64).sqrt() > 0.1);
+    }
+
+    #[test]
+    fn fastnoise_range_and_mean() {
+        assert_statistics(NoiseConfig::default().build(1337).as_ref());
+    }
+
+    #[test]
+    fn gradient_range_and_mean() {
+        assert_statistics(&GradientNoise::new(1337));
+    }
+
+    #[test]
+    fn hash_is_in_unit_interval_and_depends_on_seed() {
+        let noise = GradientNoise::new(7);
+        assert!((0..1000).all(|value| (0.0..1.0).contains(&noise.hash(value))));
+        assert_ne!(noise.hash(3), GradientNoise::new(8).hash(3));
+    }
+
+    #[test]
+    fn config_selects_backend() {
+        let config = NoiseConfig { backend: NoiseBackend::Gradient, frequency: 0.02, ..NoiseConfig::default() };
+        let noise = config.build(5);
+        assert_eq!(noise.noise3(10.0, 20.0, 30.0), GradientNoise::with_frequency(5, 0.02).noise3(10.0, 20.0, 30.0));
+    }
+}
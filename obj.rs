@@ -11,19 +11,24 @@ struct Mesh {
     normals: Vec<Vec3>,
     texcoords: Vec<Vec2>,
     indices: Vec<u32>,
+    // Nombre del material (`usemtl`) de todos sus triángulos, si el .mtl se pudo cargar
+    material: Option<String>,
 }
 
 impl Obj {
     pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
+        let (models, materials) = tobj::load_obj(filename, &tobj::LoadOptions {
             single_index: true,
             triangulate: true,
             ..Default::default()
         })?;
 
+        // Sin .mtl los triángulos quedan sin material y el modelo se dibuja con un solo shader
+        let materials = materials.unwrap_or_default();
         let meshes = models.into_iter().map(|model| {
             let mesh = model.mesh;
             Mesh {
+                material: mesh.material_id.and_then(|id| materials.get(id)).map(|material| material.name.clone()),
                 vertices: mesh.positions.chunks(3)
                     .map(|v| Vec3::new(v[0], v[1], v[2]))
                     .collect(),
@@ -44,19 +49,42 @@ impl Obj {
         let mut vertices = Vec::new();
 
         for mesh in &self.meshes {
-            for &index in &mesh.indices {
-                let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
-                let tex_coords = mesh.texcoords.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec2::new(0.0, 0.0));
-
-                vertices.push(Vertex::new(position, normal, tex_coords));
-            }
+            mesh.push_vertices(&mut vertices);
         }
 
         vertices
     }
+
+    // Los triángulos agrupados por material, en el orden en que aparece cada uno en el archivo.
+    // Una malla sin materiales da un solo grupo sin nombre con todo el arreglo de vértices.
+    pub fn get_material_batches(&self) -> Vec<(Option<String>, Vec<Vertex>)> {
+        let mut batches: Vec<(Option<String>, Vec<Vertex>)> = Vec::new();
+
+        for mesh in &self.meshes {
+            let position = batches.iter().position(|(material, _)| *material == mesh.material);
+            let index = position.unwrap_or_else(|| {
+                batches.push((mesh.material.clone(), Vec::new()));
+                batches.len() - 1
+            });
+            mesh.push_vertices(&mut batches[index].1);
+        }
+
+        batches
+    }
+}
+
+impl Mesh {
+    fn push_vertices(&self, vertices: &mut Vec<Vertex>) {
+        for &index in &self.indices {
+            let position = self.vertices[index as usize];
+            let normal = self.normals.get(index as usize)
+                .cloned()
+                .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+            let tex_coords = self.texcoords.get(index as usize)
+                .cloned()
+                .unwrap_or(Vec2::new(0.0, 0.0));
+
+            vertices.push(Vertex::new(position, normal, tex_coords));
+        }
+    }
 }
@@ -0,0 +1,189 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use minifb::{Key, MouseButton, Window};
+use std::f32::consts::{FRAC_PI_2, TAU};
+use crate::camera::Camera;
+use crate::cli::key_values;
+#[cfg(feature = "overlay")]
+use crate::framebuffer::Framebuffer;
+#[cfg(feature = "overlay")]
+use crate::overlay::{adaptive_backing, draw_text, ADVANCE, LINE_HEIGHT};
+use crate::shaders::smoothstep;
+use crate::spherical::{lat_long_to_dir, tangent_basis};
+use crate::vertex::Vertex;
+
+// Radianes por cuadro que mueven las flechas sobre la superficie, y por píxel arrastrado la mirada
+const WALK_SPEED: f32 = 0.01;
+const LOOK_SPEED: f32 = 0.005;
+// La latitud y la mirada no llegan justo a la vertical, donde el este deja de estar definido
+const POLE_MARGIN: f32 = 0.01;
+const MAX_SPEED: u32 = 256;
+
+const HUD_SCALE: usize = 2;
+const HUD_MARGIN: usize = 10;
+const HUD_PADDING: usize = 6;
+
+// Observador parado en la superficie de un planeta: la cámara va anclada a una latitud y longitud,
+// gira con el cuerpo y mira hacia afuera, así se ve salir el sol y cruzar la luna desde el suelo
+pub struct Observer {
+    pub enabled: bool,
+    pub latitude: f32,  // Radianes
+    pub longitude: f32, // Radianes
+    pub altitude: f32,  // Altura sobre el terreno, en radios del planeta
+    pub yaw: f32,       // Rumbo de la mirada desde el norte hacia el este, en radianes
+    pub pitch: f32,     // Elevación de la mirada sobre el horizonte, en radianes
+    pub day: f32,       // Segundos por vuelta del planeta
+    pub speed: u32,     // Cuánto se acelera el tiempo mientras se observa
+    // Cuerpo de `--solar-system` sobre el que se para; el planeta de siempre fuera de ese modo
+    pub body: String,
+}
+
+// Lo que el cielo de fondo necesita del observador en un cuadro
+#[derive(Clone, Copy)]
+pub struct Sky {
+    // Dirección del sol en espacio de mundo, si hay que dibujar su disco (en `--solar-system` el sol es un cuerpo)
+    pub sun: Option<Vec3>,
+    // Cuánto tapa el cielo de día a las estrellas: 0 de noche o sin aire, 1 con el sol alto
+    pub glare: f32,
+}
+
+impl Observer {
+    // `--observer [lat=20] [long=0] [altitude=0.02] [yaw=90] [pitch=5] [day=40] [speed=8] [body=earth]`
+    // arranca sobre la superficie; los ángulos van en grados
+    pub fn from_args(args: &[String]) -> Self {
+        let pairs = key_values(args, "--observer");
+        let text = |key: &str| pairs.as_ref().and_then(|pairs| pairs.iter().find(|(k, _)| k == key)).map(|(_, v)| v.clone());
+        let value = |key: &str, default: f32| text(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+
+        Observer {
+            enabled: pairs.is_some(),
+            latitude: value("lat", 20.0).to_radians().clamp(-FRAC_PI_2 + POLE_MARGIN, FRAC_PI_2 - POLE_MARGIN),
+            longitude: value("long", 0.0).to_radians(),
+            altitude: value("altitude", 0.02).max(1e-3),
+            yaw: value("yaw", 90.0).to_radians(),
+            pitch: value("pitch", 5.0).to_radians().clamp(-FRAC_PI_2 + POLE_MARGIN, FRAC_PI_2 - POLE_MARGIN),
+            day: value("day", 40.0).max(0.1),
+            speed: value("speed", 8.0).clamp(1.0, MAX_SPEED as f32) as u32,
+            body: text("body").unwrap_or_else(|| "earth".to_string()),
+        }
+    }
+
+    // Ángulo de giro del planeta alrededor de su eje en un instante de la simulación
+    pub fn spin(&self, sim_time: f32) -> f32 {
+        (sim_time / self.day).fract() * TAU
+    }
+
+    // Dirección del observador desde el centro del cuerpo, en espacio de objeto
+    pub fn direction(&self) -> Vec3 {
+        lat_long_to_dir(self.latitude, self.longitude)
+    }
+
+    // Coloca la cámara a `altitude` radios sobre el suelo, que está a `ground` del centro del cuerpo,
+    // mirando según el rumbo y la elevación. `host` es la matriz de modelo del cuerpo en este cuadro.
+    // El plano cercano baja con la altura para que el suelo bajo los pies no se recorte entero.
+    pub fn place(&self, camera: &mut Camera, host: &Mat4, radius: f32, ground: f32) {
+        let up = self.direction();
+        let (east, north) = tangent_basis(&up);
+        let heading = north * self.yaw.cos() + east * self.yaw.sin();
+        let forward = heading * self.pitch.cos() + up * self.pitch.sin();
+        let height = self.altitude * radius;
+
+        let point = |v: Vec3| (host * Vec4::new(v.x, v.y, v.z, 1.0)).xyz();
+        let vector = |v: Vec3| (host * Vec4::new(v.x, v.y, v.z, 0.0)).xyz();
+        camera.eye = point(up * (ground + height));
+        camera.center = camera.eye + vector(forward).normalize();
+        camera.up = vector(up).normalize();
+        camera.near = (vector(up * height).magnitude() * 0.5).max(1e-4);
+        camera.has_changed = true;
+    }
+
+    // Cielo visto desde la cámara ya colocada, con `camera.up` como vertical local. Sin atmósfera
+    // el cielo es negro también de día y las estrellas siguen ahí.
+    pub fn sky(&self, camera: &Camera, light_position: &Vec3, sun_disc: bool, air: bool) -> Sky {
+        let sun = (light_position - camera.eye).normalize();
+        let glare = if air { smoothstep(-0.1, 0.15, sun.dot(&camera.up)) } else { 0.0 };
+        Sky { sun: sun_disc.then_some(sun), glare }
+    }
+
+    // Flechas: caminar en latitud y longitud. Arrastre con el botón izquierdo: mirar alrededor.
+    // Rueda: altura. ";" y "'": más lento o más rápido el paso del día.
+    pub fn handle_input(&mut self, window: &Window, pointer: Option<(f32, f32)>, last_mouse: &mut Option<(f32, f32)>) {
+        let pole = FRAC_PI_2 - POLE_MARGIN;
+        if window.is_key_down(Key::Left) {
+            self.longitude -= WALK_SPEED;
+        }
+        if window.is_key_down(Key::Right) {
+            self.longitude += WALK_SPEED;
+        }
+        if window.is_key_down(Key::Up) {
+            self.latitude = (self.latitude + WALK_SPEED).min(pole);
+        }
+        if window.is_key_down(Key::Down) {
+            self.latitude = (self.latitude - WALK_SPEED).max(-pole);
+        }
+        if window.is_key_pressed(Key::Semicolon, minifb::KeyRepeat::No) {
+            self.speed = (self.speed / 2).max(1);
+        }
+        if window.is_key_pressed(Key::Apostrophe, minifb::KeyRepeat::No) {
+            self.speed = (self.speed * 2).min(MAX_SPEED);
+        }
+
+        let looking = window.get_mouse_down(MouseButton::Left);
+        if let (true, Some((x, y)), Some((last_x, last_y))) = (looking, pointer, *last_mouse) {
+            self.yaw = (self.yaw + (x - last_x) * LOOK_SPEED).rem_euclid(TAU);
+            self.pitch = (self.pitch - (y - last_y) * LOOK_SPEED).clamp(-pole, pole);
+        }
+        *last_mouse = pointer.filter(|_| looking);
+
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            self.altitude = (self.altitude * (-scroll * 0.05).exp()).clamp(1e-3, 1.0);
+        }
+    }
+
+    #[cfg(feature = "overlay")]
+    pub fn draw_hud(&self, framebuffer: &mut Framebuffer, camera: &Camera, light_position: &Vec3, inset: usize) {
+        if !self.enabled {
+            return;
+        }
+        let elevation = (light_position - camera.eye).normalize().dot(&camera.up).clamp(-1.0, 1.0).asin();
+        let lines = [
+            format!("Observador: lat {:.1} long {:.1}", self.latitude.to_degrees(), self.longitude.to_degrees().rem_euclid(360.0)),
+            format!("Sol a {:.1} grados ({}), x{}", elevation.to_degrees(), if elevation > 0.0 { "dia" } else { "noche" }, self.speed),
+        ];
+
+        let line_height = LINE_HEIGHT * HUD_SCALE;
+        let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let width = columns * ADVANCE * HUD_SCALE + 2 * HUD_PADDING;
+        let height = lines.len() * line_height + 2 * HUD_PADDING;
+        let left = HUD_MARGIN;
+        let top = framebuffer.height.saturating_sub(height + HUD_MARGIN + inset);
+        let style = adaptive_backing(framebuffer, left, top, width, height);
+        for (row, line) in lines.iter().enumerate() {
+            draw_text(framebuffer, left + HUD_PADDING, top + HUD_PADDING + row * line_height, line, style.text, HUD_SCALE);
+        }
+    }
+}
+
+// Distancia desde el centro hasta la malla en `direction` (unitaria), en espacio de objeto. Las
+// esferas de los modelos no tienen todos sus vértices al mismo radio: el suelo bajo los pies es
+// el de la cara que cruza el rayo, no el radio medio.
+pub fn surface_distance(vertices: &[Vertex], direction: &Vec3) -> Option<f32> {
+    vertices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            // Möller-Trumbore con el rayo saliendo del origen
+            let (a, b, c) = (triangle[0].position, triangle[1].position, triangle[2].position);
+            let (edge1, edge2) = (b - a, c - a);
+            let p = direction.cross(&edge2);
+            let determinant = edge1.dot(&p);
+            if determinant.abs() < 1e-9 {
+                return None;
+            }
+            let offset = -a;
+            let u = offset.dot(&p) / determinant;
+            let q = offset.cross(&edge1);
+            let v = direction.dot(&q) / determinant;
+            let distance = edge2.dot(&q) / determinant;
+            (u >= 0.0 && v >= 0.0 && u + v <= 1.0 && distance > 0.0).then_some(distance)
+        })
+        .reduce(f32::max)
+}
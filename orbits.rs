@@ -0,0 +1,140 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::cli::key_values;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::gizmo::world_segment;
+use crate::Uniforms;
+
+const ORBIT_SEGMENTS: usize = 96;
+const PRIMARY_ORBIT_COLOR: Color = Color::new(110, 160, 230);
+const SECONDARY_ORBIT_COLOR: Color = Color::new(200, 200, 200);
+const HELIOCENTRIC_COLOR: Color = Color::new(150, 130, 70);
+
+// Planeta doble estilo Plutón–Caronte: los dos cuerpos giran alrededor de un baricentro común,
+// en lados opuestos y con radios inversamente proporcionales a sus masas. El baricentro a su vez
+// puede orbitar el origen (el sol de la escena) como cualquier cuerpo. Todo en el plano XZ.
+pub struct Barycenter {
+    // Masa del secundario dividida por la del primario
+    pub mass_ratio: f32,
+    // Distancia entre los centros de los dos cuerpos
+    pub separation: f32,
+    // Segundos por vuelta alrededor del baricentro
+    pub period: f32,
+    // Radio y período de la órbita del baricentro alrededor del origen; radio 0 lo deja fijo
+    pub orbit_radius: f32,
+    pub orbit_period: f32,
+    pub show_orbits: bool,
+}
+
+impl Barycenter {
+    // `--double-planet ratio=0.12 separation=2 period=15 orbit=0 orbit_period=100`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--double-planet")?;
+        let value = |key: &str, default: f32| {
+            pairs
+                .iter()
+                .find(|(k, _)| k == key)
+                .and_then(|(_, v)| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Some(Barycenter {
+            mass_ratio: value("ratio", 0.12).max(1e-3),
+            separation: value("separation", 2.0).max(0.0),
+            period: value("period", 15.0).max(0.1),
+            orbit_radius: value("orbit", 0.0).max(0.0),
+            orbit_period: value("orbit_period", 100.0).max(0.1),
+            show_orbits: true,
+        })
+    }
+
+    // Posición del baricentro en su órbita alrededor del origen
+    pub fn node(&self, time: f32) -> Vec3 {
+        let angle = time / self.orbit_period * 2.0 * PI;
+        Vec3::new(angle.cos(), 0.0, angle.sin()) * self.orbit_radius
+    }
+
+    // Radios de las órbitas del primario y del secundario: m1 * r1 = m2 * r2 y r1 + r2 = separación
+    pub fn radii(&self) -> (f32, f32) {
+        let total = 1.0 + self.mass_ratio;
+        (self.separation * self.mass_ratio / total, self.separation / total)
+    }
+
+    // Centros del primario y del secundario, siempre a 180° uno del otro
+    pub fn positions(&self, time: f32) -> (Vec3, Vec3) {
+        let node = self.node(time);
+        let (primary_radius, secondary_radius) = self.radii();
+        let angle = time / self.period * 2.0 * PI;
+        let direction = Vec3::new(angle.cos(), 0.0, angle.sin());
+        (node - direction * primary_radius, node + direction * secondary_radius)
+    }
+
+    // Escala del secundario respecto del primario, suponiendo la misma densidad
+    pub fn secondary_scale(&self) -> f32 {
+        self.mass_ratio.cbrt()
+    }
+
+    // Las dos órbitas pequeñas alrededor del baricentro y la trayectoria del baricentro
+    pub fn draw_orbits(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, time: f32) {
+        if !self.show_orbits {
+            return;
+        }
+
+        let node = self.node(time);
+        let (primary_radius, secondary_radius) = self.radii();
+        draw_circle(framebuffer, uniforms, &node, primary_radius, PRIMARY_ORBIT_COLOR);
+        draw_circle(framebuffer, uniforms, &node, secondary_radius, SECONDARY_ORBIT_COLOR);
+        if self.orbit_radius > 0.0 {
+            draw_circle(framebuffer, uniforms, &Vec3::zeros(), self.orbit_radius, HELIOCENTRIC_COLOR);
+        }
+    }
+}
+
+fn draw_circle(framebuffer: &mut Framebuffer, uniforms: &Uniforms, center: &Vec3, radius: f32, color: Color) {
+    let point = |i: usize| {
+        let angle = i as f32 / ORBIT_SEGMENTS as f32 * 2.0 * PI;
+        center + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius
+    };
+    for i in 0..ORBIT_SEGMENTS {
+        world_segment(framebuffer, uniforms, &point(i), &point(i + 1), color);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bodies_stay_opposed_around_the_barycenter() {
+        // Con el baricentro quieto en el origen y con el baricentro en órbita
+        for orbit_radius in [0.0, 6.0] {
+            let system = Barycenter {
+                mass_ratio: 0.12,
+                separation: 2.0,
+                period: 15.0,
+                orbit_radius,
+                orbit_period: 100.0,
+                show_orbits: false,
+            };
+            assert_bodies_opposed(&system);
+        }
+    }
+
+    fn assert_bodies_opposed(system: &Barycenter) {
+        for step in 0..600 {
+            let time = step as f32 * 0.25;
+            let (primary, secondary) = system.positions(time);
+            let node = system.node(time);
+
+            // De lados opuestos del nodo, a la separación pedida
+            let (to_primary, to_secondary) = (primary - node, secondary - node);
+            assert!((to_primary.normalize() + to_secondary.normalize()).magnitude() < 1e-4, "t = {}", time);
+            assert!(((secondary - primary).magnitude() - system.separation).abs() < 1e-4);
+
+            // La posición ponderada por masa es el baricentro, esté donde esté su órbita
+            let weighted = (primary + secondary * system.mass_ratio) / (1.0 + system.mass_ratio);
+            assert!((weighted - node).magnitude() < 1e-4, "t = {}: {:?} vs {:?}", time, weighted, node);
+            assert!((node.magnitude() - system.orbit_radius).abs() < 1e-4);
+        }
+    }
+}
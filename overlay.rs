@@ -0,0 +1,169 @@
+use crate::framebuffer::Framebuffer;
+
+// Primitivas 2D para overlays: rectángulos y texto con una fuente de mapa de bits de 5x7.
+// Todo se dibuja encima de la escena, sin prueba de profundidad.
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+// Avance horizontal y alto de línea (en píxeles, antes de escalar)
+pub const ADVANCE: usize = GLYPH_WIDTH + 1;
+pub const LINE_HEIGHT: usize = GLYPH_HEIGHT + 2;
+
+// Filas de 5 bits, el bit más significativo es la columna izquierda.
+// Las minúsculas se dibujan como mayúsculas; los caracteres sin glifo como un recuadro.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0; GLYPH_HEIGHT],
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        '/' => [0b00001, 0b00010, 0b00010, 0b00100, 0b01000, 0b01000, 0b10000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        '?' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        '=' => [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000],
+        '%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+        '[' => [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110],
+        ']' => [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110],
+        '<' => [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010],
+        '>' => [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000],
+        '*' => [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000],
+        '\'' => [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '#' => [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010],
+        '|' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        _ => [0b11111, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11111],
+    }
+}
+
+// Rellena mezclando `color` con lo que ya hay debajo; `alpha` 1 equivale a `Framebuffer::fill_rect`
+pub fn blend_rect(framebuffer: &mut Framebuffer, x: usize, y: usize, width: usize, height: usize, color: u32, alpha: f32) {
+    let alpha = alpha.clamp(0.0, 1.0);
+    for py in y..(y + height).min(framebuffer.height) {
+        for px in x..(x + width).min(framebuffer.width) {
+            let index = py * framebuffer.width + px;
+            let under = framebuffer.buffer[index];
+            let channel = |shift: u32| {
+                let below = ((under >> shift) & 0xFF) as f32;
+                let above = ((color >> shift) & 0xFF) as f32;
+                ((below + (above - below) * alpha).round() as u32) << shift
+            };
+            framebuffer.buffer[index] = channel(16) | channel(8) | channel(0);
+        }
+    }
+}
+
+// Luma Rec. 709 de un color empaquetado 0xRRGGBB, en [0, 1]
+pub fn luminance(color: u32) -> f32 {
+    let r = ((color >> 16) & 0xFF) as f32;
+    let g = ((color >> 8) & 0xFF) as f32;
+    let b = (color & 0xFF) as f32;
+    (0.2126 * r + 0.7152 * g + 0.0722 * b) / 255.0
+}
+
+// Luma media de una región, muestreando uno de cada dos píxeles en cada eje
+pub fn region_luminance(framebuffer: &Framebuffer, x: usize, y: usize, width: usize, height: usize) -> f32 {
+    let mut total = 0.0;
+    let mut samples = 0;
+    for py in (y..(y + height).min(framebuffer.height)).step_by(2) {
+        for px in (x..(x + width).min(framebuffer.width)).step_by(2) {
+            total += luminance(framebuffer.buffer[py * framebuffer.width + px]);
+            samples += 1;
+        }
+    }
+    if samples == 0 { 0.0 } else { total / samples as f32 }
+}
+
+// Colores con los que escribir sobre un fondo recién adaptado
+pub struct OverlayStyle {
+    pub text: u32,
+    pub accent: u32,
+}
+
+// Opacidad del fondo: casi transparente si lo de abajo ya contrasta con el texto, casi opaco en tonos medios
+const MIN_BACKING_ALPHA: f32 = 0.3;
+const MAX_BACKING_ALPHA: f32 = 0.9;
+
+// Todo texto de HUD pasa por aquí antes de dibujarse: mide lo que va a tapar (la imagen todavía
+// sin este overlay) y elige texto claro sobre fondo oscuro o al revés, con un fondo translúcido
+// tanto más opaco cuanto más cerca está la región de un gris medio, donde ninguno de los dos contrasta
+pub fn adaptive_backing(framebuffer: &mut Framebuffer, x: usize, y: usize, width: usize, height: usize) -> OverlayStyle {
+    let background = region_luminance(framebuffer, x, y, width, height);
+    backing_over(framebuffer, x, y, width, height, background)
+}
+
+// Lo mismo con la luminancia `background` ya medida, para quien pinta encima antes del texto
+pub fn backing_over(framebuffer: &mut Framebuffer, x: usize, y: usize, width: usize, height: usize, background: f32) -> OverlayStyle {
+    let contrast_need = 1.0 - ((background - 0.5).abs() * 2.0).min(1.0);
+    let alpha = MIN_BACKING_ALPHA + (MAX_BACKING_ALPHA - MIN_BACKING_ALPHA) * contrast_need;
+
+    let (backing, style) = if background > 0.5 {
+        (0xF0F0F0, OverlayStyle { text: 0x141420, accent: 0x8A5A00 })
+    } else {
+        (0x15152A, OverlayStyle { text: 0xE0E0E0, accent: 0xFFD700 })
+    };
+    blend_rect(framebuffer, x, y, width, height, backing, alpha);
+    style
+}
+
+// Dibuja `text` con la esquina superior izquierda en (x, y); '\n' salta de línea
+pub fn draw_text(framebuffer: &mut Framebuffer, x: usize, y: usize, text: &str, color: u32, scale: usize) {
+    let mut cursor_x = x;
+    let mut cursor_y = y;
+
+    for c in text.chars() {
+        if c == '\n' {
+            cursor_x = x;
+            cursor_y += LINE_HEIGHT * scale;
+            continue;
+        }
+
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    framebuffer.fill_rect(cursor_x + column * scale, cursor_y + row * scale, scale, scale, color);
+                }
+            }
+        }
+        cursor_x += ADVANCE * scale;
+    }
+}
\ No newline at end of file
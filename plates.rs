@@ -0,0 +1,139 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::TAU;
+use crate::animation::params;
+use crate::cli::key_values;
+use crate::noise::{sphere_noise, NoiseSource};
+use crate::spherical::tangent_basis;
+
+// Escala del ruido que ondula los bordes de las placas, para que no sean arcos perfectos
+const WARP_ZOOM: f32 = 300.0;
+
+// Tectónica de placas de la Tierra: celdas de Voronoi sobre la esfera, cada una con su deriva.
+// Donde dos placas chocan se levanta una cordillera a lo largo del borde; donde se separan, una fosa.
+#[derive(Clone, Copy, Debug)]
+pub struct Plates {
+    pub count: usize,
+    pub drift: f32,  // Velocidad de deriva; solo importa la relativa entre vecinas
+    pub height: f32, // Altura de una cordillera entre dos placas que chocan de frente
+    pub width: f32,  // Media anchura de las cordilleras y fosas, en unidades de la esfera unitaria
+    pub crust: f32,  // Cuánto sube una placa continental y baja una oceánica respecto del ruido
+    pub warp: f32,   // Cuánto ondula el ruido los bordes
+}
+
+impl Default for Plates {
+    fn default() -> Self {
+        Plates { count: 12, drift: 1.0, height: 1.0, width: 0.1, crust: 0.25, warp: 0.08 }
+    }
+}
+
+impl Plates {
+    // `--plates [count=12] [drift=1] [height=1] [width=0.1] [crust=0.25] [warp=0.08]`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--plates")?;
+        let defaults = Plates::default();
+        let value = |key: &str, default: f32| pairs.iter().find(|(k, _)| k == key).and_then(|(_, v)| v.parse().ok()).unwrap_or(default);
+
+        Some(Plates {
+            count: value("count", defaults.count as f32).max(2.0) as usize,
+            drift: value("drift", defaults.drift).max(0.0),
+            height: value("height", defaults.height),
+            width: value("width", defaults.width).max(1e-3),
+            crust: value("crust", defaults.crust),
+            warp: value("warp", defaults.warp).max(0.0),
+        })
+    }
+
+    // Las placas de la semilla de `noise`: la misma semilla da siempre los mismos continentes
+    pub fn generate(&self, noise: &dyn NoiseSource) -> PlateField {
+        let random = |plate: usize, channel: u32| noise.hash(plate as u32 * 8 + channel);
+        let plates = (0..self.count)
+            .map(|plate| {
+                // Uniforme sobre la esfera: altura y ángulo uniformes
+                let y = random(plate, 0) * 2.0 - 1.0;
+                let angle = random(plate, 1) * TAU;
+                let ring = (1.0 - y * y).sqrt();
+                let center = Vec3::new(ring * angle.cos(), y, ring * angle.sin());
+
+                let (east, north) = tangent_basis(&center);
+                let heading = random(plate, 2) * TAU;
+                let speed = self.drift * (0.5 + 0.5 * random(plate, 3));
+                Plate {
+                    center,
+                    drift: (east * heading.cos() + north * heading.sin()) * speed,
+                    continental: random(plate, 4) < 0.5,
+                }
+            })
+            .collect();
+        PlateField { plates, params: *self }
+    }
+}
+
+params!(Plates { drift, height, width, crust, warp });
+
+struct Plate {
+    center: Vec3,
+    // Tangente a la esfera en el centro de la placa
+    drift: Vec3,
+    continental: bool,
+}
+
+// Placas ya generadas para una semilla, listas para evaluar en cada punto
+pub struct PlateField {
+    plates: Vec<Plate>,
+    params: Plates,
+}
+
+impl PlateField {
+    // Lo que las placas suman a la elevación en una posición en espacio de objeto
+    pub fn elevation(&self, noise: &dyn NoiseSource, position: &Vec3) -> f32 {
+        let params = &self.params;
+        let direction = position.normalize();
+        let warp = Vec3::new(
+            sphere_noise(noise, &direction, WARP_ZOOM, Vec3::new(31.0, 0.0, 0.0)),
+            sphere_noise(noise, &direction, WARP_ZOOM, Vec3::new(0.0, 47.0, 0.0)),
+            sphere_noise(noise, &direction, WARP_ZOOM, Vec3::new(0.0, 0.0, 59.0)),
+        );
+        let direction = (direction + warp * params.warp).normalize();
+
+        // La placa del punto y la vecina más cercana; en la esfera el borde entre dos celdas es
+        // el plano bisector de sus centros
+        let (mut first, mut second) = (None, None);
+        for (index, plate) in self.plates.iter().enumerate() {
+            let distance = (plate.center - direction).magnitude_squared();
+            match first {
+                Some((_, best)) if distance >= best => {
+                    if second.is_none_or(|(_, runner_up)| distance < runner_up) {
+                        second = Some((index, distance));
+                    }
+                }
+                _ => {
+                    second = first;
+                    first = Some((index, distance));
+                }
+            }
+        }
+        let (Some((own, _)), Some((neighbor, _))) = (first, second) else {
+            return 0.0;
+        };
+        let (own, neighbor) = (&self.plates[own], &self.plates[neighbor]);
+
+        let across = neighbor.center - own.center;
+        let Some(boundary_normal) = across.try_normalize(1e-6) else {
+            return 0.0;
+        };
+        let distance = direction.dot(&-boundary_normal).max(0.0);
+
+        // Convergencia: cuánto se acerca la placa propia a la vecina a lo largo de la normal del
+        // borde, normalizada para que un choque de frente a toda velocidad valga 1
+        let tangent_normal = (boundary_normal - direction * boundary_normal.dot(&direction)).try_normalize(1e-6).unwrap_or(boundary_normal);
+        let convergence = (own.drift - neighbor.drift).dot(&tangent_normal) / (2.0 * params.drift).max(1e-6);
+        let ridge = (-(distance / params.width).powi(2)).exp();
+
+        // Corteza: continental arriba, oceánica abajo; en el borde se promedia con la vecina
+        let crust = |plate: &Plate| if plate.continental { params.crust } else { -params.crust };
+        let blend = 0.5 + 0.5 * (distance / params.width).min(1.0);
+        let base = crust(neighbor) + (crust(own) - crust(neighbor)) * blend;
+
+        base + params.height * convergence.clamp(-1.0, 1.0) * ridge
+    }
+}
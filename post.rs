@@ -0,0 +1,80 @@
+use std::any::Any;
+use crate::frame_graph::{FramePlan, FrameTargets, PassIo, PassTargets};
+use crate::framebuffer::Framebuffer;
+use crate::readback::Readback;
+use crate::Uniforms;
+
+// Una pasada de post-proceso sobre el framebuffer ya renderizado
+pub trait PostPass {
+    fn name(&self) -> &'static str;
+    fn is_enabled(&self) -> bool;
+    fn set_enabled(&mut self, enabled: bool);
+    // Objetivos que lee y escribe; el grafo ordena las pasadas y les asigna buffers a partir de esto
+    fn io(&self) -> PassIo;
+    // Se llama cada cuadro aunque esté desactivada, para que la pasada pueda guardar su historial
+    fn apply(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, targets: &mut PassTargets);
+    // Permite ajustar los parámetros de una pasada concreta desde el bucle principal
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+// Pasadas de post-proceso ordenadas por sus dependencias (ver `FramePlan`); puede estar vacía,
+// en cuyo caso no hace nada
+pub struct PostChain {
+    passes: Vec<Box<dyn PostPass>>,
+    plan: Option<FramePlan>,
+    targets: FrameTargets,
+}
+
+impl PostChain {
+    pub fn new() -> Self {
+        PostChain { passes: Vec::new(), plan: None, targets: FrameTargets::default() }
+    }
+
+    pub fn push(&mut self, pass: Box<dyn PostPass>) {
+        self.passes.push(pass);
+        self.plan = None;
+    }
+
+    // Valida el grafo de las pasadas agregadas; `run` no hace nada hasta que se compile sin errores
+    pub fn compile(&mut self) -> Result<&FramePlan, String> {
+        let passes: Vec<_> = self.passes.iter().map(|pass| (pass.name(), pass.io())).collect();
+        Ok(self.plan.insert(FramePlan::compile(&passes)?))
+    }
+
+    // Alterna una pasada por nombre y devuelve su nuevo estado
+    pub fn toggle(&mut self, name: &str) -> Option<bool> {
+        let pass = self.passes.iter_mut().find(|pass| pass.name() == name)?;
+        let enabled = !pass.is_enabled();
+        pass.set_enabled(enabled);
+        Some(enabled)
+    }
+
+    pub fn get_mut<T: PostPass + 'static>(&mut self) -> Option<&mut T> {
+        self.passes.iter_mut().find_map(|pass| pass.as_any_mut().downcast_mut::<T>())
+    }
+
+    // Con `readback`, cada objetivo que escribe una pasada se copia apenas ella termina
+    pub fn run(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, mut readback: Option<&mut Readback>) {
+        let Some(plan) = &self.plan else {
+            return;
+        };
+        self.targets.prepare(plan, framebuffer);
+        for &pass in plan.order() {
+            self.targets.begin(plan, pass, framebuffer);
+            let mut targets = PassTargets { plan, targets: &mut self.targets };
+            self.passes[pass].apply(framebuffer, uniforms, &mut targets);
+            self.targets.end(plan, pass, framebuffer);
+
+            if let Some(readback) = readback.as_deref_mut() {
+                let (name, enabled, io) = (self.passes[pass].name(), self.passes[pass].is_enabled(), self.passes[pass].io());
+                for target in io.writes.iter().chain(&io.scratch) {
+                    if let Some(pixels) = self.targets.contents(plan, target.name, framebuffer) {
+                        let (width, height) = (framebuffer.width.div_ceil(target.scale), framebuffer.height.div_ceil(target.scale));
+                        readback.color(target.name, name, enabled, width, height, pixels);
+                    }
+                }
+            }
+        }
+        self.targets.finish(plan, framebuffer);
+    }
+}
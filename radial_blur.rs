@@ -0,0 +1,140 @@
+use nalgebra_glm::Vec3;
+use std::any::Any;
+use crate::bloom::BLOOMED;
+use crate::frame_graph::{PassIo, PassTargets, TargetDesc, SCENE_DEPTH};
+use crate::framebuffer::Framebuffer;
+use crate::half_resolution::{linear_depth, HalfResolution};
+use crate::post::PostPass;
+use crate::Uniforms;
+
+const SAMPLES: usize = 12;
+// Hasta esta intensidad la versión reducida se mezcla de a poco con la imagen original, así el
+// comienzo de la transición no salta de golpe a media resolución
+const FADE_IN: f32 = 0.1;
+
+pub const HYPERSPACE: TargetDesc = TargetDesc::color("hyperspace");
+
+// Desenfoque radial de "hiperespacio": cada píxel promedia muestras en la línea hacia el punto focal,
+// sobre la imagen a media resolución que vuelve con el reescalado bilateral de la profundidad de campo.
+// `strength` (0..1) la fija el bucle principal siguiendo la transición de cámara; con 0 no toca la imagen.
+pub struct RadialBlur {
+    pub enabled: bool,
+    pub strength: f32,
+    // Punto focal en píxeles del framebuffer (el destino de la transición proyectado)
+    pub focal_point: (f32, f32),
+    // Fracción de la distancia al punto focal que cubre el desenfoque con strength = 1
+    pub max_length: f32,
+    // Corrimiento al azul con strength = 1
+    pub blue_shift: f32,
+}
+
+impl RadialBlur {
+    pub fn new() -> Self {
+        RadialBlur {
+            enabled: true,
+            strength: 0.0,
+            focal_point: (0.0, 0.0),
+            max_length: 0.3,
+            blue_shift: 0.2,
+        }
+    }
+}
+
+impl PostPass for RadialBlur {
+    fn name(&self) -> &'static str {
+        "radial_blur"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn io(&self) -> PassIo {
+        PassIo { reads: vec![BLOOMED, SCENE_DEPTH], writes: vec![HYPERSPACE], scratch: Vec::new() }
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, _targets: &mut PassTargets) {
+        let strength = self.strength.clamp(0.0, 1.0);
+        if !self.enabled || strength <= 0.0 {
+            return;
+        }
+        let Some(depth) = linear_depth(framebuffer, uniforms) else {
+            return;
+        };
+
+        // Las muestras se toman a media resolución, con el punto focal en esa escala
+        let half = HalfResolution::downsample(framebuffer, &depth);
+        let (width, height) = (half.width, half.height);
+        let (focal_x, focal_y) = (self.focal_point.0 * 0.5, self.focal_point.1 * 0.5);
+        let length = self.max_length * strength;
+        let shift = self.blue_shift * strength;
+        let tint = Vec3::new(1.0 - shift, 1.0 - shift * 0.5, 1.0 + shift);
+
+        let mut streaked = vec![Vec3::zeros(); width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (focal_x - x as f32, focal_y - y as f32);
+                let mut sum = Vec3::zeros();
+                for sample in 0..SAMPLES {
+                    let t = sample as f32 / SAMPLES as f32 * length;
+                    let sx = (x as f32 + dx * t).round().clamp(0.0, (width - 1) as f32) as usize;
+                    let sy = (y as f32 + dy * t).round().clamp(0.0, (height - 1) as f32) as usize;
+                    sum += half.color[sy * width + sx];
+                }
+                streaked[y * width + x] = (sum / SAMPLES as f32).component_mul(&tint);
+            }
+        }
+
+        let amount = (strength / FADE_IN).min(1.0);
+        half.upsample(&streaked, framebuffer, &depth, |_| amount);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::{build_post_chain, create_cloud_noise, create_uniforms, DEFAULT_SEED};
+
+    // Franjas verticales a media luz, para que el desenfoque tenga algo que mezclar
+    fn blurred(strength: f32) -> (Vec<u32>, Vec<u32>) {
+        let (width, height) = (64, 48);
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 2.2), Vec3::zeros(), Vec3::y());
+        let uniforms = create_uniforms(&camera, width, height, width, height, 0.0, Box::new(create_cloud_noise(DEFAULT_SEED)));
+        let mut framebuffer = Framebuffer::new(width, height);
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            *pixel = if index % width / 4 % 2 == 0 { 0x606060 } else { 0x202020 };
+        }
+        let original = framebuffer.buffer.clone();
+
+        // La cadena completa, con las demás pasadas apagadas como arrancan
+        let mut chain = build_post_chain(&[]);
+        let blur = chain.get_mut::<RadialBlur>().unwrap();
+        blur.strength = strength;
+        blur.focal_point = (32.0, 24.0);
+        chain.run(&mut framebuffer, &uniforms, None);
+        (original, framebuffer.buffer)
+    }
+
+    #[test]
+    fn zero_strength_leaves_the_frame_untouched() {
+        let (original, result) = blurred(0.0);
+        assert_eq!(original, result);
+    }
+
+    #[test]
+    fn full_strength_streaks_and_shifts_to_blue() {
+        let (original, result) = blurred(1.0);
+        let changed = original.iter().zip(&result).filter(|(a, b)| a != b).count();
+        assert!(changed > original.len() / 2, "solo cambiaron {} píxeles", changed);
+        let (red, blue) = result.iter().fold((0, 0), |(r, b), &pixel| (r + (pixel >> 16 & 0xFF), b + (pixel & 0xFF)));
+        assert!(blue > red, "sin corrimiento al azul: rojo {}, azul {}", red, blue);
+    }
+}
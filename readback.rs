@@ -0,0 +1,123 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::cli::key_values;
+use crate::frame_graph::TargetFormat;
+
+// `--dump-buffers frame=120 dir=dump/`: vuelca los buffers de ese cuadro; sin `frame=` solo se vuelca
+// al presionar F9 en la ventana
+pub struct DumpOptions {
+    pub frame: Option<u32>,
+    pub dir: String,
+}
+
+impl DumpOptions {
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--dump-buffers")?;
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+        Some(DumpOptions {
+            frame: value("frame").and_then(|frame| frame.parse().ok()),
+            dir: value("dir").unwrap_or_else(|| "dump".to_string()),
+        })
+    }
+}
+
+enum Pixels {
+    Color(Vec<u32>),
+    Depth(Vec<f32>),
+}
+
+struct Capture {
+    name: &'static str,
+    // Pasada que lo dejó así; "render" para el color y la profundidad de la escena
+    pass: &'static str,
+    enabled: bool,
+    width: usize,
+    height: usize,
+    pixels: Pixels,
+}
+
+// Copias de los buffers de un cuadro, tomadas a medida que cada pasada termina: con los buffers
+// compartidos del grafo, al final del cuadro un objetivo ya puede tener el contenido de otro
+pub struct Readback {
+    captures: Vec<Capture>,
+}
+
+impl Readback {
+    pub fn new() -> Self {
+        Readback { captures: Vec::new() }
+    }
+
+    pub fn color(&mut self, name: &'static str, pass: &'static str, enabled: bool, width: usize, height: usize, pixels: &[u32]) {
+        let pixels = Pixels::Color(pixels[..width * height].to_vec());
+        self.captures.push(Capture { name, pass, enabled, width, height, pixels });
+    }
+
+    pub fn depth(&mut self, name: &'static str, pass: &'static str, width: usize, height: usize, depths: &[f32]) {
+        let pixels = Pixels::Depth(depths[..width * height].to_vec());
+        self.captures.push(Capture { name, pass, enabled: true, width, height, pixels });
+    }
+
+    // Escribe cada buffer como `<nombre>.npy` en `dir/frame<N>/`, junto con `manifest.json`.
+    // El color va como uint8 (alto, ancho, 3) en RGB y la profundidad como float32 (alto, ancho),
+    // con infinito donde no se dibujó nada. Devuelve la carpeta.
+    pub fn save(&self, dir: &str, frame: u32) -> io::Result<String> {
+        let folder = Path::new(dir).join(format!("frame{:04}", frame));
+        fs::create_dir_all(&folder)?;
+
+        let mut entries = Vec::new();
+        for capture in &self.captures {
+            let file = format!("{}.npy", capture.name);
+            let (format, dtype, shape) = match &capture.pixels {
+                Pixels::Color(pixels) => {
+                    let data: Vec<u8> = pixels.iter().flat_map(|pixel| [(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8]).collect();
+                    let shape = [capture.height, capture.width, 3];
+                    fs::write(folder.join(&file), npy("|u1", &shape, &data))?;
+                    (TargetFormat::Color, "uint8", shape.to_vec())
+                }
+                Pixels::Depth(depths) => {
+                    let data: Vec<u8> = depths.iter().flat_map(|depth| depth.to_le_bytes()).collect();
+                    let shape = [capture.height, capture.width];
+                    fs::write(folder.join(&file), npy("<f4", &shape, &data))?;
+                    (TargetFormat::Depth, "float32", shape.to_vec())
+                }
+            };
+            let shape: Vec<String> = shape.iter().map(usize::to_string).collect();
+            entries.push(format!(
+                "    {{ \"name\": \"{}\", \"file\": \"{}\", \"pass\": \"{}\", \"enabled\": {}, \"format\": \"{}\", \"dtype\": \"{}\", \"width\": {}, \"height\": {}, \"shape\": [{}] }}",
+                capture.name,
+                file,
+                capture.pass,
+                capture.enabled,
+                if format == TargetFormat::Color { "color" } else { "depth" },
+                dtype,
+                capture.width,
+                capture.height,
+                shape.join(", "),
+            ));
+        }
+
+        // Los buffers van en el orden en que se produjeron; los nombres son los del grafo de post-proceso
+        let manifest = format!("{{\n  \"frame\": {},\n  \"buffers\": [\n{}\n  ]\n}}\n", frame, entries.join(",\n"));
+        fs::write(folder.join("manifest.json"), manifest)?;
+        Ok(folder.display().to_string())
+    }
+}
+
+// Formato .npy versión 1.0: la firma, el largo de la cabecera y un diccionario de Python con el tipo
+// y la forma, rellenado con espacios para que los datos empiecen alineados a 64 bytes
+fn npy(descr: &str, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    let dims: Vec<String> = shape.iter().map(usize::to_string).collect();
+    let dims = if dims.len() == 1 { format!("{},", dims[0]) } else { dims.join(", ") };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': ({}), }}", descr, dims);
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(10 + header.len() + data.len());
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
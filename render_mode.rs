@@ -0,0 +1,132 @@
+use crate::cli::arg_value;
+use crate::framebuffer::Framebuffer;
+use crate::line::line;
+use crate::shaders::ShaderKind;
+use crate::vertex::Vertex;
+
+// Verde, que se distingue sobre la Luna blanca y sobre el sol
+const WIRE_COLOR: u32 = 0x30FF60;
+// La línea muestrea en la esquina del píxel y el triángulo en su centro; sin este margen la mitad
+// de sus píxeles perdería la prueba de profundidad contra la propia cara
+const WIRE_DEPTH_BIAS: f32 = 2e-4;
+
+// Cómo se dibujan los cuerpos: relleno con su shader, solo aristas, las dos cosas o una de las
+// vistas de depuración, que reemplazan el shader de todos los cuerpos como lo hace "N"
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+    Filled,
+    Wireframe,
+    FilledWireframe,
+    Normals,
+    Depth,
+    Uv,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 6] = [
+        RenderMode::Filled,
+        RenderMode::Wireframe,
+        RenderMode::FilledWireframe,
+        RenderMode::Normals,
+        RenderMode::Depth,
+        RenderMode::Uv,
+    ];
+
+    // `--render-mode filled|wireframe|overlay|normals|depth|uv`
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        match arg_value(args, "--render-mode") {
+            None => Ok(RenderMode::Filled),
+            Some(value) => RenderMode::ALL
+                .into_iter()
+                .find(|mode| mode.name() == value)
+                .ok_or_else(|| format!("Modo de render desconocido '{}' (filled, wireframe, overlay, normals, depth o uv)", value)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Filled => "filled",
+            RenderMode::Wireframe => "wireframe",
+            RenderMode::FilledWireframe => "overlay",
+            RenderMode::Normals => "normals",
+            RenderMode::Depth => "depth",
+            RenderMode::Uv => "uv",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let position = RenderMode::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        RenderMode::ALL[(position + 1) % RenderMode::ALL.len()]
+    }
+
+    // Shader de depuración que manda sobre el de cada cuerpo en este modo
+    pub fn debug_shader(self) -> Option<ShaderKind> {
+        match self {
+            RenderMode::Normals => Some(ShaderKind::NormalsDebug),
+            RenderMode::Depth => Some(ShaderKind::DepthDebug),
+            RenderMode::Uv => Some(ShaderKind::UvDebug),
+            _ => None,
+        }
+    }
+
+    pub fn fills(self) -> bool {
+        self != RenderMode::Wireframe
+    }
+
+    pub fn draws_wireframe(self) -> bool {
+        matches!(self, RenderMode::Wireframe | RenderMode::FilledWireframe)
+    }
+}
+
+// Aristas de los triángulos ya proyectados, con Bresenham y prueba de profundidad. Los pedazos de
+// un triángulo recortado se dibujan cada uno, así también se ve por dónde cortó el plano cercano.
+pub fn draw_wireframe(framebuffer: &mut Framebuffer, triangles: &[Vec<[Vertex; 3]>]) {
+    framebuffer.set_current_color(WIRE_COLOR);
+    let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+    for [a, b, c] in triangles.iter().flatten() {
+        for (start, end) in [(a, b), (b, c), (c, a)] {
+            let Some((start, end)) = clip_to_screen(start, end, width, height) else {
+                continue;
+            };
+            for fragment in line(&start, &end) {
+                framebuffer.point(fragment.position.x as usize, fragment.position.y as usize, fragment.depth - WIRE_DEPTH_BIAS);
+            }
+        }
+    }
+}
+
+// Recorta el segmento al rectángulo de la pantalla (Liang-Barsky), así una arista que se sale
+// muy lejos, con la cámara pegada a la superficie, no recorre miles de píxeles invisibles
+fn clip_to_screen(start: &Vertex, end: &Vertex, width: f32, height: f32) -> Option<(Vertex, Vertex)> {
+    let (from, to) = (start.transformed_position, end.transformed_position);
+    let delta = to - from;
+    let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+    for (step, distance) in [
+        (-delta.x, from.x),
+        (delta.x, width - 1.0 - from.x),
+        (-delta.y, from.y),
+        (delta.y, height - 1.0 - from.y),
+    ] {
+        if step == 0.0 {
+            if distance < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = distance / step;
+        if step < 0.0 {
+            enter = enter.max(t);
+        } else {
+            exit = exit.min(t);
+        }
+    }
+    if enter > exit {
+        return None;
+    }
+    let at = |t: f32| {
+        let mut vertex = start.clone();
+        vertex.transformed_position = from + delta * t;
+        vertex
+    };
+    Some((at(enter), at(exit)))
+}
@@ -0,0 +1,319 @@
+use std::ops::Range;
+use crate::cli::arg_value;
+use crate::framebuffer::Framebuffer;
+#[cfg(feature = "io")]
+use crate::image_io::load_png_rgb;
+
+// Paleta de 16 colores con tonos de espacio profundo, planetas y estrellas
+const SPACE_16: [u32; 16] = [
+    0x000000, 0x0b0b1e, 0x1b1b3a, 0x33335a, 0x4a4e8a, 0x2e6ea6, 0x46a6d8, 0x9fd8f0,
+    0x1f5a2a, 0x4f9a3a, 0xc8b070, 0xe07a2e, 0xb0301e, 0x6a1a1a, 0xd8d8d8, 0xffffff,
+];
+
+// Versión de 32 colores con rampas intermedias
+const SPACE_32: [u32; 32] = [
+    0x000000, 0x07071a, 0x0f0f28, 0x1b1b3a, 0x26264a, 0x33335a, 0x404472, 0x4a4e8a,
+    0x1e3f78, 0x2e6ea6, 0x3a8cc2, 0x46a6d8, 0x72c0e6, 0x9fd8f0, 0x123a1c, 0x1f5a2a,
+    0x357a30, 0x4f9a3a, 0x8a9a50, 0xc8b070, 0xe0c890, 0xf0a040, 0xe07a2e, 0xc8501e,
+    0xb0301e, 0x8a2018, 0x6a1a1a, 0x5a5a5a, 0x8a8a8a, 0xb4b4b4, 0xd8d8d8, 0xffffff,
+];
+
+// Matriz de Bayer 4x4, valores 0..15
+const BAYER_4X4: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
+// Amplitud del tramado en niveles de 0-255 por canal
+const DITHER_SPREAD: f32 = 24.0;
+
+// La difusión se atenúa y se acota para que las zonas planas con un color cercano a la paleta
+// no acumulen error hasta salpicarse de puntos de colores lejanos
+const DIFFUSION_STRENGTH: f32 = 0.8;
+const MAX_DIFFUSED_ERROR: f32 = 32.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DitherMode {
+    Off,
+    // Bayer 4x4: barato y estable entre cuadros, pero deja un patrón visible en zonas planas
+    Ordered,
+    // Floyd–Steinberg: sin patrón, pero es serial y cambia de un cuadro a otro; solo para capturas
+    ErrorDiffusion,
+}
+
+impl DitherMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(DitherMode::Off),
+            "ordered" => Some(DitherMode::Ordered),
+            "diffusion" => Some(DitherMode::ErrorDiffusion),
+            _ => None,
+        }
+    }
+}
+
+// Modo retro: se renderiza a baja resolución, se cuantiza a una paleta y se escala
+// con vecino más cercano por un factor entero, centrado con bordes negros.
+pub struct RetroMode {
+    pub enabled: bool,
+    // Tramado en la ventana (nunca difusión de error) y en la transmisión y las imágenes guardadas
+    pub dither: DitherMode,
+    pub capture_dither: DitherMode,
+    pub framebuffer: Framebuffer,
+    palette: Vec<u32>,
+}
+
+impl RetroMode {
+    // `--retro 200x150` fija la resolución interna y `--palette space16|space32|strip.png` la paleta.
+    // `--retro-dither off|ordered` y `--capture-dither off|ordered|diffusion` eligen el tramado
+    pub fn from_args(args: &[String]) -> Self {
+        let (width, height) = arg_value(args, "--retro")
+            .and_then(|value| {
+                let (w, h) = value.split_once('x')?;
+                Some((w.parse().ok()?, h.parse().ok()?))
+            })
+            .filter(|&(w, h): &(usize, usize)| w > 0 && h > 0)
+            .unwrap_or((200, 150));
+
+        let palette = match arg_value(args, "--palette").as_deref() {
+            None | Some("space16") => SPACE_16.to_vec(),
+            Some("space32") => SPACE_32.to_vec(),
+            Some(path) => load_palette_strip(path).unwrap_or_else(|error| {
+                eprintln!("No se pudo cargar la paleta {}: {}", path, error);
+                SPACE_16.to_vec()
+            }),
+        };
+
+        let dither_arg = |flag: &str, default: DitherMode| match arg_value(args, flag) {
+            None => default,
+            Some(value) => DitherMode::parse(&value).unwrap_or_else(|| {
+                eprintln!("Tramado desconocido '{}' en {}", value, flag);
+                default
+            }),
+        };
+        let mut dither = dither_arg("--retro-dither", DitherMode::Ordered);
+        if dither == DitherMode::ErrorDiffusion {
+            eprintln!("La difusión de error solo se usa en capturas; la ventana usa tramado ordenado");
+            dither = DitherMode::Ordered;
+        }
+
+        let mut framebuffer = Framebuffer::new(width, height);
+        framebuffer.set_background_color(0x333355);
+
+        RetroMode {
+            enabled: arg_value(args, "--retro").is_some(),
+            dither,
+            capture_dither: dither_arg("--capture-dither", DitherMode::ErrorDiffusion),
+            framebuffer,
+            palette,
+        }
+    }
+
+    // Factor entero de escalado y desplazamiento para centrar la imagen en la ventana
+    pub fn layout(&self, target_width: usize, target_height: usize) -> (usize, usize, usize) {
+        let scale = (target_width / self.framebuffer.width)
+            .min(target_height / self.framebuffer.height)
+            .max(1);
+        let offset_x = target_width.saturating_sub(self.framebuffer.width * scale) / 2;
+        let offset_y = target_height.saturating_sub(self.framebuffer.height * scale) / 2;
+        (scale, offset_x, offset_y)
+    }
+
+    // Píxel interno bajo el punto (x, y) de una ventana de `target_width` x `target_height`, deshaciendo
+    // el escalado y el centrado de `layout`; `None` sobre los bordes negros o fuera de la ventana
+    pub fn to_internal(&self, x: f32, y: f32, target_width: usize, target_height: usize) -> Option<(usize, usize)> {
+        if !(0.0..target_width as f32).contains(&x) || !(0.0..target_height as f32).contains(&y) {
+            return None;
+        }
+        let (scale, offset_x, offset_y) = self.layout(target_width, target_height);
+        let internal_x = (x as usize).checked_sub(offset_x)? / scale;
+        let internal_y = (y as usize).checked_sub(offset_y)? / scale;
+        (internal_x < self.framebuffer.width && internal_y < self.framebuffer.height).then_some((internal_x, internal_y))
+    }
+
+    // Cuantiza el framebuffer interno con el tramado de la ventana y lo escala sobre `target`
+    pub fn present(&self, target: &mut Framebuffer) {
+        self.present_with(target, self.dither, 0..target.height);
+    }
+
+    // Igual que `present` con otro tramado. El tramado se hace a la resolución interna, así el error
+    // nunca llega a los bordes negros del centrado; `visible` son las filas de `target` que no tapan
+    // las franjas de cine, y las filas internas que caen enteras debajo de ellas no reciben error.
+    pub fn present_with(&self, target: &mut Framebuffer, dither: DitherMode, visible: Range<usize>) {
+        let (scale, offset_x, offset_y) = self.layout(target.width, target.height);
+        let source = &self.framebuffer;
+        let quantized = match dither {
+            DitherMode::ErrorDiffusion => {
+                let shown = |y: &usize| offset_y + (y + 1) * scale > visible.start && offset_y + y * scale < visible.end;
+                let start = (0..source.height).find(shown).unwrap_or(source.height);
+                let end = (start..source.height).rev().find(shown).map_or(start, |y| y + 1);
+                self.diffuse(start..end)
+            }
+            mode => (0..source.width * source.height)
+                .map(|i| self.quantize(source.buffer[i], i % source.width, i / source.width, mode))
+                .collect(),
+        };
+
+        target.buffer.iter_mut().for_each(|pixel| *pixel = 0x000000);
+
+        for y in 0..source.height {
+            for x in 0..source.width {
+                let color = quantized[y * source.width + x];
+
+                for sy in 0..scale {
+                    let ty = offset_y + y * scale + sy;
+                    if ty >= target.height {
+                        break;
+                    }
+                    let row = ty * target.width;
+                    for sx in 0..scale {
+                        let tx = offset_x + x * scale + sx;
+                        if tx < target.width {
+                            target.buffer[row + tx] = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn quantize(&self, color: u32, x: usize, y: usize, dither: DitherMode) -> u32 {
+        let offset = if dither == DitherMode::Ordered {
+            (BAYER_4X4[y % 4][x % 4] as f32 + 0.5) / 16.0 - 0.5
+        } else {
+            0.0
+        } * DITHER_SPREAD;
+
+        let channel = |shift: u32| (((color >> shift) & 0xFF) as f32 + offset).clamp(0.0, 255.0);
+        let (r, g, b) = (channel(16), channel(8), channel(0));
+
+        nearest_color(&self.palette, r, g, b)
+    }
+}
+
+impl RetroMode {
+    // Floyd–Steinberg con recorrido en serpentina (alterna el sentido de cada fila) para evitar vetas
+    // diagonales. Las filas fuera de `rows` se cuantizan sin tramado y no reciben error.
+    fn diffuse(&self, rows: Range<usize>) -> Vec<u32> {
+        let source = &self.framebuffer;
+        let width = source.width;
+        let mut error = vec![[0.0f32; 3]; width * source.height];
+        let mut output: Vec<u32> = (0..width * source.height)
+            .map(|i| self.quantize(source.buffer[i], i % width, i / width, DitherMode::Off))
+            .collect();
+
+        for y in rows.clone() {
+            let forward = (y - rows.start).is_multiple_of(2);
+            for step in 0..width {
+                let x = if forward { step } else { width - 1 - step };
+                let index = y * width + x;
+                let color = source.buffer[index];
+
+                let wanted = [
+                    (((color >> 16) & 0xFF) as f32 + error[index][0]).clamp(0.0, 255.0),
+                    (((color >> 8) & 0xFF) as f32 + error[index][1]).clamp(0.0, 255.0),
+                    ((color & 0xFF) as f32 + error[index][2]).clamp(0.0, 255.0),
+                ];
+                let chosen = nearest_color(&self.palette, wanted[0], wanted[1], wanted[2]);
+                output[index] = chosen;
+
+                let got = [((chosen >> 16) & 0xFF) as f32, ((chosen >> 8) & 0xFF) as f32, (chosen & 0xFF) as f32];
+                let ahead = |dx: i32| if forward { x as i32 + dx } else { x as i32 - dx };
+                for (dx, dy, weight) in [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)] {
+                    let (nx, ny) = (ahead(dx), y + dy);
+                    if nx < 0 || nx >= width as i32 || ny >= rows.end {
+                        continue;
+                    }
+                    let neighbor = &mut error[ny * width + nx as usize];
+                    for channel in 0..3 {
+                        neighbor[channel] = (neighbor[channel] + (wanted[channel] - got[channel]) * weight / 16.0 * DIFFUSION_STRENGTH)
+                            .clamp(-MAX_DIFFUSED_ERROR, MAX_DIFFUSED_ERROR);
+                    }
+                }
+            }
+        }
+
+        output
+    }
+}
+
+fn nearest_color(palette: &[u32], r: f32, g: f32, b: f32) -> u32 {
+    let distance = |color: u32| {
+        let dr = ((color >> 16) & 0xFF) as f32 - r;
+        let dg = ((color >> 8) & 0xFF) as f32 - g;
+        let db = (color & 0xFF) as f32 - b;
+        dr * dr + dg * dg + db * db
+    };
+
+    palette
+        .iter()
+        .copied()
+        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+        .unwrap_or(0x000000)
+}
+
+// Una tira PNG define la paleta con los colores (sin repetir) de su primera fila
+#[cfg(feature = "io")]
+fn load_palette_strip(path: &str) -> Result<Vec<u32>, String> {
+    let (width, _, pixels) = load_png_rgb(path).map_err(|error| error.to_string())?;
+
+    let mut palette: Vec<u32> = Vec::new();
+    for &color in &pixels[..width] {
+        if !palette.contains(&color) {
+            palette.push(color);
+        }
+    }
+
+    if palette.is_empty() {
+        return Err("la tira no tiene colores".to_string());
+    }
+    Ok(palette)
+}
+
+#[cfg(not(feature = "io"))]
+fn load_palette_strip(_path: &str) -> Result<Vec<u32>, String> {
+    Err("sin la feature `io` solo hay space16 y space32".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retro(width: usize, height: usize) -> RetroMode {
+        RetroMode::from_args(&["--retro".to_string(), format!("{}x{}", width, height)])
+    }
+
+    #[test]
+    fn window_points_map_through_the_integer_scale() {
+        // 200x150 en 800x650: escala 4, sin borde a los lados y 25 filas arriba y abajo
+        let mode = retro(200, 150);
+        assert_eq!(mode.layout(800, 650), (4, 0, 25));
+        assert_eq!(mode.to_internal(0.0, 25.0, 800, 650), Some((0, 0)));
+        assert_eq!(mode.to_internal(3.9, 28.9, 800, 650), Some((0, 0)));
+        assert_eq!(mode.to_internal(4.0, 29.0, 800, 650), Some((1, 1)));
+        assert_eq!(mode.to_internal(799.5, 624.5, 800, 650), Some((199, 149)));
+        assert_eq!(mode.to_internal(400.0, 24.9, 800, 650), None);
+        assert_eq!(mode.to_internal(400.0, 625.0, 800, 650), None);
+        assert_eq!(mode.to_internal(-0.5, 100.0, 800, 650), None);
+        assert_eq!(mode.to_internal(800.0, 100.0, 800, 650), None);
+
+        // Bordes a los cuatro lados: escala 3 en 700x500, 50 columnas y 25 filas de margen
+        assert_eq!(mode.layout(700, 500), (3, 50, 25));
+        assert_eq!(mode.to_internal(49.0, 200.0, 700, 500), None);
+        assert_eq!(mode.to_internal(50.0, 200.0, 700, 500), Some((0, 58)));
+        assert_eq!(mode.to_internal(649.0, 474.0, 700, 500), Some((199, 149)));
+        assert_eq!(mode.to_internal(650.0, 200.0, 700, 500), None);
+    }
+
+    #[test]
+    fn window_smaller_than_the_internal_image_crops_at_scale_one() {
+        // La imagen no entra: escala 1 recortada desde la esquina, sin bordes
+        let mode = retro(200, 150);
+        assert_eq!(mode.layout(120, 90), (1, 0, 0));
+        assert_eq!(mode.to_internal(0.0, 0.0, 120, 90), Some((0, 0)));
+        assert_eq!(mode.to_internal(119.0, 89.0, 120, 90), Some((119, 89)));
+        assert_eq!(mode.to_internal(120.0, 10.0, 120, 90), None);
+    }
+}
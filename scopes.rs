@@ -0,0 +1,118 @@
+use crate::framebuffer::Framebuffer;
+use crate::overlay::{backing_over, draw_text, luminance, region_luminance, ADVANCE, LINE_HEIGHT};
+
+const BINS: usize = 64;
+// Se mide uno de cada SAMPLE_STEP píxeles en cada eje para que el costo sea bajo
+const SAMPLE_STEP: usize = 4;
+const MARGIN: usize = 10;
+const HISTOGRAM_BAR_WIDTH: usize = 3;
+const HISTOGRAM_HEIGHT: usize = 80;
+const WAVEFORM_HEIGHT: usize = 64;
+
+const BACKGROUND_COLOR: u32 = 0x101020;
+const GUIDE_COLOR: u32 = 0x303050;
+const BAR_COLOR: u32 = 0xC8C8C8;
+const MEAN_COLOR: u32 = 0x40C040;
+const MAX_COLOR: u32 = 0xF0F0F0;
+const FILL_COLOR: u32 = 0x204020;
+
+// Histograma de luminancia (arriba a la derecha) y forma de onda por columnas (abajo).
+// Se mide la imagen final antes de dibujar los propios scopes encima; `inset` deja libres
+// esas filas arriba y abajo (las franjas de cine) tanto al medir como al dibujar.
+pub struct Scopes {
+    pub enabled: bool,
+    histogram: [u32; BINS],
+    column_mean: Vec<f32>,
+    column_max: Vec<f32>,
+}
+
+impl Scopes {
+    pub fn new() -> Self {
+        Scopes {
+            enabled: false,
+            histogram: [0; BINS],
+            column_mean: Vec::new(),
+            column_max: Vec::new(),
+        }
+    }
+
+    pub fn draw(&mut self, framebuffer: &mut Framebuffer, inset: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        self.measure(framebuffer, inset);
+        self.draw_histogram(framebuffer, inset);
+        self.draw_waveform(framebuffer, inset);
+    }
+
+    fn measure(&mut self, framebuffer: &Framebuffer, inset: usize) {
+        let columns = framebuffer.width.div_ceil(SAMPLE_STEP);
+        self.histogram = [0; BINS];
+        self.column_mean = vec![0.0; columns];
+        self.column_max = vec![0.0; columns];
+        let mut column_samples = vec![0; columns];
+
+        for y in (inset..framebuffer.height.saturating_sub(inset)).step_by(SAMPLE_STEP) {
+            for x in (0..framebuffer.width).step_by(SAMPLE_STEP) {
+                let value = luminance(framebuffer.buffer[y * framebuffer.width + x]);
+                let bin = ((value * BINS as f32) as usize).min(BINS - 1);
+                self.histogram[bin] += 1;
+
+                let column = x / SAMPLE_STEP;
+                self.column_mean[column] += value;
+                self.column_max[column] = self.column_max[column].max(value);
+                column_samples[column] += 1;
+            }
+        }
+
+        for (mean, samples) in self.column_mean.iter_mut().zip(column_samples) {
+            *mean /= samples.max(1) as f32;
+        }
+    }
+
+    fn draw_histogram(&self, framebuffer: &mut Framebuffer, inset: usize) {
+        let width = BINS * HISTOGRAM_BAR_WIDTH;
+        let Some(left) = framebuffer.width.checked_sub(width + MARGIN) else {
+            return;
+        };
+        let top = MARGIN + inset;
+        // La etiqueta se adapta a la imagen que el histograma tapa, no a su propio fondo
+        let label = "LUMA";
+        let (label_width, label_height) = (label.len() * ADVANCE + 3, LINE_HEIGHT);
+        let background = region_luminance(framebuffer, left + 1, top + 1, label_width, label_height);
+        framebuffer.fill_rect(left, top, width, HISTOGRAM_HEIGHT, BACKGROUND_COLOR);
+
+        // Escala logarítmica: el fondo liso no aplasta al resto de los tonos
+        let peak = (*self.histogram.iter().max().unwrap_or(&0) as f32).ln_1p().max(1e-6);
+        for (bin, count) in self.histogram.iter().enumerate() {
+            let height = ((*count as f32).ln_1p() / peak * HISTOGRAM_HEIGHT as f32) as usize;
+            let x = left + bin * HISTOGRAM_BAR_WIDTH;
+            framebuffer.fill_rect(x, top + HISTOGRAM_HEIGHT - height, HISTOGRAM_BAR_WIDTH - 1, height, BAR_COLOR);
+        }
+        let style = backing_over(framebuffer, left + 1, top + 1, label_width, label_height, background);
+        draw_text(framebuffer, left + 3, top + 3, label, style.text, 1);
+    }
+
+    fn draw_waveform(&self, framebuffer: &mut Framebuffer, inset: usize) {
+        let Some(top) = framebuffer.height.checked_sub(WAVEFORM_HEIGHT + inset) else {
+            return;
+        };
+        framebuffer.fill_rect(0, top, framebuffer.width, WAVEFORM_HEIGHT, BACKGROUND_COLOR);
+
+        // Guías al 25 %, 50 % y 75 %
+        for quarter in 1..4 {
+            let y = top + WAVEFORM_HEIGHT - quarter * WAVEFORM_HEIGHT / 4;
+            framebuffer.fill_rect(0, y, framebuffer.width, 1, GUIDE_COLOR);
+        }
+
+        let level = |value: f32| top + WAVEFORM_HEIGHT - 1 - (value * (WAVEFORM_HEIGHT - 1) as f32) as usize;
+        for (column, (mean, max)) in self.column_mean.iter().zip(&self.column_max).enumerate() {
+            let x = column * SAMPLE_STEP;
+            let mean_y = level(*mean);
+            framebuffer.fill_rect(x, mean_y, SAMPLE_STEP, top + WAVEFORM_HEIGHT - mean_y, FILL_COLOR);
+            framebuffer.fill_rect(x, mean_y, SAMPLE_STEP, 1, MEAN_COLOR);
+            framebuffer.fill_rect(x, level(*max), SAMPLE_STEP, 1, MAX_COLOR);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,110 @@
+use crate::animation::params;
+use crate::cli::key_values;
+use crate::color::Color;
+
+// Estaciones de la Tierra: un reloj anual que mueve la línea de nieve y los colores de la vegetación.
+// Cada hemisferio va medio año desfasado del otro; el norte empieza el año en primavera.
+#[derive(Clone, Copy, Debug)]
+pub struct Seasons {
+    pub year: f32,       // Duración del año en segundos de tiempo local
+    pub snow_swing: f32, // Cuánto baja la línea de nieve hacia el ecuador en pleno invierno (radianes)
+    pub tropics: f32,    // |latitud| hasta la que no hay estaciones (radianes, ~23°)
+    pub band_fade: f32,  // Ancho de la transición entre los trópicos y la banda templada
+    // Puntos de paso de cada bioma, al empezar cada estación
+    pub forest_spring: Color,
+    pub forest_summer: Color,
+    pub forest_autumn: Color,
+    pub forest_winter: Color,
+    pub grass_spring: Color,
+    pub grass_summer: Color,
+    pub grass_autumn: Color,
+    pub grass_winter: Color,
+}
+
+impl Default for Seasons {
+    fn default() -> Self {
+        Seasons {
+            year: 10.0,
+            snow_swing: 0.3,
+            tropics: 0.4,
+            band_fade: 0.15,
+            forest_spring: Color::new(80, 165, 60),
+            forest_summer: Color::new(20, 100, 25),
+            forest_autumn: Color::new(200, 105, 30),
+            forest_winter: Color::new(100, 75, 50),
+            grass_spring: Color::new(130, 190, 70),
+            grass_summer: Color::new(70, 140, 40),
+            grass_autumn: Color::new(205, 150, 60),
+            grass_winter: Color::new(140, 115, 75),
+        }
+    }
+}
+
+impl Seasons {
+    // `--seasons [year=10] [snow_swing=0.3] [tropics=0.4] [fade=0.15] [forest_autumn=c8691e ...]`;
+    // sin la opción la línea de nieve queda fija y la vegetación no cambia
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let pairs = key_values(args, "--seasons")?;
+        let defaults = Seasons::default();
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let number = |key: &str, default: f32| value(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+        let color = |key: &str, default: Color| {
+            value(key)
+                .and_then(|hex| u32::from_str_radix(hex.trim_start_matches('#'), 16).ok())
+                .map(Color::from_hex)
+                .unwrap_or(default)
+        };
+
+        Some(Seasons {
+            year: number("year", defaults.year).max(0.1),
+            snow_swing: number("snow_swing", defaults.snow_swing).max(0.0),
+            tropics: number("tropics", defaults.tropics).max(0.0),
+            band_fade: number("fade", defaults.band_fade).max(1e-3),
+            forest_spring: color("forest_spring", defaults.forest_spring),
+            forest_summer: color("forest_summer", defaults.forest_summer),
+            forest_autumn: color("forest_autumn", defaults.forest_autumn),
+            forest_winter: color("forest_winter", defaults.forest_winter),
+            grass_spring: color("grass_spring", defaults.grass_spring),
+            grass_summer: color("grass_summer", defaults.grass_summer),
+            grass_autumn: color("grass_autumn", defaults.grass_autumn),
+            grass_winter: color("grass_winter", defaults.grass_winter),
+        })
+    }
+
+    // Punto del año en [0, 1) para el hemisferio de `latitude`: 0 primavera, 0.25 verano,
+    // 0.5 otoño, 0.75 invierno
+    pub fn phase(&self, time: f32, latitude: f32) -> f32 {
+        let offset = if latitude < 0.0 { 0.5 } else { 0.0 };
+        (time / self.year + offset).rem_euclid(1.0)
+    }
+
+    // Latitud de la línea de nieve: baja `snow_swing` en la mitad del invierno y vuelve a `base` en verano
+    pub fn snow_latitude(&self, base: f32, phase: f32) -> f32 {
+        let cold = 0.5 + 0.5 * (std::f32::consts::TAU * (phase - 0.875)).cos();
+        base - self.snow_swing * cold
+    }
+
+    // Peso de las estaciones según la latitud: nada en los trópicos, todo en la banda templada
+    pub fn weight(&self, latitude: f32) -> f32 {
+        ((latitude.abs() - self.tropics) / self.band_fade).clamp(0.0, 1.0)
+    }
+
+    pub fn forest(&self, phase: f32) -> Color {
+        cycle(&[self.forest_spring, self.forest_summer, self.forest_autumn, self.forest_winter], phase)
+    }
+
+    pub fn grass(&self, phase: f32) -> Color {
+        cycle(&[self.grass_spring, self.grass_summer, self.grass_autumn, self.grass_winter], phase)
+    }
+}
+
+// Interpola entre los puntos de paso repartidos a lo largo del año; después del invierno vuelve a la primavera
+fn cycle(waypoints: &[Color; 4], phase: f32) -> Color {
+    let position = phase.rem_euclid(1.0) * 4.0;
+    let index = (position as usize).min(3);
+    waypoints[index].lerp(&waypoints[(index + 1) % 4], position - index as f32)
+}
+
+params!(Seasons { year, snow_swing, tropics, band_fade } colors {
+    forest_spring, forest_summer, forest_autumn, forest_winter, grass_spring, grass_summer, grass_autumn, grass_winter
+});
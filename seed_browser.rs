@@ -0,0 +1,133 @@
+use crate::framebuffer::Framebuffer;
+
+const GRID: usize = 4;
+const MAX_CELL_SIZE: usize = 160;
+const MARGIN: usize = 20;
+const BORDER_COLOR: u32 = 0xFFD700;
+const PENDING_COLOR: u32 = 0x202030;
+
+// Deriva la semilla de la celda `index` a partir de la semilla maestra
+fn derive_seed(master_seed: i32, index: usize) -> i32 {
+    let mut h = (master_seed as u32) ^ (index as u32 + 1).wrapping_mul(0x9E37_79B9);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h as i32
+}
+
+// Cuadrícula de miniaturas del modelo enfocado renderizado con distintas semillas.
+// Las celdas se renderizan de a poco en varios cuadros para no congelar la ventana.
+pub struct SeedBrowser {
+    pub active: bool,
+    pub time: f32,
+    pub cell_size: usize,
+    seeds: Vec<i32>,
+    cells: Vec<Option<Vec<u32>>>,
+    selected: usize,
+    next_cell: usize,
+}
+
+impl SeedBrowser {
+    pub fn new(target_width: usize, target_height: usize) -> Self {
+        let available = target_width.min(target_height).saturating_sub(2 * MARGIN) / GRID;
+        SeedBrowser {
+            active: false,
+            time: 0.0,
+            cell_size: available.clamp(1, MAX_CELL_SIZE),
+            seeds: Vec::new(),
+            cells: Vec::new(),
+            selected: 0,
+            next_cell: 0,
+        }
+    }
+
+    // Empieza a explorar a partir de la semilla actual; `time` queda fijo para todas las celdas
+    pub fn open(&mut self, master_seed: i32, time: f32) {
+        self.active = true;
+        self.time = time;
+        self.seeds = (0..GRID * GRID).map(|i| derive_seed(master_seed, i)).collect();
+        self.cells = vec![None; GRID * GRID];
+        self.selected = 0;
+        self.next_cell = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.active = false;
+        self.cells.clear();
+    }
+
+    pub fn navigate(&mut self, dx: i32, dy: i32) {
+        let column = (self.selected % GRID) as i32 + dx;
+        let row = (self.selected / GRID) as i32 + dy;
+        let column = column.rem_euclid(GRID as i32) as usize;
+        let row = row.rem_euclid(GRID as i32) as usize;
+        self.selected = row * GRID + column;
+    }
+
+    pub fn selected_seed(&self) -> i32 {
+        self.seeds[self.selected]
+    }
+
+    // Renderiza hasta `budget` celdas pendientes; `render_cell` dibuja el modelo con la semilla dada
+    pub fn render_pending<F: FnMut(&mut Framebuffer, i32)>(&mut self, budget: usize, mut render_cell: F) {
+        let mut cell = Framebuffer::new(self.cell_size, self.cell_size);
+        cell.set_background_color(0x333355);
+
+        for _ in 0..budget {
+            if self.next_cell >= self.cells.len() {
+                break;
+            }
+            cell.clear();
+            render_cell(&mut cell, self.seeds[self.next_cell]);
+            self.cells[self.next_cell] = Some(cell.buffer.clone());
+            self.next_cell += 1;
+        }
+    }
+
+    // Compone la cuadrícula centrada, el marco de la celda seleccionada y una barra de progreso
+    pub fn compose(&self, target: &mut Framebuffer) {
+        target.buffer.iter_mut().for_each(|pixel| *pixel = 0x000000);
+
+        let size = self.cell_size;
+        let grid_size = size * GRID;
+        let origin_x = target.width.saturating_sub(grid_size) / 2;
+        let origin_y = target.height.saturating_sub(grid_size) / 2;
+
+        for (index, cell) in self.cells.iter().enumerate() {
+            let cell_x = origin_x + (index % GRID) * size;
+            let cell_y = origin_y + (index / GRID) * size;
+
+            for y in 0..size {
+                for x in 0..size {
+                    let color = match cell {
+                        Some(pixels) => pixels[y * size + x],
+                        None => PENDING_COLOR,
+                    };
+                    // Un píxel de separación entre celdas
+                    let is_gap = x == 0 || y == 0;
+                    let is_border = index == self.selected && (x < 3 || y < 3 || x >= size - 3 || y >= size - 3);
+                    let color = if is_border { BORDER_COLOR } else if is_gap { 0x000000 } else { color };
+                    set_pixel(target, cell_x + x, cell_y + y, color);
+                }
+            }
+        }
+
+        // Barra de progreso bajo la cuadrícula mientras quedan celdas por renderizar
+        if self.next_cell < self.cells.len() {
+            let bar_y = (origin_y + grid_size + MARGIN / 2).min(target.height.saturating_sub(4));
+            let filled = grid_size * self.next_cell / self.cells.len();
+            for y in bar_y..bar_y + 4 {
+                for x in 0..grid_size {
+                    let color = if x < filled { BORDER_COLOR } else { PENDING_COLOR };
+                    set_pixel(target, origin_x + x, y, color);
+                }
+            }
+        }
+    }
+}
+
+fn set_pixel(target: &mut Framebuffer, x: usize, y: usize, color: u32) {
+    if x < target.width && y < target.height {
+        target.buffer[y * target.width + x] = color;
+    }
+}
@@ -0,0 +1,32 @@
+use crate::shaders::ShaderKind;
+
+// Pila de shaders temporales para vistas de depuración.
+// Renderizar usa el tope de la pila; el shader base no se modifica,
+// así que al hacer pop se recupera exactamente el que había.
+pub struct ShaderOverrideStack {
+    stack: Vec<ShaderKind>,
+}
+
+impl ShaderOverrideStack {
+    pub fn new() -> Self {
+        ShaderOverrideStack { stack: Vec::new() }
+    }
+
+    pub fn push(&mut self, shader: ShaderKind) {
+        self.stack.push(shader);
+    }
+
+    pub fn pop(&mut self) -> Option<ShaderKind> {
+        self.stack.pop()
+    }
+
+    // Shader a usar para renderizar dado el shader base de la escena
+    pub fn resolve(&self, base_shader: ShaderKind) -> ShaderKind {
+        self.stack.last().copied().unwrap_or(base_shader)
+    }
+
+    // Texto para el HUD, p. ej. "override: normals"
+    pub fn label(&self) -> Option<String> {
+        self.stack.last().map(|&shader| format!("override: {}", shader.name()))
+    }
+}
@@ -0,0 +1,355 @@
+use std::fs;
+use crate::animation::{params, ParamRoot, Params};
+use crate::color::Color;
+
+// Números de cada shader que se pueden ajustar sin recompilar: `--shader-params look.toml` los
+// carga al arrancar y F5 vuelve a leer el archivo. Los valores por defecto son los de siempre.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShaderParams {
+    pub sun: SunParams,
+    pub earth: EarthParams,
+    pub moon: MoonParams,
+    pub ripple: RippleParams,
+    pub noise: SpotsParams,
+    pub cellular: CellularParams,
+    pub europa: EuropaParams,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SunParams {
+    pub zoom: f32,            // Zoom para el patrón de ruido de las manchas
+    pub granule_zoom: f32,    // Tamaño de las celdas de convección
+    pub granule_drift: f32,   // Velocidad por segundo con la que derivan los centros de las celdas
+    pub limb_power: f32,      // Exponente del oscurecimiento hacia el borde
+    pub equatorial_rate: f32, // Radianes por segundo en el ecuador
+    pub differential: f32,    // Cuánto más lento giran los polos respecto al ecuador
+    pub spot_threshold: f32,  // Ruido desde el que hay manchas solares
+    pub rim_strength: f32,
+    pub rim_power: f32, // Más alto, borde más fino
+    pub rim_color: Color,
+    pub lane_color: Color,
+    pub cell_color: Color,
+    pub bright_color: Color,
+    pub spot_color: Color,
+    pub limb_color: Color,
+}
+
+impl Default for SunParams {
+    fn default() -> Self {
+        SunParams {
+            zoom: 50.0,
+            granule_zoom: 25.0,
+            granule_drift: 1.2,
+            limb_power: 0.6,
+            equatorial_rate: 0.24,
+            differential: 0.35,
+            spot_threshold: 0.6,
+            rim_strength: 0.6,
+            rim_power: 3.0,
+            rim_color: Color::new(255, 220, 170),
+            lane_color: Color::new(205, 55, 0),
+            cell_color: Color::new(255, 120, 20),
+            bright_color: Color::new(255, 255, 102),
+            spot_color: Color::new(139, 0, 0),
+            limb_color: Color::new(110, 15, 0),
+        }
+    }
+}
+
+params!(SunParams { zoom, granule_zoom, granule_drift, limb_power, equatorial_rate, differential, spot_threshold, rim_strength, rim_power }
+    colors { rim_color, lane_color, cell_color, bright_color, spot_color, limb_color });
+
+// Biomas, nubes y atmósfera de la Tierra. La elevación (`EARTH_ZOOM`, `SEA_LEVEL`) queda fija:
+// la comparten el relieve de vértices y los exportadores.
+#[derive(Debug, Clone, Copy)]
+pub struct EarthParams {
+    pub ocean_color: Color,
+    pub land_color: Color,
+    pub desert_color: Color,
+    pub snow_color: Color,
+    pub rock_color: Color, // Roca de las cordilleras
+    pub snow_latitude: f32, // Radianes
+    pub desert_threshold: f32,
+    pub forest_threshold: f32, // Por encima, bosque templado; entre la tierra y esto, pradera
+    pub rock_threshold: f32,   // Solo las cordilleras de las placas llegan tan alto
+    pub peak_threshold: f32,
+    pub cloud_zoom: f32,
+    pub cloud_threshold: f32,       // Por debajo no hay nubes
+    pub cloud_detail: f32,          // Ruido fino, solo donde ya hay cobertura
+    pub cloud_rotation: f32,        // Radianes por segundo alrededor del eje polar
+    pub cloud_drift: f32,           // Desplazamiento del ruido fino por segundo
+    pub cloud_height_scale: f32,    // Cuánto inclina la pendiente de la nube su normal
+    pub cloud_shadow_softness: f32, // Ancho de la transición entre la cara iluminada y la sombreada
+    pub ground_shadow: f32,         // Oscurecimiento del suelo bajo las nubes más altas
+    pub cloud_color: Color,
+    pub cloud_shade: Color,
+    pub sky_tint: Color,
+    pub rim_strength: f32,
+    pub rim_power: f32,
+    pub rim_color: Color,
+    pub shallow_color: Color, // Mar junto a la costa
+    pub shallow_depth: f32,   // Profundidad bajo la costa donde el mar ya tiene `ocean_color`
+    pub wave_zoom: f32,
+    pub wave_speed: f32,      // Desplazamiento del oleaje por segundo, en unidades del ruido
+    pub wave_strength: f32,   // Cuánto inclinan las olas la normal del brillo
+    pub ocean_shininess: f32,
+    pub ocean_specular: f32,
+}
+
+impl Default for EarthParams {
+    fn default() -> Self {
+        EarthParams {
+            ocean_color: Color::new(0, 105, 148),
+            land_color: Color::new(34, 139, 34),
+            desert_color: Color::new(210, 180, 140),
+            snow_color: Color::new(255, 250, 250),
+            rock_color: Color::new(120, 105, 90),
+            snow_latitude: 0.78,
+            desert_threshold: 0.3,
+            forest_threshold: 0.5,
+            rock_threshold: 0.8,
+            peak_threshold: 1.0,
+            cloud_zoom: 100.0,
+            cloud_threshold: 0.35,
+            cloud_detail: 0.3,
+            cloud_rotation: 0.12,
+            cloud_drift: 6.0,
+            cloud_height_scale: 0.06,
+            cloud_shadow_softness: 0.15,
+            ground_shadow: 0.4,
+            cloud_color: Color::new(236, 240, 245),
+            cloud_shade: Color::new(150, 165, 190),
+            sky_tint: Color::new(135, 206, 250),
+            rim_strength: 0.7,
+            rim_power: 2.5,
+            rim_color: Color::new(120, 180, 255),
+            shallow_color: Color::new(64, 200, 200),
+            shallow_depth: 0.2,
+            wave_zoom: 600.0,
+            wave_speed: 30.0,
+            wave_strength: 0.006,
+            ocean_shininess: 200.0,
+            ocean_specular: 1.2,
+        }
+    }
+}
+
+params!(EarthParams {
+    snow_latitude, desert_threshold, forest_threshold, rock_threshold, peak_threshold, cloud_zoom, cloud_threshold,
+    cloud_detail, cloud_rotation, cloud_drift, cloud_height_scale, cloud_shadow_softness, ground_shadow, rim_strength, rim_power,
+    shallow_depth, wave_zoom, wave_speed, wave_strength, ocean_shininess, ocean_specular,
+} colors { ocean_color, land_color, desert_color, snow_color, rock_color, cloud_color, cloud_shade, sky_tint, rim_color, shallow_color });
+
+#[derive(Debug, Clone, Copy)]
+pub struct MoonParams {
+    pub zoom: f32,
+    pub specular: f32,         // El polvo lunar apenas brilla
+    pub bump_strength: f32,    // Cuánto inclinan las paredes de los cráteres la normal
+    pub crater_threshold: f32, // Ruido por debajo del cual empieza un cráter
+    pub pulse: f32,            // Cuánto late el umbral con el tiempo
+    pub drift: f32,            // Desplazamiento del ruido por segundo
+    pub plain_color: Color,
+    pub crater_color: Color,
+    pub floor_color: Color, // Fondo de los cráteres más hondos
+}
+
+impl Default for MoonParams {
+    fn default() -> Self {
+        MoonParams {
+            zoom: 50.0,
+            specular: 0.2,
+            bump_strength: 0.3,
+            crater_threshold: 0.4,
+            pulse: 0.05,
+            drift: 6.0,
+            plain_color: Color::new(200, 200, 200),
+            crater_color: Color::new(220, 220, 220),
+            floor_color: Color::new(250, 250, 250),
+        }
+    }
+}
+
+params!(MoonParams { zoom, specular, bump_strength, crater_threshold, pulse, drift } colors { plain_color, crater_color, floor_color });
+
+// Las fuentes de las ondas siguen en `default_ripple_sources`; aquí va lo que comparten todas
+#[derive(Debug, Clone, Copy)]
+pub struct RippleParams {
+    pub wave_speed: f32, // Radianes de arco por segundo
+    pub base_color: Color,
+    pub crest_color: Color,
+}
+
+impl Default for RippleParams {
+    fn default() -> Self {
+        RippleParams { wave_speed: 1.2, base_color: Color::new(70, 130, 180), crest_color: Color::new(173, 216, 230) }
+    }
+}
+
+params!(RippleParams { wave_speed } colors { base_color, crest_color });
+
+// Shader `noise`: una rejilla de círculos que se desplaza en diagonal
+#[derive(Debug, Clone, Copy)]
+pub struct SpotsParams {
+    pub radius: f32,
+    pub spacing: f32,
+    pub speed: f32, // Unidades por segundo
+    pub spot_color: Color,
+    pub background_color: Color,
+}
+
+impl Default for SpotsParams {
+    fn default() -> Self {
+        SpotsParams { radius: 0.1, spacing: 0.3, speed: 0.12, spot_color: Color::black(), background_color: Color::new(255, 255, 255) }
+    }
+}
+
+params!(SpotsParams { radius, spacing, speed } colors { spot_color, background_color });
+
+// Cuatro tonos de energía separados por tres umbrales del ruido
+#[derive(Debug, Clone, Copy)]
+pub struct CellularParams {
+    pub zoom: f32,
+    pub flow_speed: f32, // Desplazamiento del ruido por segundo
+    pub low: f32,
+    pub mid: f32,
+    pub high: f32,
+    pub color_1: Color,
+    pub color_2: Color,
+    pub color_3: Color,
+    pub color_4: Color,
+}
+
+impl Default for CellularParams {
+    fn default() -> Self {
+        CellularParams {
+            zoom: 30.0,
+            flow_speed: 6.0,
+            low: 0.2,
+            mid: 0.5,
+            high: 0.8,
+            color_1: Color::new(255, 69, 0),
+            color_2: Color::new(255, 140, 0),
+            color_3: Color::new(255, 215, 0),
+            color_4: Color::new(255, 255, 153),
+        }
+    }
+}
+
+params!(CellularParams { zoom, flow_speed, low, mid, high } colors { color_1, color_2, color_3, color_4 });
+
+#[derive(Debug, Clone, Copy)]
+pub struct EuropaParams {
+    pub linea_density: f32, // Frecuencia del ruido de las líneas
+    pub linea_width: f32,   // Ancho de las crestas del ruido que se vuelven líneas
+    pub linea_stretch: f32, // Cuánto se alargan las líneas a lo largo del flujo
+    pub plate_zoom: f32,    // Escala de las placas (celdas de Worley)
+    pub drift_speed: f32,   // Deriva de los bordes de las placas por segundo
+    pub ice_shininess: f32, // Más cerrado que el de la luz: el hielo liso es casi un espejo
+    pub linea_color: Color,
+    // Tintes de las placas
+    pub clean_ice: Color,
+    pub blue_ice: Color,
+    pub cream_ice: Color,
+    pub gray_ice: Color,
+}
+
+impl Default for EuropaParams {
+    fn default() -> Self {
+        EuropaParams {
+            linea_density: 900.0,
+            linea_width: 0.06,
+            linea_stretch: 8.0,
+            plate_zoom: 4.0,
+            drift_speed: 0.03,
+            ice_shininess: 120.0,
+            linea_color: Color::new(150, 88, 56),
+            clean_ice: Color::new(236, 232, 224),
+            blue_ice: Color::new(222, 228, 238),
+            cream_ice: Color::new(238, 226, 204),
+            gray_ice: Color::new(226, 218, 210),
+        }
+    }
+}
+
+params!(EuropaParams { linea_density, linea_width, linea_stretch, plate_zoom, drift_speed, ice_shininess }
+    colors { linea_color, clean_ice, blue_ice, cream_ice, gray_ice });
+
+impl ParamRoot for ShaderParams {
+    fn group(&mut self, name: &str) -> Option<&mut dyn Params> {
+        match name {
+            "sun" => Some(&mut self.sun),
+            "earth" => Some(&mut self.earth),
+            "moon" => Some(&mut self.moon),
+            "ripple" => Some(&mut self.ripple),
+            "noise" => Some(&mut self.noise),
+            "cellular" => Some(&mut self.cellular),
+            "europa" => Some(&mut self.europa),
+            _ => None,
+        }
+    }
+}
+
+impl ShaderParams {
+    // Un subconjunto de TOML: secciones con el nombre del shader y `campo = valor`, con colores
+    // como "#rrggbb". Cada carga parte de los valores por defecto, así lo que falta (o se borra
+    // antes de recargar) vuelve a ellos; lo desconocido o mal escrito se avisa y se salta.
+    //
+    //     [earth]
+    //     ocean_color = "#1e3c78"
+    //     cloud_threshold = 0.5
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("No se pudo leer {}: {}", path, error))?;
+        let mut params = ShaderParams::default();
+        let mut section: Option<String> = None;
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let warn = |message: String| eprintln!("{}:{}: {}", path, number + 1, message);
+            if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                let name = name.trim();
+                if params.group(name).is_none() {
+                    warn(format!("shader desconocido [{}]", name));
+                }
+                section = Some(name.to_string());
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn(format!("se esperaba `campo = valor`: {}", line));
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim().trim_matches('"'));
+            // En una sección desconocida ya se avisó en su encabezado
+            let Some(name) = section.as_deref() else {
+                warn(format!("{} fuera de una sección [shader]", key));
+                continue;
+            };
+            let Some(group) = params.group(name) else {
+                continue;
+            };
+            match group.param(key) {
+                Some(param) => {
+                    if !param.assign(value) {
+                        warn(format!("valor inválido para {}: {}", key, value));
+                    }
+                }
+                None => warn(format!("campo desconocido {}", key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+// Lo que sigue a un `#` fuera de comillas es comentario; dentro de ellas es un color
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
@@ -0,0 +1,125 @@
+use std::sync::OnceLock;
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::shaders::{builtin_shader, Shaded, ShaderKind};
+use crate::Uniforms;
+
+// Shader de fragmentos enchufable. Los de `shaders.rs` lo implementan y uno nuevo puede vivir en su
+// propio módulo: se registra al arrancar (ver `ShaderRegistry::register`) y se elige por su nombre
+// como cualquier otro, en la línea de comandos, los marcadores o los materiales.
+pub trait FragmentShader: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color;
+
+    // Salida completa: con emisión para el resplandor, o `None` para descartar el fragmento.
+    // Por defecto es el color de `shade` sin emisión.
+    fn output(&self, fragment: &Fragment, uniforms: &Uniforms) -> Option<Shaded> {
+        Some(self.shade(fragment, uniforms).into())
+    }
+
+    // Si escribe en el buffer de emisión; la niebla lo atenúa menos (ver `Fog::emissive`)
+    fn emits(&self) -> bool {
+        false
+    }
+
+    // Si las calcomanías se pintan encima; las vistas de depuración y las capas transparentes no
+    fn takes_decals(&self) -> bool {
+        true
+    }
+}
+
+// Los shaders por `ShaderKind`: primero los de `ShaderKind::ALL`, en ese orden, y después los
+// registrados, que reciben `ShaderKind::Custom` con su posición. Así los números de los de
+// siempre no cambian al agregar uno.
+pub struct ShaderRegistry {
+    builtin: Vec<Box<dyn FragmentShader>>,
+    custom: Vec<Box<dyn FragmentShader>>,
+}
+
+static SHADERS: OnceLock<ShaderRegistry> = OnceLock::new();
+
+impl ShaderRegistry {
+    pub fn builtin() -> Self {
+        ShaderRegistry { builtin: ShaderKind::ALL.into_iter().map(builtin_shader).collect(), custom: Vec::new() }
+    }
+
+    // Agrega un shader y devuelve su `ShaderKind`; el nombre no puede repetir el de otro
+    pub fn register(&mut self, shader: Box<dyn FragmentShader>) -> Result<ShaderKind, String> {
+        if self.find(shader.name()).is_some() {
+            return Err(format!("Ya hay un shader llamado '{}'", shader.name()));
+        }
+        let index = u16::try_from(self.custom.len()).map_err(|_| "Demasiados shaders registrados".to_string())?;
+        self.custom.push(shader);
+        Ok(ShaderKind::Custom(index))
+    }
+
+    // Deja el registro para todo el programa. Va antes de leer los argumentos, que ya eligen shaders
+    // por nombre; sin instalar ninguno queda el de los shaders de siempre.
+    pub fn install(self) -> Result<(), String> {
+        SHADERS.set(self).map_err(|_| "El registro de shaders ya estaba instalado".to_string())
+    }
+
+    pub fn global() -> &'static ShaderRegistry {
+        SHADERS.get_or_init(ShaderRegistry::builtin)
+    }
+
+    // El shader de `kind`; uno que no existe (un `Custom` de otro registro) cae en el shader sin efectos
+    pub fn get(&self, kind: ShaderKind) -> &dyn FragmentShader {
+        let shader = match kind {
+            ShaderKind::Custom(index) => self.custom.get(index as usize),
+            _ => ShaderKind::ALL.iter().position(|&builtin| builtin == kind).and_then(|index| self.builtin.get(index)),
+        };
+        match shader {
+            Some(shader) => shader.as_ref(),
+            None => self.get(ShaderKind::Default),
+        }
+    }
+
+    // Todos en orden: el de la tecla "S" y el de los números de shader
+    pub fn kinds(&self) -> impl Iterator<Item = ShaderKind> + '_ {
+        ShaderKind::ALL.into_iter().chain((0..self.custom.len()).map(|index| ShaderKind::Custom(index as u16)))
+    }
+
+    pub fn find(&self, name: &str) -> Option<ShaderKind> {
+        self.kinds().find(|&kind| self.get(kind).name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec3;
+    use crate::camera::Camera;
+    use crate::noise::GradientNoise;
+
+    struct FlatShader(Color);
+
+    impl FragmentShader for FlatShader {
+        fn name(&self) -> &'static str {
+            "flat"
+        }
+
+        fn shade(&self, _fragment: &Fragment, _uniforms: &Uniforms) -> Color {
+            self.0
+        }
+    }
+
+    #[test]
+    fn registered_shader_dispatches_by_kind_and_name() {
+        let mut registry = ShaderRegistry::builtin();
+        let kind = registry.register(Box::new(FlatShader(Color::new(12, 34, 56)))).unwrap_or_else(|error| panic!("{}", error));
+        assert_eq!(kind, ShaderKind::Custom(0));
+        assert_eq!(registry.find("FLAT"), Some(kind));
+        assert_eq!(registry.kinds().last(), Some(kind));
+
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 2.0), Vec3::zeros(), Vec3::y());
+        let uniforms = crate::create_uniforms(&camera, 4, 4, 4, 4, 0.0, Box::new(GradientNoise::new(1)));
+        let fragment = Fragment::new(1.0, 1.0, Color::black(), 0.5, Vec3::z(), 1.0, Vec3::z());
+        let shaded = registry.get(kind).output(&fragment, &uniforms).map(|shaded| shaded.color.to_hex());
+        assert_eq!(shaded, Some(Color::new(12, 34, 56).to_hex()));
+
+        // Un nombre repetido no entra y un `Custom` que no existe cae en el shader sin efectos
+        assert!(registry.register(Box::new(FlatShader(Color::black()))).is_err());
+        assert_eq!(registry.get(ShaderKind::Custom(9)).name(), "default");
+    }
+}
@@ -1,142 +1,515 @@
-use nalgebra_glm::{Vec3, Vec4, Mat3, mat4_to_mat3};
+use std::f32::consts::PI;
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat3, mat4_to_mat3, rotate_vec3};
 use crate::vertex::Vertex;
 use crate::Uniforms;
 use crate::fragment::Fragment;
 use crate::color::Color;
+use crate::light::Light;
+use crate::terrain::{earth_surface, EARTH_DRIFT};
+use crate::worley::{worley, worley_cell};
+use crate::spherical::{differential_rotation, great_circle_distance, lat_long_to_dir, tangent_basis, to_lat_long};
+use crate::noise::{sphere_noise, NoiseSource};
+use crate::animation::params;
+use crate::cli::key_values;
+use crate::environment::{environment, starfield};
+use crate::gizmo::project;
+use crate::displacement::DisplacementParams;
+use crate::eclipse::light_visibility;
+use crate::shader_params::EarthParams;
+use crate::shader_registry::{FragmentShader, ShaderRegistry};
 
-pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
-    let position = Vec4::new(
-        vertex.position.x,
-        vertex.position.y,
-        vertex.position.z,
-        1.0
-    );
+// Interior promedio de la granulación del sol sobre muchas celdas (medido), el color que queda de lejos
+const MEAN_CELL_INTERIOR: f32 = 0.45;
+// Zoom del detalle de las nubes y del oleaje corto respecto de su capa base
+const CLOUD_DETAIL_ZOOM: f32 = 6.0;
+const CHOP_ZOOM: f32 = 2.3;
 
-    let transformed = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
-
-    let w = transformed.w;
-    let transformed_position = Vec4::new(
-        transformed.x / w,
-        transformed.y / w,
-        transformed.z / w,
+// Con `displacement` el vértice se mueve a lo largo de su normal antes de la MVP, y esa posición
+// desplazada es la que llega interpolada a los fragmentos. El relieve usa el ruido de `shader`.
+pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms, shader: ShaderKind, displacement: Option<&DisplacementParams>) -> Vertex {
+    let (object_position, object_normal) = match displacement {
+        Some(params) => params.displace(uniforms.noise_for(shader), uniforms.plates.as_ref(), &vertex.position, &vertex.normal, uniforms.time),
+        None => (vertex.position, vertex.normal),
+    };
+    let position = Vec4::new(
+        object_position.x,
+        object_position.y,
+        object_position.z,
         1.0
     );
 
-    let screen_position = uniforms.viewport_matrix * transformed_position;
+    // Queda en espacio de recorte: la división por w y el viewport van después de recortar, en `clipping`
+    let clip_position = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
 
     let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
     let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
 
-    let transformed_normal = normal_matrix * vertex.normal;
+    let transformed_normal = normal_matrix * object_normal;
+    let world_position = (uniforms.model_matrix * position).xyz();
 
     Vertex {
-        position: vertex.position,
-        normal: vertex.normal,
+        position: object_position,
+        normal: object_normal,
         tex_coords: vertex.tex_coords,
         color: vertex.color,
-        transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
-        transformed_normal: transformed_normal
+        transformed_position: Vec3::new(clip_position.x, clip_position.y, clip_position.z),
+        transformed_normal: transformed_normal,
+        intensity: vertex_intensity(&world_position, &transformed_normal, uniforms),
+        clip_position,
+        inverse_w: 1.0,
     }
 }
 
-pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, current_shader: u32) -> Color {
-    match current_shader {
-        0 => sun_shader(fragment, uniforms),            // Shader de Sol estilo lava
-        1 => earth_clouds(fragment, uniforms),          // Shader de Tierra con nubes
-        2 => noise_shader(fragment, uniforms),          // Shader de ruido para manchas dinámicas
-        3 => moon_shader_bright_craters(fragment, uniforms), // Shader de Luna con cráteres brillantes
-        4 => ripple_shader(fragment, uniforms),         // Shader de ondas
-        5 => dynamic_cellular_shader(fragment, uniforms), // Nuevo shader dinámico celular
-        _ => dynamic_cellular_shader(fragment, uniforms),        // Shader por defecto
+// Salida de un fragmento: el color que se ve y la luz que emite por sí mismo, que va al buffer
+// de emisión y de ahí al resplandor (`Bloom`). Casi todos los shaders no emiten nada.
+pub struct Shaded {
+    pub color: Color,
+    pub emission: Color,
+}
+
+impl From<Color> for Shaded {
+    fn from(color: Color) -> Self {
+        Shaded { color, emission: Color::black() }
     }
 }
 
-fn ripple_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    // Posición del fragmento
-    let pos = fragment.vertex_position;
-    
-    // Configuración de la onda
-    let wave_speed = 0.3;
-    let wave_frequency = 10.0;
-    let wave_amplitude = 0.05;
-    let time = uniforms.time as f32 * wave_speed;
+// `None` descarta el fragmento: no se escribe ni en el color ni en el z-buffer
+pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, shader: ShaderKind) -> Option<Shaded> {
+    let shader = ShaderRegistry::global().get(shader);
+    let shaded = shader.output(fragment, uniforms)?;
 
-    // Calcular el desplazamiento basado en el ruido y la onda
-    let distance = (pos.x.powi(2) + pos.y.powi(2)).sqrt();
-    let ripple = (wave_frequency * (distance - time)).sin() * wave_amplitude;
+    // Las calcomanías se aplican encima de cualquier superficie
+    #[cfg(feature = "overlay")]
+    if shader.takes_decals() {
+        let color = uniforms.decals
+            .iter()
+            .fold(shaded.color, |color, decal| decal.shade(&fragment.vertex_position, color));
+        return Some(Shaded { color, ..shaded });
+    }
+    Some(shaded)
+}
 
-    // Colores de las ondas
-    let base_color = Color::new(70, 130, 180); // Azul acero
-    let ripple_color = Color::new(173, 216, 230); // Azul claro
+// Los shaders de este archivo, uno por variante de `ShaderKind` (ver `ShaderRegistry::builtin`)
+pub fn builtin_shader(kind: ShaderKind) -> Box<dyn FragmentShader> {
+    match kind {
+        ShaderKind::Sun => Box::new(SunShader),
+        ShaderKind::EarthClouds => Box::new(EarthShader),
+        ShaderKind::Noise => Box::new(NoiseShader),
+        ShaderKind::Moon => Box::new(MoonShader),
+        ShaderKind::Ripple => Box::new(RippleShader),
+        ShaderKind::Cellular => Box::new(CellularShader),
+        ShaderKind::NormalsDebug => Box::new(NormalsDebugShader),
+        ShaderKind::Europa => Box::new(EuropaShader),
+        ShaderKind::NoiseDebug => Box::new(NoiseDebugShader::default()),
+        ShaderKind::GasGiant => Box::new(GasGiantShader::default()),
+        ShaderKind::Ring => Box::new(RingShader::default()),
+        ShaderKind::Crystal => Box::new(CrystalShader),
+        ShaderKind::CloudLayer => Box::new(CloudLayerShader::default()),
+        ShaderKind::Textured => Box::new(TexturedShader),
+        ShaderKind::DepthDebug => Box::new(DepthDebugShader),
+        ShaderKind::UvDebug => Box::new(UvDebugShader),
+        ShaderKind::Default | ShaderKind::Custom(_) => Box::new(DefaultShader),
+    }
+}
 
-    // Mezclar los colores basados en el valor de la onda
-    let color_factor = ripple.clamp(0.0, 1.0);
-    let final_color = base_color.lerp(&ripple_color, color_factor);
+// Shader sin parámetros propios que solo llama a su función; con `debug` es una vista de depuración
+// y no lleva calcomanías
+macro_rules! function_shader {
+    ($shader:ident, $name:literal, $function:ident) => {
+        function_shader!($shader, $name, $function, true);
+    };
+    ($shader:ident, $name:literal, $function:ident, debug) => {
+        function_shader!($shader, $name, $function, false);
+    };
+    ($shader:ident, $name:literal, $function:ident, $decals:literal) => {
+        struct $shader;
 
-    // Aplicar intensidad para simular iluminación
-    final_color * fragment.intensity
+        impl FragmentShader for $shader {
+            fn name(&self) -> &'static str {
+                $name
+            }
+
+            fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+                $function(fragment, uniforms)
+            }
+
+            fn takes_decals(&self) -> bool {
+                $decals
+            }
+        }
+    };
 }
 
+function_shader!(EarthShader, "earth", earth_clouds);                // Tierra con nubes
+function_shader!(NoiseShader, "noise", noise_shader);                // Manchas dinámicas
+function_shader!(MoonShader, "moon", moon_shader_bright_craters);    // Luna con cráteres brillantes
+function_shader!(RippleShader, "ripple", ripple_shader);             // Ondas
+function_shader!(CellularShader, "cellular", dynamic_cellular_shader);
+function_shader!(EuropaShader, "europa", europa_shader);             // Luna helada con placas y líneas
+function_shader!(CrystalShader, "crystal", crystal_shader);          // Cristal que refracta lo de atrás
+function_shader!(TexturedShader, "textured", textured_shader);       // Imagen de `--texture` sobre las UV
+function_shader!(DefaultShader, "default", default_shader);          // Color del rasterizador, sin efectos
+function_shader!(NormalsDebugShader, "normals", normals_debug_shader, debug);
+function_shader!(DepthDebugShader, "depth", depth_debug_shader, debug);
+function_shader!(UvDebugShader, "uv", uv_debug_shader, debug);
 
-/// Shader para namecusein
-fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 50.0; // Zoom para el patrón de ruido
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let time = uniforms.time as f32 * 0.01; // Tiempo para animar el patrón
+// Sol estilo lava: el único que emite luz
+struct SunShader;
 
-    // Obtener el valor de ruido en 2D con desplazamiento temporal para movimiento
-    let noise_value = uniforms.noise.get_noise_2d(x * zoom + time, y * zoom + time);
+impl FragmentShader for SunShader {
+    fn name(&self) -> &'static str {
+        "sun"
+    }
 
-    // Definir los colores de las manchas solares
-    let bright_color = Color::new(255, 255, 102); // Amarillo brillante para áreas calientes
-    let dark_spot_color = Color::new(139, 0, 0);  // Rojo oscuro para manchas solares
-    let base_color = Color::new(255, 69, 0);      // Rojo anaranjado para la superficie
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+        sun_shader(fragment, uniforms).0
+    }
 
-    // Umbral para decidir entre zonas brillantes y oscuras
-    let spot_threshold = 0.6;
+    fn output(&self, fragment: &Fragment, uniforms: &Uniforms) -> Option<Shaded> {
+        let (color, emission) = sun_shader(fragment, uniforms);
+        Some(Shaded { color, emission })
+    }
+
+    fn emits(&self) -> bool {
+        true
+    }
+}
+
+// Shader de cada cuerpo. El número de cada variante es su posición en `ShaderRegistry::kinds`,
+// que además es el orden de la tecla "S". Los de este archivo tienen su variante y su struct en
+// `builtin_shader`; uno de otro módulo no toca nada de aquí: se registra con `ShaderRegistry::register`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShaderKind {
+    Sun,
+    EarthClouds,
+    Noise,
+    Moon,
+    Ripple,
+    Cellular,
+    NormalsDebug,
+    Europa,
+    Default,
+    NoiseDebug,
+    GasGiant,
+    Ring,
+    Crystal,
+    CloudLayer,
+    Textured,
+    DepthDebug,
+    UvDebug,
+    // Registrado al arrancar: su posición entre los registrados
+    Custom(u16),
+}
+
+impl ShaderKind {
+    pub const ALL: [ShaderKind; 17] = [
+        ShaderKind::Sun,
+        ShaderKind::EarthClouds,
+        ShaderKind::Noise,
+        ShaderKind::Moon,
+        ShaderKind::Ripple,
+        ShaderKind::Cellular,
+        ShaderKind::NormalsDebug,
+        ShaderKind::Europa,
+        ShaderKind::Default,
+        ShaderKind::NoiseDebug,
+        ShaderKind::GasGiant,
+        ShaderKind::Ring,
+        ShaderKind::Crystal,
+        ShaderKind::CloudLayer,
+        ShaderKind::Textured,
+        ShaderKind::DepthDebug,
+        ShaderKind::UvDebug,
+    ];
+
+    pub fn name(self) -> &'static str {
+        ShaderRegistry::global().get(self).name()
+    }
 
-    // Determinar el color basado en el valor de ruido
-    let noise_color = if noise_value < spot_threshold {
-        bright_color // Zonas brillantes
+    // Por nombre o por número, como se escriben en la línea de comandos y en los marcadores
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.parse::<u32>() {
+            Ok(index) => ShaderKind::try_from(index).ok(),
+            Err(_) => ShaderRegistry::global().find(value),
+        }
+    }
+
+    // Las vistas de depuración, el shader sin efectos, el de anillos (que solo tiene sentido
+    // sobre la malla plana), el de la capa de nubes y el texturizado (que depende de `--texture`)
+    // no entran en el ciclo de la tecla "S"
+    fn cycles(self) -> bool {
+        !self.is_debug_view() && !matches!(self, ShaderKind::Default | ShaderKind::Ring | ShaderKind::CloudLayer | ShaderKind::Textured)
+    }
+
+    // Las vistas de depuración escriben su color tal cual, sin el mapeo de tonos de la escena
+    pub fn is_debug_view(self) -> bool {
+        matches!(self, ShaderKind::NormalsDebug | ShaderKind::NoiseDebug | ShaderKind::DepthDebug | ShaderKind::UvDebug)
+    }
+
+    // Los que escriben en el buffer de emisión; la niebla los atenúa menos (ver `Fog::emissive`)
+    pub fn emits(self) -> bool {
+        ShaderRegistry::global().get(self).emits()
+    }
+
+    // Los que muestrean el color ya dibujado detrás (`uniforms.backdrop`): se dibujan después de lo opaco
+    pub fn reads_backdrop(self) -> bool {
+        matches!(self, ShaderKind::Crystal)
+    }
+
+    pub fn next(self) -> Self {
+        let kinds: Vec<ShaderKind> = ShaderRegistry::global().kinds().collect();
+        let position = kinds.iter().position(|&shader| shader == self).unwrap_or(0);
+        (1..=kinds.len())
+            .map(|offset| kinds[(position + offset) % kinds.len()])
+            .find(|shader| shader.cycles())
+            .unwrap_or(self)
+    }
+}
+
+impl TryFrom<u32> for ShaderKind {
+    type Error = String;
+
+    fn try_from(index: u32) -> Result<Self, Self::Error> {
+        let registry = ShaderRegistry::global();
+        registry
+            .kinds()
+            .nth(index as usize)
+            .ok_or_else(|| format!("No existe el shader número {} (hay {})", index, registry.kinds().count()))
+    }
+}
+
+impl From<ShaderKind> for u32 {
+    fn from(shader: ShaderKind) -> u32 {
+        ShaderRegistry::global().kinds().position(|kind| kind == shader).unwrap_or(0) as u32
+    }
+}
+
+// Vista de depuración del ruido crudo en gris, para comparar los dos muestreos en una captura:
+// el hemisferio x < 0 usa el muestreo plano (x, y) de antes y el x >= 0 el 3D de `sphere_noise`
+struct NoiseDebugShader {
+    zoom: f32,
+}
+
+impl Default for NoiseDebugShader {
+    fn default() -> Self {
+        NoiseDebugShader { zoom: 50.0 }
+    }
+}
+
+impl FragmentShader for NoiseDebugShader {
+    fn name(&self) -> &'static str {
+        "noise-debug"
+    }
+
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+        noise_debug_shader(self, fragment, uniforms)
+    }
+
+    fn takes_decals(&self) -> bool {
+        false
+    }
+}
+
+fn noise_debug_shader(shader: &NoiseDebugShader, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let noise = uniforms.noise_for(ShaderKind::NoiseDebug);
+    let zoom = shader.zoom;
+    let position = fragment.vertex_position;
+    let value = if position.x < 0.0 {
+        noise.noise2(position.x * zoom, position.y * zoom)
     } else {
-        dark_spot_color // Manchas solares
+        sphere_noise(noise, &position, zoom, Vec3::zeros())
     };
+    let gray = ((value * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::new(gray, gray, gray)
+}
 
-    // Mezclar el color base con el color determinado por el ruido
-    let final_color = base_color.lerp(&noise_color, noise_value.clamp(0.0, 1.0));
+// Vista de depuración: normal * 0.5 + 0.5 como color
+fn normals_debug_shader(fragment: &Fragment, _uniforms: &Uniforms) -> Color {
+    let normal = fragment.normal.normalize();
+    let channel = |c: f32| ((c * 0.5 + 0.5) * 255.0).round() as u8;
+    Color::new(channel(normal.x), channel(normal.y), channel(normal.z))
+}
 
-    // Ajustar la intensidad para simular iluminación
-    final_color * fragment.intensity
+// Distancia de vista en gris, de blanco en lo más cercano del cuerpo a negro a la altura de su
+// centro. La z del fragmento no es lineal; se vuelve a distancia con la proyección,
+// z_ndc = (P22 ze + P23) / -ze. La escala es la distancia del punto al centro del cuerpo, que en
+// una esfera es su radio.
+fn depth_debug_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let projection = &uniforms.projection_matrix;
+    let distance = projection[(2, 3)] / (fragment.depth + projection[(2, 2)]);
+    let to_view = uniforms.view_matrix * uniforms.model_matrix;
+    let center = (to_view * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+    let p = fragment.vertex_position;
+    let radius = ((to_view * Vec4::new(p.x, p.y, p.z, 1.0)).xyz() - center).magnitude().max(1e-6);
+    let gray = ((-center.z - distance) / radius).clamp(0.0, 1.0);
+    let level = (gray * 255.0).round() as u8;
+    Color::new(level, level, level)
 }
 
-fn noise_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    // Posición del fragmento
-    let pos = fragment.vertex_position;
+// Coordenadas UV de la malla en rojo y verde, repetidas en [0, 1)
+fn uv_debug_shader(fragment: &Fragment, _uniforms: &Uniforms) -> Color {
+    let channel = |c: f32| (c.rem_euclid(1.0) * 255.0).round() as u8;
+    Color::new(channel(fragment.tex_coords.x), channel(fragment.tex_coords.y), 0)
+}
+
+// Fuente de ondas sobre la superficie de la esfera, en espacio de objeto
+#[derive(Debug, Clone, Copy)]
+pub struct RippleSource {
+    pub origin: Vec3,     // Dirección inicial de la fuente
+    pub drift_axis: Vec3, // Eje alrededor del cual deriva la fuente
+    pub drift_speed: f32, // Radianes por segundo
+    pub phase: f32,
+    pub frequency: f32,   // Ondas por radián de arco
+    pub amplitude: f32,
+    pub damping: f32,     // Atenuación exponencial con la distancia
+}
+
+impl RippleSource {
+    fn position(&self, time: f32) -> Vec3 {
+        rotate_vec3(&self.origin.normalize(), self.drift_speed * time, &self.drift_axis)
+    }
+}
+
+pub fn default_ripple_sources() -> Vec<RippleSource> {
+    vec![
+        RippleSource {
+            origin: Vec3::new(0.0, 0.3, 1.0),
+            drift_axis: Vec3::new(0.0, 1.0, 0.0),
+            drift_speed: 0.12,
+            phase: 0.0,
+            frequency: 30.0,
+            amplitude: 0.6,
+            damping: 0.8,
+        },
+        RippleSource {
+            origin: Vec3::new(0.8, -0.2, 0.6),
+            drift_axis: Vec3::new(1.0, 0.5, 0.0),
+            drift_speed: -0.18,
+            phase: 1.5,
+            frequency: 24.0,
+            amplitude: 0.5,
+            damping: 0.6,
+        },
+        RippleSource {
+            origin: Vec3::new(-0.7, 0.6, 0.4),
+            drift_axis: Vec3::new(0.0, 0.3, 1.0),
+            drift_speed: 0.09,
+            phase: 3.0,
+            frequency: 36.0,
+            amplitude: 0.4,
+            damping: 1.2,
+        },
+    ]
+}
+
+// Colores del gigante gaseoso; por defecto los de Júpiter
+#[derive(Debug, Clone, Copy)]
+pub struct GasGiantPalette {
+    pub zone: Color,  // Bandas claras
+    pub belt: Color,  // Cinturones oscuros
+    pub polar: Color, // Casquetes, más apagados
+    pub storm: Color, // Gran mancha
+}
+
+impl Default for GasGiantPalette {
+    fn default() -> Self {
+        GasGiantPalette {
+            zone: Color::new(232, 218, 190),
+            belt: Color::new(172, 118, 80),
+            polar: Color::new(150, 142, 132),
+            storm: Color::new(196, 92, 62),
+        }
+    }
+}
+
+fn ripple_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let params = &uniforms.shader_params.ripple;
+    let time = uniforms.time;
+
+    // Sumar las ondas de cada fuente; la interferencia aparece donde se cruzan los anillos
+    let mut ripple = 0.0;
+    for source in &uniforms.ripple_sources {
+        let distance = great_circle_distance(&fragment.vertex_position, &source.position(time));
+        let wave = (source.frequency * (distance - time * params.wave_speed) + source.phase).sin();
+        ripple += wave * source.amplitude * (-source.damping * distance).exp();
+    }
+
+    // Mezclar los colores basados en el valor de la onda
+    let color_factor = (0.5 + 0.5 * ripple).clamp(0.0, 1.0);
+    let final_color = params.base_color.lerp(&params.crest_color, color_factor);
+
+    compute_lighting(fragment, uniforms).shade(final_color, &uniforms.light, 1.0)
+}
 
-    // Radio del círculo
-    let radius = 0.1;
 
-    // Velocidad del movimiento
-    let speed = 0.2;
-    let time = uniforms.time as f32 * 0.01;  // Escala de tiempo para el movimiento
+/// Shader para namecusein. Es emisivo: no usa la luz de la escena
+// Devuelve el color y la emisión
+fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> (Color, Color) {
+    let noise = uniforms.noise_for(ShaderKind::Sun);
+    let params = &uniforms.shader_params.sun;
+
+    // Todos los rasgos se muestrean en la posición rotada según su latitud
+    let surface_position = differential_rotation(&fragment.vertex_position, params.equatorial_rate, params.differential, uniforms.time);
+
+    // Granulación: celdas de Worley con interiores brillantes y bordes (lanes) más oscuros. De lejos,
+    // con varias celdas por píxel, queda su promedio.
+    let granule_detail = detail(fragment, uniforms, params.granule_zoom);
+    let cell_interior = if granule_detail > 0.0 {
+        let (f1, f2) = worley(&(surface_position * params.granule_zoom), uniforms.time * params.granule_drift);
+        smoothstep(0.0, 0.4, f2 - f1)
+    } else {
+        MEAN_CELL_INTERIOR
+    };
+    let cell_interior = MEAN_CELL_INTERIOR + (cell_interior - MEAN_CELL_INTERIOR) * granule_detail;
+    let granulation_color = params.lane_color.lerp(&params.cell_color, cell_interior);
+
+    // Ruido 3D sobre la superficie; el movimiento viene de la rotación diferencial. Su promedio es 0.
+    let spot_detail = detail(fragment, uniforms, params.zoom * noise.frequency());
+    let noise_value = if spot_detail > 0.0 { sphere_noise(noise, &surface_position, params.zoom, Vec3::zeros()) * spot_detail } else { 0.0 };
+
+    // Zonas calientes en amarillo por debajo del umbral, manchas solares por encima
+    let noise_color = if noise_value < params.spot_threshold {
+        params.bright_color
+    } else {
+        params.spot_color
+    };
+
+    // Las manchas solares se mezclan sobre la granulación
+    let surface_color = granulation_color.lerp(&noise_color, noise_value.clamp(0.0, 1.0));
 
-    // Luz direccional
-    let light_dir = Vec3::new(1.0, 1.0, 1.0).normalize();
+    // Oscurecimiento de limbo: más oscuro y rojizo a medida que n·v se acerca a 0
     let normal = fragment.normal.normalize();
-    let intensity = normal.dot(&light_dir).max(0.0);
+    let view_dir = view_direction(fragment, uniforms);
+    let mu = normal.dot(&view_dir).max(0.0);
+    let limb = params.limb_color.lerp(&surface_color, mu.powf(params.limb_power));
+
+    // Emisión: el interior de las celdas y el amarillo de las zonas calientes; los carriles oscuros
+    // y las manchas casi no brillan. Sin oscurecimiento de limbo, para que el resplandor rodee el disco.
+    let hot_spot = if noise_value < params.spot_threshold { noise_value.clamp(0.0, 1.0) } else { 0.0 };
+    let emission = surface_color * cell_interior.max(hot_spot);
+
+    // El brillo de la corona se impone al oscurecimiento justo en el borde
+    (limb.lerp(&params.rim_color, params.rim_strength * fresnel(&normal, &view_dir, params.rim_power)), emission)
+}
+
+fn noise_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let params = &uniforms.shader_params.noise;
+    let pos = fragment.vertex_position;
+    let time = uniforms.time;
+
+    let lighting = compute_lighting(fragment, uniforms);
 
     // Círculos en movimiento
     let mut circle_mask = 0.0;
     for i in -3..=3 {
         for j in -3..=3 {
             // Desplazamiento dinámico basado en el tiempo
-            let offset_x = (i as f32 * 0.3) + (time * speed);
-            let offset_y = (j as f32 * 0.3) + (time * speed * 0.5);
+            let offset_x = (i as f32 * params.spacing) + (time * params.speed);
+            let offset_y = (j as f32 * params.spacing) + (time * params.speed * 0.5);
 
             let dist_to_circle = ((pos.x - offset_x).powi(2) + (pos.y - offset_y).powi(2)).sqrt();
 
-            if dist_to_circle < radius {
+            if dist_to_circle < params.radius {
                 circle_mask = 1.0;
                 break;
             }
@@ -148,126 +521,684 @@ fn noise_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
     // Determinar el color basado en si está dentro del círculo
     if circle_mask > 0.5 {
-        // Círculo negro con sombreado basado en la luz
-        Color::new(0, 0, 0) * intensity
+        // Círculo (negro por defecto); solo se le ve el brillo especular
+        lighting.shade(params.spot_color, &uniforms.light, 1.0)
     } else {
-        // Fondo blanco también afectado por la luz para un toque más realista
-        Color::new(255, 255, 255) * (0.5 + intensity * 0.5)
+        // Fondo también afectado por la luz para un toque más realista
+        lighting.shade(params.background_color, &uniforms.light, 1.0)
     }
 }
 
 
 fn moon_shader_bright_craters(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 50.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let t = uniforms.time as f32 * 0.1;
+    let noise = uniforms.noise_for(ShaderKind::Moon);
+    let params = &uniforms.shader_params.moon;
+    let t = uniforms.time * params.drift;
 
     // Añadimos un efecto pulsante a los cráteres
-    let pulsate = (t * 0.5).sin() * 0.05;
+    let pulsate = (t * 0.5).sin() * params.pulse;
+
+    // Con los cráteres más chicos que el píxel la Luna es lisa, del gris promedio de sus colores
+    let crater_detail = detail(fragment, uniforms, params.zoom * noise.frequency());
+    let average_color = params.plain_color.lerp(&params.crater_color, 0.25).lerp(&params.floor_color, 0.25);
+    if crater_detail <= 0.0 {
+        return compute_lighting(fragment, uniforms).shade(average_color, &uniforms.light, params.specular);
+    }
 
     // Ruido para la textura de la superficie
-    let surface_noise = uniforms.noise.get_noise_2d(x * zoom + t, y * zoom + t);
+    let crater_noise = |position: &Vec3| sphere_noise(noise, position, params.zoom, Vec3::new(t, t, 0.0));
+    let surface_noise = crater_noise(&fragment.vertex_position);
 
-    let gray_color = Color::new(200, 200, 200);
-    let bright_crater_color = Color::new(220, 220, 220); // Cráter más brillante
-    let dynamic_color = Color::new(250, 250, 250); // Toque dinámico brillante
+    let crater_threshold = params.crater_threshold + pulsate; // Dinamismo en los cráteres
 
-    let crater_threshold = 0.4 + pulsate; // Dinamismo en los cráteres
+    // Relieve con el mismo ruido: el suelo se hunde hacia los cráteres (ruido bajo) y sube hacia la
+    // llanura gris, así una pared del cráter mira a la luz y la opuesta queda en sombra
+    let crater_height = |position: &Vec3| smoothstep(crater_threshold - 0.8, crater_threshold, crater_noise(position));
+    let normal = bump_normal(fragment, uniforms, params.bump_strength * crater_detail, crater_height);
 
-    // Definir el color base de la luna
+    // Llanura gris, borde del cráter más claro y el fondo todavía más
     let base_color = if surface_noise > crater_threshold {
-        gray_color
+        params.plain_color
     } else if surface_noise > crater_threshold - 0.1 {
-        bright_crater_color
+        params.crater_color
     } else {
-        dynamic_color // Zonas más dinámicas
+        params.floor_color
     };
+    let base_color = average_color.lerp(&base_color, crater_detail);
 
-    base_color * fragment.intensity
+    lighting_with_normal(fragment, uniforms, &normal).shade(base_color, &uniforms.light, params.specular)
 }
 
 fn earth_clouds(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 80.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let t = uniforms.time as f32 * 0.1;
-
-    // Ruido para la superficie terrestre
-    let surface_noise = uniforms.noise.get_noise_2d(x * zoom + t, y * zoom);
-
-    let ocean_color = Color::new(0, 105, 148);     // Azul océano
-    let land_color = Color::new(34, 139, 34);      // Verde tierra
-    let desert_color = Color::new(210, 180, 140);  // Marrón desierto
-    let snow_color = Color::new(255, 250, 250);    // Blanco nieve
-
-    // Umbrales para definir las diferentes zonas geográficas
-    let snow_threshold = 0.7;
-    let land_threshold = 0.4;
-    let desert_threshold = 0.3;
-
-    // Selección de color base
-    let base_color = if y.abs() > snow_threshold {
-        snow_color
-    } else if surface_noise > land_threshold {
-        land_color
-    } else if surface_noise > desert_threshold {
-        desert_color
-    } else {
-        ocean_color
+    let noise = uniforms.noise_for(ShaderKind::EarthClouds);
+    let t = uniforms.time * EARTH_DRIFT;
+
+    // Biomas procedurales; un mapa de control pintado a mano puede mezclarse encima
+    let params = &uniforms.shader_params.earth;
+    let (procedural, ocean_depth) = earth_surface(params, noise, &fragment.vertex_position, t, uniforms.plates.as_ref(), uniforms.seasons.as_ref(), uniforms.time);
+    #[cfg(feature = "io")]
+    let base_color = match &uniforms.control_map {
+        Some(control_map) => control_map.blend(&fragment.vertex_position, procedural),
+        None => procedural,
+    };
+    #[cfg(not(feature = "io"))]
+    let base_color = procedural;
+
+    // Nubes como campo de alturas: lo que el ruido supera el umbral es la altura de la nube
+    let light_dir = light_direction(fragment, uniforms);
+
+    let cloud_detail = detail(fragment, uniforms, params.cloud_zoom * CLOUD_DETAIL_ZOOM * noise.frequency());
+
+    // Las nubes giran en bloque alrededor del polo. Con la capa de nubes aparte (`--cloud-shell`)
+    // la superficie queda despejada y las nubes las dibuja `cloud_layer_shader`.
+    let cloud_height = |position: Vec3| {
+        if uniforms.cloud_shell {
+            return 0.0;
+        }
+        cloud_coverage(uniforms, &differential_rotation(&position, params.cloud_rotation, 0.0, uniforms.time), cloud_detail)
     };
 
-    // Dinámica de nubes
-    let cloud_zoom = 100.0; // Ajuste para las nubes
-    let cloud_noise = uniforms.noise.get_noise_2d(x * cloud_zoom + t * 0.5, y * cloud_zoom + t * 0.5);
+    // Pendiente por diferencias finitas en espacio del objeto, independiente de la resolución
+    let epsilon = 0.005;
+    let position = fragment.vertex_position;
+    let height = cloud_height(position);
+    let derivative = |axis: Vec3| {
+        (cloud_height(position + axis * epsilon) - cloud_height(position - axis * epsilon)) / (2.0 * epsilon)
+    };
+    let gradient = Vec3::new(derivative(Vec3::x()), derivative(Vec3::y()), derivative(Vec3::z()));
+    let normal = fragment.normal.normalize();
+    let tangent_gradient = gradient - normal * gradient.dot(&normal);
+    let cloud_normal = (normal - tangent_gradient * params.cloud_height_scale).normalize();
+    let slope = cloud_normal.dot(&light_dir) - normal.dot(&light_dir);
+
+    // Laderas hacia el sol más claras, las opuestas en una sombra gris azulada suave
+    let highlight = smoothstep(0.0, params.cloud_shadow_softness, slope);
+    let shade = smoothstep(0.0, params.cloud_shadow_softness, -slope);
+    let cloud_tone = params.cloud_color.lerp(&params.cloud_shade, shade) * (1.0 + 0.08 * highlight);
 
-    // Crear nubes dinámicas y movimiento
-    let cloud_color = Color::new(255, 255, 255); // Blanco para nubes
-    let sky_gradient = Color::new(135, 206, 250); // Azul cielo claro
+    let ground = base_color.lerp(&params.sky_tint, 0.1) * (1.0 - params.ground_shadow * height);
+    let cloud_cover = smoothstep(0.0, 0.3, height) * 0.85;
+    let final_color = ground.lerp(&cloud_tone, cloud_cover);
+
+    // Las nubes difunden la luz: el brillo especular solo queda donde se ve el suelo. En el mar la
+    // difusa sigue la normal de la esfera y el brillo, más cerrado, la normal de las olas.
+    let mut lighting = compute_lighting(fragment, uniforms);
+    if ocean_depth.is_some() && normal.dot(&light_dir) > 0.0 {
+        // De lejos las olas no caben en el píxel y el brillo queda como el de un mar en calma
+        let wave_detail = detail(fragment, uniforms, params.wave_zoom * CHOP_ZOOM * noise.frequency());
+        let wave_normal = if wave_detail > 0.0 {
+            bump_normal(fragment, uniforms, params.wave_strength * wave_detail, |p| ocean_waves(noise, params, p, uniforms.time))
+        } else {
+            normal
+        };
+        let half_vector = (light_dir + view_direction(fragment, uniforms)).normalize();
+        let glint = wave_normal.dot(&half_vector).max(0.0).powf(params.ocean_shininess);
+        lighting.specular = glint * params.ocean_specular * uniforms.light.specular * light_reaching(&world_position(fragment, uniforms), uniforms);
+    }
+    let lit = lighting.shade(final_color, &uniforms.light, 1.0 - cloud_cover);
+
+    // Atmósfera en el borde, después de las nubes para que también se tiñan; de noche no brilla
+    let daylight = smoothstep(-0.2, 0.3, normal.dot(&light_dir));
+    let rim = fresnel(&normal, &view_direction(fragment, uniforms), params.rim_power) * params.rim_strength * daylight;
+    lit.lerp(&params.rim_color, rim)
+}
+
+// Oleaje como campo de alturas: dos capas de ruido que se desplazan en direcciones distintas, así
+// donde se cruzan las olas cambian de forma en vez de solo deslizarse
+fn ocean_waves(noise: &dyn NoiseSource, params: &EarthParams, position: &Vec3, time: f32) -> f32 {
+    let drift = time * params.wave_speed;
+    let swell = sphere_noise(noise, position, params.wave_zoom, Vec3::new(drift, 0.0, drift * 0.5));
+    let chop = sphere_noise(noise, position, params.wave_zoom * CHOP_ZOOM, Vec3::new(-drift * 0.7, drift, 0.0));
+    swell + chop * 0.5
+}
 
-    let cloud_intensity = cloud_noise.clamp(0.4, 0.7) - 0.4;
-    let final_color = if cloud_noise > 0.6 {
-        base_color.lerp(&cloud_color, cloud_intensity * 0.5) // Mezcla el color base con nubes
+// Cobertura de nubes en [0, 1] en un punto que ya giró con las nubes; el detalle fino
+// además se desplaza y cambia de forma con el tiempo
+// `detail` (ver `detail`) apaga ese detalle fino cuando ya no cabe en el píxel.
+fn cloud_coverage(uniforms: &Uniforms, position: &Vec3, detail: f32) -> f32 {
+    // También la llama la capa de nubes: con el generador de la Tierra las dos coinciden
+    let noise = uniforms.noise_for(ShaderKind::EarthClouds);
+    let params = &uniforms.shader_params.earth;
+    let t = uniforms.time * params.cloud_drift;
+
+    let base = sphere_noise(noise, position, params.cloud_zoom, Vec3::zeros());
+    let coverage = ((base - params.cloud_threshold) / (1.0 - params.cloud_threshold).max(1e-6)).clamp(0.0, 1.0);
+    if coverage <= 0.0 || detail <= 0.0 {
+        return coverage;
+    }
+    let fine = sphere_noise(noise, position, params.cloud_zoom * CLOUD_DETAIL_ZOOM, Vec3::new(t, 0.0, -t));
+    (coverage * (1.0 + fine * params.cloud_detail * detail)).clamp(0.0, 1.0)
+}
+
+// Capa de nubes para la esfera aparte: blanco con la opacidad de la cobertura, y nada donde no hay
+// nubes. El giro lo pone la matriz de la capa, así que el ruido se muestrea en su espacio del objeto.
+struct CloudLayerShader {
+    max_opacity: f32,
+}
+
+impl Default for CloudLayerShader {
+    fn default() -> Self {
+        CloudLayerShader { max_opacity: 0.85 }
+    }
+}
+
+impl FragmentShader for CloudLayerShader {
+    fn name(&self) -> &'static str {
+        "clouds"
+    }
+
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+        cloud_layer_shader(self, fragment, uniforms).unwrap_or(Color::black().with_alpha(0.0))
+    }
+
+    fn output(&self, fragment: &Fragment, uniforms: &Uniforms) -> Option<Shaded> {
+        cloud_layer_shader(self, fragment, uniforms).map(Shaded::from)
+    }
+
+    fn takes_decals(&self) -> bool {
+        false
+    }
+}
+
+fn cloud_layer_shader(shader: &CloudLayerShader, fragment: &Fragment, uniforms: &Uniforms) -> Option<Color> {
+    let params = &uniforms.shader_params.earth;
+    let fine = detail(fragment, uniforms, params.cloud_zoom * CLOUD_DETAIL_ZOOM * uniforms.noise_for(ShaderKind::EarthClouds).frequency());
+    let coverage = smoothstep(0.0, 0.3, cloud_coverage(uniforms, &fragment.vertex_position, fine));
+    if coverage <= 0.0 {
+        return None;
+    }
+    let lit = compute_lighting(fragment, uniforms).shade(params.cloud_color, &uniforms.light, 0.0);
+    Some(lit.with_alpha(coverage * shader.max_opacity))
+}
+
+// Luna helada estilo Europa: placas de hielo que derivan muy despacio, cruzadas por líneas
+// largas y curvas de color marrón rojizo. El hielo liso tiene un brillo especular cerrado y fuerte;
+// las líneas, más rugosas, brillan menos.
+fn europa_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let noise = uniforms.noise_for(ShaderKind::Europa);
+    let params = &uniforms.shader_params.europa;
+    let tint_palette = [params.clean_ice, params.blue_ice, params.cream_ice, params.gray_ice];
+
+    let position = fragment.vertex_position;
+    let normal = fragment.normal.normalize();
+
+    // Placas: cada celda toma un tinte de la paleta y los bordes quedan un poco más oscuros
+    let (f1, f2, plate) = worley_cell(&(position * params.plate_zoom), uniforms.time * params.drift_speed);
+    let tint = tint_palette[((plate.x * tint_palette.len() as f32) as usize).min(tint_palette.len() - 1)];
+    let plate_edge = smoothstep(0.0, 0.08, f2 - f1);
+    let ice = tint * (0.9 + 0.1 * plate_edge);
+
+    // Campo de dirección del flujo: un eje casi ecuatorial que un ruido de baja frecuencia hace girar,
+    // así las líneas siguen arcos largos que se curvan poco a poco
+    let flow_angle = noise.noise3(position.x * 40.0, position.y * 40.0, position.z * 40.0) * 0.8;
+    let flow = Vec3::new(flow_angle.cos(), 0.35, flow_angle.sin()).normalize();
+
+    // Ruido con crestas sobre coordenadas comprimidas a lo largo del flujo: las crestas se alargan en esa dirección
+    let stretched = position - flow * (position.dot(&flow) * (1.0 - 1.0 / params.linea_stretch));
+    let p = stretched * params.linea_density;
+    let ridge = 1.0 - noise.noise3(p.x, p.y, p.z).abs();
+    let linea = smoothstep(1.0 - params.linea_width, 1.0 - params.linea_width * 0.3, ridge);
+    let surface = ice.lerp(&params.linea_color, linea * 0.85);
+
+    // Blinn-Phong propio: especular cerrado en el hielo y más débil sobre las líneas.
+    // La cara de noche no tiene brillo, igual que en `compute_lighting`.
+    let lighting = compute_lighting(fragment, uniforms);
+    let light_dir = light_direction(fragment, uniforms);
+    let half_vector = (light_dir + view_direction(fragment, uniforms)).normalize();
+    let facing = if normal.dot(&light_dir) > 0.0 { 1.0 } else { 0.0 };
+    let specular = facing * normal.dot(&half_vector).max(0.0).powf(params.ice_shininess) * (1.0 - 0.7 * linea);
+
+    Lighting { specular: specular * 0.8, ..lighting }.shade(surface, &uniforms.light, 1.0)
+}
+
+// Gigante gaseoso: bandas de latitud cuyos bordes el ruido vuelve turbulentos, cada una
+// desplazándose a su propia velocidad, y una gran mancha ovalada que deriva despacio
+#[derive(Clone, Copy)]
+struct GasGiantShader {
+    band_count: f32,          // Pares zona/cinturón de polo a polo
+    jet_rate: f32,            // Radianes por segundo de las corrientes más rápidas
+    turbulence_zoom: f32,     // Escala del ruido que ondula los bordes
+    turbulence_strength: f32, // Cuánto se desplaza un borde, en unidades de sin(latitud)
+    turbulence_drift: f32,    // Desplazamiento del ruido de los bordes por segundo
+    storm_latitude: f32,
+    storm_longitude: f32,
+    storm_drift: f32,         // Radianes de longitud por segundo
+    storm_size: (f32, f32),   // Semiejes este-oeste y norte-sur, en radianes de arco
+    storm_swirl: f32,         // Giro del interior de la mancha
+    swirl_rate: f32,          // Radianes por segundo que gira la espiral
+}
+
+impl Default for GasGiantShader {
+    fn default() -> Self {
+        GasGiantShader {
+            band_count: 7.0,
+            jet_rate: 0.18,
+            turbulence_zoom: 250.0,
+            turbulence_strength: 0.05,
+            turbulence_drift: 0.06,
+            storm_latitude: -0.38,      // Unos 22° sur
+            storm_longitude: PI / 2.0,  // Empieza de cara a +Z
+            storm_drift: -0.024,
+            storm_size: (0.32, 0.18),
+            storm_swirl: 2.5,
+            swirl_rate: 0.6,
+        }
+    }
+}
+
+impl FragmentShader for GasGiantShader {
+    fn name(&self) -> &'static str {
+        "gas-giant"
+    }
+
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+        gas_giant_shader(self, fragment, uniforms)
+    }
+}
+
+fn gas_giant_shader(shader: &GasGiantShader, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let noise = uniforms.noise_for(ShaderKind::GasGiant);
+    let GasGiantShader {
+        band_count, jet_rate, turbulence_zoom, turbulence_strength, turbulence_drift, storm_latitude, storm_longitude, storm_drift, storm_size, storm_swirl, swirl_rate,
+    } = *shader;
+    let palette = uniforms.gas_giant;
+
+    let position = fragment.vertex_position.normalize();
+    let (latitude, _) = to_lat_long(&position);
+
+    // Corrientes alternas: cada franja entre dos bordes gira en bloque con su propia velocidad,
+    // en sentido contrario a sus vecinas. Dentro de una franja no hay cizalla, así que la
+    // turbulencia no se estira en rayas con el tiempo. Solo se mueve la longitud: no hay costura.
+    let turbulence_in = |stripe: f32| {
+        let direction = if stripe.rem_euclid(2.0) < 1.0 { 1.0 } else { -0.7 };
+        let jet = jet_rate * direction * (0.5 + 0.5 * (stripe * 1.7).sin().abs());
+        let flowing = differential_rotation(&position, jet, 0.0, uniforms.time);
+        // Ruido 3D sobre la posición desplazada: sin costura en la longitud ni pellizco en los polos
+        let turbulence = sphere_noise(noise, &flowing, turbulence_zoom, Vec3::new(0.0, uniforms.time * turbulence_drift, 0.0));
+        let detail = sphere_noise(noise, &flowing, turbulence_zoom * 4.0, Vec3::zeros());
+        turbulence + 0.3 * detail
+    };
+
+    // Cerca de un borde se mezcla con la franja vecina para que el salto de velocidad no se vea
+    let stripe_coordinate = latitude.sin() * band_count;
+    let stripe = stripe_coordinate.floor();
+    let within = stripe_coordinate - stripe;
+    let neighbor = if within < 0.5 { stripe - 1.0 } else { stripe + 1.0 };
+    let edge_blend = smoothstep(0.3, 0.5, (within - 0.5).abs()) * 0.5;
+    let turbulence = turbulence_in(stripe) * (1.0 - edge_blend) + turbulence_in(neighbor) * edge_blend;
+    let band_coordinate = latitude.sin() + turbulence * turbulence_strength;
+
+    // sin(latitud) reparte las bandas con el mismo ancho aparente visto de frente
+    let band = (band_coordinate * band_count * PI).sin() * 0.5 + 0.5;
+    let mut color = palette.belt.lerp(&palette.zone, smoothstep(0.3, 0.7, band));
+    color = color.lerp(&palette.polar, smoothstep(0.65, 0.95, latitude.sin().abs()));
+
+    // La mancha se mide en el plano tangente de su centro, así conserva la forma a cualquier latitud
+    let storm_center = lat_long_to_dir(storm_latitude, storm_longitude + storm_drift * uniforms.time);
+    let facing = position.dot(&storm_center);
+    if facing > 0.0 {
+        let east = Vec3::y().cross(&storm_center).normalize();
+        let north = storm_center.cross(&east);
+        let offset = position / facing - storm_center; // Proyección gnomónica
+        let (u, v) = (offset.dot(&east) / storm_size.0, offset.dot(&north) / storm_size.1);
+        let radius = (u * u + v * v).sqrt();
+        if radius < 1.4 {
+            // Espiral interior: el ruido se muestrea en coordenadas giradas según la distancia al centro
+            let angle = storm_swirl * (1.0 - radius).max(0.0) + uniforms.time * swirl_rate;
+            let (sin_a, cos_a) = angle.sin_cos();
+            let swirl = Vec3::new(u * cos_a - v * sin_a, u * sin_a + v * cos_a, 0.0);
+            let texture = sphere_noise(noise, &(swirl + Vec3::new(3.0, 3.0, 3.0)), 120.0, Vec3::zeros());
+            let storm = palette.storm.lerp(&palette.zone, 0.25 + 0.25 * texture);
+            // Un borde claro separa la mancha de las bandas que la rodean
+            let collar = smoothstep(0.9, 1.1, radius) * (1.0 - smoothstep(1.1, 1.4, radius));
+            color = color.lerp(&palette.zone, collar * 0.6);
+            color = color.lerp(&storm, 1.0 - smoothstep(0.85, 1.0, radius));
+        }
+    }
+
+    compute_lighting(fragment, uniforms).shade(color, &uniforms.light, 0.15)
+}
+
+// Radios de la malla de anillos, en radios del planeta; el shader mide sobre la misma escala
+pub const RING_INNER_RADIUS: f32 = 1.25;
+pub const RING_OUTER_RADIUS: f32 = 2.3;
+
+// Anillos: bandas 1D según la distancia al centro, con huecos que se descartan
+#[derive(Clone, Copy)]
+struct RingShader {
+    band_zoom: f32,       // Frecuencia de las bandas a lo largo del radio
+    gap_threshold: f32,   // Densidad por debajo de la cual no hay anillo
+    division: (f32, f32), // División de Cassini, como fracción del ancho del anillo
+    inner_color: Color,
+    outer_color: Color,
+}
+
+impl Default for RingShader {
+    fn default() -> Self {
+        RingShader {
+            band_zoom: 2000.0,
+            gap_threshold: 0.3,
+            division: (0.58, 0.63),
+            inner_color: Color::new(140, 122, 104),
+            outer_color: Color::new(226, 208, 176),
+        }
+    }
+}
+
+impl FragmentShader for RingShader {
+    fn name(&self) -> &'static str {
+        "ring"
+    }
+
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+        ring_shader(self, fragment, uniforms).unwrap_or(Color::black().with_alpha(0.0))
+    }
+
+    fn output(&self, fragment: &Fragment, uniforms: &Uniforms) -> Option<Shaded> {
+        ring_shader(self, fragment, uniforms).map(Shaded::from)
+    }
+
+    fn takes_decals(&self) -> bool {
+        false
+    }
+}
+
+fn ring_shader(shader: &RingShader, fragment: &Fragment, uniforms: &Uniforms) -> Option<Color> {
+    let noise = uniforms.noise_for(ShaderKind::Ring);
+    let RingShader { band_zoom, gap_threshold, division, inner_color, outer_color } = *shader;
+
+    let position = fragment.vertex_position;
+    let radius = (position.x * position.x + position.z * position.z).sqrt();
+    let t = ((radius - RING_INNER_RADIUS) / (RING_OUTER_RADIUS - RING_INNER_RADIUS)).clamp(0.0, 1.0);
+
+    // Ruido muestreado sobre una recta: solo depende del radio, así cada banda es un círculo completo
+    let coarse = noise.noise2(radius * band_zoom, 0.0);
+    let fine = noise.noise2(radius * band_zoom * 5.0, 37.0);
+    let density = 0.5 + 0.35 * coarse + 0.15 * fine;
+    if (division.0..division.1).contains(&t) || density < gap_threshold {
+        return None;
+    }
+
+    let color = inner_color.lerp(&outer_color, t) * (0.6 + 0.5 * density);
+    Some(compute_lighting(fragment, uniforms).shade(color, &uniforms.light, 0.0))
+}
+
+// Material del planeta de cristal
+#[derive(Debug, Clone, Copy)]
+pub struct CrystalMaterial {
+    pub ior: f32,              // Índice de refracción; 1 es aire y no desvía nada
+    pub tint: Color,           // Color que absorbe la luz al atravesarlo
+    pub facet_scale: f32,      // Celdas de Worley por unidad de radio; cada una es una faceta
+    pub facet_strength: f32,   // Cuánto inclina cada faceta la normal
+    pub thickness: f32,        // Recorrido del rayo refractado antes de muestrear el fondo
+}
+
+impl Default for CrystalMaterial {
+    fn default() -> Self {
+        CrystalMaterial {
+            ior: 1.45,
+            tint: Color::new(190, 230, 255),
+            facet_scale: 6.0,
+            facet_strength: 0.35,
+            thickness: 0.8,
+        }
+    }
+}
+
+impl CrystalMaterial {
+    // `--crystal ior=1.45 tint=bee6ff facets=6 facet_strength=0.35 thickness=0.8`
+    pub fn from_args(args: &[String]) -> Self {
+        let defaults = CrystalMaterial::default();
+        let Some(pairs) = key_values(args, "--crystal") else {
+            return defaults;
+        };
+        let value = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let number = |key: &str, default: f32| value(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+
+        CrystalMaterial {
+            ior: number("ior", defaults.ior).max(1.0),
+            tint: value("tint")
+                .and_then(|hex| u32::from_str_radix(hex.trim_start_matches('#'), 16).ok())
+                .map(Color::from_hex)
+                .unwrap_or(defaults.tint),
+            facet_scale: number("facets", defaults.facet_scale).max(0.0),
+            facet_strength: number("facet_strength", defaults.facet_strength).max(0.0),
+            thickness: number("thickness", defaults.thickness).max(0.0),
+        }
+    }
+}
+
+params!(CrystalMaterial { ior, facet_scale, facet_strength, thickness } colors { tint });
+
+// Lo que se ve en una dirección: el mismo cielo que el fondo de la escena
+fn sky(direction: &Vec3, uniforms: &Uniforms) -> Color {
+    if uniforms.starfield {
+        starfield(direction, uniforms.noise.as_ref())
     } else {
-        base_color.lerp(&sky_gradient, 0.1) // Mezcla con el gradiente del cielo
+        environment(direction)
+    }
+}
+
+// Planeta de cristal: muestrea la copia del color ya dibujado (`uniforms.backdrop`) donde sale el rayo
+// refractado y lo mezcla con el reflejo del cielo estrellado según Fresnel (Schlick). Si el rayo
+// refractado sale de la pantalla, o no hay copia, se usa el cielo en esa dirección.
+fn crystal_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let material = uniforms.crystal;
+    let position = world_position(fragment, uniforms);
+    let incident = (position - uniforms.camera_position).normalize();
+
+    // Cada celda inclina la normal en una dirección fija: caras planas con aristas netas
+    let (_, _, cell) = worley_cell(&(fragment.vertex_position * material.facet_scale), 0.0);
+    let tilt = (cell - Vec3::new(0.5, 0.5, 0.5)) * (2.0 * material.facet_strength);
+    let mut normal = (fragment.normal.normalize() + tilt).normalize();
+    if normal.dot(&incident) > 0.0 {
+        normal = -normal;
+    }
+
+    let reflected = reflect(&incident, &normal);
+    // En reflexión total interna no hay rayo que atraviese; se ve solo el reflejo
+    let transmitted = match refract(&incident, &normal, 1.0 / material.ior) {
+        Some(refracted) => {
+            let exit = position + refracted * material.thickness;
+            project(&exit, uniforms)
+                .and_then(|screen| uniforms.backdrop.as_ref()?.sample(screen.x, screen.y))
+                .unwrap_or_else(|| sky(&refracted, uniforms))
+                .blend_multiply(&material.tint)
+        }
+        None => sky(&reflected, uniforms),
     };
 
-    final_color * fragment.intensity
+    let f0 = ((material.ior - 1.0) / (material.ior + 1.0)).powi(2);
+    let cos_theta = (-incident).dot(&normal).clamp(0.0, 1.0);
+    let reflectance = f0 + (1.0 - f0) * (1.0 - cos_theta).powi(5);
+
+    let highlight = compute_lighting(fragment, uniforms).specular;
+    transmitted.lerp(&sky(&reflected, uniforms), reflectance) + uniforms.light.color * highlight
+}
+
+// Dirección reflejada de `incident` sobre una superficie con esa normal
+fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
+    incident - normal * (2.0 * normal.dot(incident))
+}
+
+// Ley de Snell con `eta` = n1 / n2; None en reflexión total interna
+fn refract(incident: &Vec3, normal: &Vec3, eta: f32) -> Option<Vec3> {
+    let cos_i = -normal.dot(incident);
+    let k = 1.0 - eta * eta * (1.0 - cos_i * cos_i);
+    (k >= 0.0).then(|| incident * eta + normal * (eta * cos_i - k.sqrt()))
 }
 
 fn dynamic_cellular_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 30.0;  // Escala del patrón celular
-    let flow_speed = 0.1; // Velocidad del flujo
-    let time = uniforms.time as f32 * flow_speed; // Tiempo para animación
-
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-
-    // Usar `get_noise_2d` con tiempo para animación controlada
-    let cell_noise_value = uniforms.noise.get_noise_2d(x * zoom, y * zoom + time).abs();
-
-    // Definir colores dinámicos para las células
-    let energy_color_1 = Color::new(255, 69, 0);    // Naranja brillante
-    let energy_color_2 = Color::new(255, 140, 0);   // Naranja más suave
-    let energy_color_3 = Color::new(255, 215, 0);   // Amarillo dorado
-    let energy_color_4 = Color::new(255, 255, 153); // Amarillo pálido
-
-    // Selección de color basado en el valor de ruido
-    let final_color = if cell_noise_value < 0.2 {
-        energy_color_1
-    } else if cell_noise_value < 0.5 {
-        energy_color_2
-    } else if cell_noise_value < 0.8 {
-        energy_color_3
+    let noise = uniforms.noise_for(ShaderKind::Cellular);
+    let params = &uniforms.shader_params.cellular;
+    let time = uniforms.time * params.flow_speed; // Tiempo para animación
+
+    // Con las células más chicas que el píxel queda el promedio de sus colores
+    let cell_detail = detail(fragment, uniforms, params.zoom * noise.frequency());
+    let average_color = params.color_1.lerp(&params.color_2, 0.5).lerp(&params.color_3.lerp(&params.color_4, 0.5), 0.5);
+    if cell_detail <= 0.0 {
+        return compute_lighting(fragment, uniforms).shade(average_color, &uniforms.light, 0.0);
+    }
+
+    // Ruido 3D desplazado en y con el tiempo para una animación controlada
+    let cell_noise_value = sphere_noise(noise, &fragment.vertex_position, params.zoom, Vec3::new(0.0, time, 0.0)).abs();
+
+    // Selección de color basado en el valor de ruido: de naranja brillante a amarillo pálido
+    let final_color = if cell_noise_value < params.low {
+        params.color_1
+    } else if cell_noise_value < params.mid {
+        params.color_2
+    } else if cell_noise_value < params.high {
+        params.color_3
     } else {
-        energy_color_4
+        params.color_4
+    };
+
+    let final_color = average_color.lerp(&final_color, cell_detail);
+
+    // Solo difusa: las células son mates
+    compute_lighting(fragment, uniforms).shade(final_color, &uniforms.light, 0.0)
+}
+
+
+fn world_position(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let position = fragment.vertex_position;
+    (uniforms.model_matrix * Vec4::new(position.x, position.y, position.z, 1.0)).xyz()
+}
+
+// Dirección desde el fragmento hacia la cámara, en espacio de mundo
+fn view_direction(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    (uniforms.camera_position - world_position(fragment, uniforms)).normalize()
+}
+
+// Dirección desde el fragmento hacia la luz, en espacio de mundo
+fn light_direction(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    (uniforms.light.position - world_position(fragment, uniforms)).normalize()
+}
+
+// Respuesta de la luz en un fragmento: `diffuse` ya incluye el ambiente
+struct Lighting {
+    diffuse: f32,
+    specular: f32,
+}
+
+impl Lighting {
+    // Albedo por la difusa más el brillo especular encima, teñidos por el color de la luz.
+    // `specular_weight` deja que cada superficie decida cuánto brilla.
+    fn shade(&self, albedo: Color, light: &Light, specular_weight: f32) -> Color {
+        (albedo * self.diffuse).blend_multiply(&light.color) + light.color * (self.specular * specular_weight)
+    }
+}
+
+// Phong con la luz de la escena: ambiente + difusa de Lambert + especular de Blinn-Phong.
+// Las caras que no miran a la luz reciben solo el ambiente, nunca negro.
+fn compute_lighting(fragment: &Fragment, uniforms: &Uniforms) -> Lighting {
+    lighting_with_normal(fragment, uniforms, &fragment.normal)
+}
+
+// La misma luz con otra normal en espacio de mundo, por ejemplo la de `bump_normal`
+// A la sombra de otro cuerpo (ver `light_visibility`) la difusa y la especular se apagan hasta
+// dejar solo el ambiente.
+fn lighting_with_normal(fragment: &Fragment, uniforms: &Uniforms, normal: &Vec3) -> Lighting {
+    let light = &uniforms.light;
+    let normal = normal.normalize();
+    let light_dir = light_direction(fragment, uniforms);
+    let n_dot_l = normal.dot(&light_dir);
+    if n_dot_l <= 0.0 {
+        return Lighting { diffuse: light.ambient, specular: 0.0 };
+    }
+
+    let visibility = light_reaching(&world_position(fragment, uniforms), uniforms);
+    let half_vector = (light_dir + view_direction(fragment, uniforms)).normalize();
+    let specular = normal.dot(&half_vector).max(0.0).powf(light.shininess) * light.specular;
+    Lighting {
+        diffuse: light.ambient + light.diffuse * n_dot_l * visibility,
+        specular: specular * visibility,
+    }
+}
+
+// Fracción de la luz que llega a `world` sin que la tape otro cuerpo de la escena
+fn light_reaching(world: &Vec3, uniforms: &Uniforms) -> f32 {
+    if uniforms.occluders.is_empty() {
+        return 1.0;
+    }
+    let own_center = (uniforms.model_matrix * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+    light_visibility(world, &uniforms.light, &uniforms.occluders, &own_center)
+}
+
+// Ambiente más la difusa de Lambert en un vértice, sin especular: `vertex_shader` la deja en cada
+// vértice y llega interpolada a `fragment.intensity`. Como en `compute_lighting`, lo que no mira a
+// la luz (o no tiene normal) se queda con el ambiente.
+fn vertex_intensity(world: &Vec3, normal: &Vec3, uniforms: &Uniforms) -> f32 {
+    let light = &uniforms.light;
+    let n_dot_l = match normal.try_normalize(1e-6) {
+        Some(normal) => normal.dot(&(light.position - world).normalize()),
+        None => 0.0,
     };
+    if n_dot_l <= 0.0 {
+        return light.ambient;
+    }
+    light.ambient + light.diffuse * n_dot_l * light_reaching(world, uniforms)
+}
+
+// Normal de mundo inclinada por un campo de alturas en espacio de objeto (relieve sin mover la malla).
+// La pendiente se mide por diferencias finitas a lo largo del este y el norte de la esfera, que salen
+// de la posición sin necesitar tangentes en los vértices; luego esos ejes pasan a mundo con la matriz
+// de modelo. `strength` escala la altura: con 0 queda la normal interpolada.
+fn bump_normal(fragment: &Fragment, uniforms: &Uniforms, strength: f32, height: impl Fn(&Vec3) -> f32) -> Vec3 {
+    let epsilon = 0.002;
+    let position = fragment.vertex_position;
+    let (east, north) = tangent_basis(&position.normalize());
+    let slope = |axis: Vec3| (height(&(position + axis * epsilon)) - height(&(position - axis * epsilon))) / (2.0 * epsilon);
+
+    let normal = fragment.normal.normalize();
+    let model = mat4_to_mat3(&uniforms.model_matrix);
+    let to_world = |axis: Vec3| {
+        let axis = model * axis;
+        (axis - normal * axis.dot(&normal)).try_normalize(1e-6).unwrap_or_else(Vec3::zeros)
+    };
+    (normal - (to_world(east) * slope(east) + to_world(north) * slope(north)) * strength).normalize()
+}
 
-    // Ajustar intensidad para simular efectos de iluminación
-    final_color * fragment.intensity
+// Nivel de detalle de una capa con `features` rasgos por unidad de espacio de objeto (el zoom por la
+// frecuencia del ruido): 1 mientras cada rasgo cubre un par de píxeles y 0 cuando ya caben varios en
+// uno, donde solo se verían como destellos que cambian de un cuadro a otro. El tamaño del píxel es
+// el `footprint` del fragmento por el `lod_bias` de la escena.
+fn detail(fragment: &Fragment, uniforms: &Uniforms, features: f32) -> f32 {
+    let per_pixel = fragment.footprint * uniforms.lod_bias * features;
+    1.0 - smoothstep(0.25, 0.75, per_pixel)
 }
 
+// Término de Fresnel aproximado: 0 de frente a la cámara y 1 en la silueta.
+// `power` controla qué tan pegado al borde queda el efecto.
+fn fresnel(normal: &Vec3, view_dir: &Vec3, power: f32) -> f32 {
+    (1.0 - normal.dot(view_dir).clamp(0.0, 1.0)).powf(power)
+}
+
+// Hermite entre `edge0` y `edge1`: 0 antes, 1 después y una subida suave en medio
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// La imagen de `--texture` en las UV del fragmento, con la misma luz que los shaders procedurales
+// para poder compararlos sobre la misma esfera. Sin textura se ve un tablero que deja ver las UV.
+fn textured_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    // La difusa por vértice interpolada, sin especular: la textura ya trae sus propios brillos
+    let lit = |albedo| Lighting { diffuse: fragment.intensity, specular: 0.0 }.shade(albedo, &uniforms.light, 0.0);
+    #[cfg(feature = "io")]
+    if let Some(texture) = &uniforms.texture {
+        return lit(texture.sample(texture.coordinates(fragment.tex_coords, &fragment.vertex_position)));
+    }
+    lit(checkerboard(fragment.tex_coords))
+}
+
+// Sin textura cargada: un damero de 16x16 casillas en las coordenadas UV
+fn checkerboard(tex_coords: Vec2) -> Color {
+    let cell = (tex_coords * 16.0).map(f32::floor);
+    if (cell.x + cell.y).rem_euclid(2.0) < 1.0 { Color::new(220, 220, 220) } else { Color::new(60, 60, 60) }
+}
 
 fn default_shader(fragment: &Fragment, _uniforms: &Uniforms) -> Color {
-    fragment.color // Devuelve el color original del fragmento
+    fragment.color * fragment.intensity // El color del rasterizador con la difusa interpolada
 }
\ No newline at end of file
@@ -0,0 +1,62 @@
+use crate::cli::arg_value;
+use crate::framebuffer::Framebuffer;
+use crate::image_io::save_png_rgb;
+
+pub struct SlitScanOptions {
+    pub output: String,
+    pub columns: usize,
+}
+
+impl SlitScanOptions {
+    // `--slitscan out.png --columns 1920`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let output = arg_value(args, "--slitscan")?;
+        let columns = arg_value(args, "--columns")
+            .and_then(|value| value.parse().ok())
+            .filter(|&columns| columns > 0)
+            .unwrap_or(1920);
+
+        Some(SlitScanOptions { output, columns })
+    }
+}
+
+// Imagen acumulada: cada cuadro aporta la columna central del framebuffer
+pub struct SlitScan {
+    pub width: usize,
+    pub height: usize,
+    buffer: Vec<u32>,
+    next_column: usize,
+}
+
+impl SlitScan {
+    pub fn new(width: usize, height: usize) -> Self {
+        SlitScan {
+            width,
+            height,
+            buffer: vec![0; width * height],
+            next_column: 0,
+        }
+    }
+
+    // Copia la columna central; debe llamarse antes de dibujar cualquier overlay
+    pub fn capture(&mut self, framebuffer: &Framebuffer) {
+        if self.is_complete() {
+            return;
+        }
+
+        let source_x = framebuffer.width / 2;
+        for y in 0..self.height.min(framebuffer.height) {
+            self.buffer[y * self.width + self.next_column] = framebuffer.buffer[y * framebuffer.width + source_x];
+        }
+
+        self.next_column += 1;
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.next_column >= self.width
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), png::EncodingError> {
+        save_png_rgb(path, self.width, self.height, &self.buffer)
+    }
+}
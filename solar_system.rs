@@ -0,0 +1,95 @@
+use nalgebra_glm::{Mat4, Vec3};
+use std::f32::consts::PI;
+use crate::create_model_matrix;
+use crate::magnetosphere::{Magnetosphere, STANDOFF_RADII};
+use crate::shaders::ShaderKind;
+
+// Órbita circular en el plano XZ alrededor del padre, o del origen si no tiene
+#[derive(Clone, Copy)]
+pub struct Orbit {
+    pub radius: f32,
+    // Segundos de tiempo local por vuelta
+    pub period: f32,
+    // Ángulo inicial, para que los cuerpos no arranquen alineados
+    pub phase: f32,
+}
+
+impl Orbit {
+    pub fn position(&self, time: f32) -> Vec3 {
+        let angle = self.phase + time / self.period * 2.0 * PI;
+        Vec3::new(angle.cos(), 0.0, angle.sin()) * self.radius
+    }
+}
+
+// Un cuerpo con su propia transformación y shader. La órbita se suma al centro del padre; el giro,
+// la inclinación y la escala son solo del cuerpo, así una luna no hereda el día ni el tamaño del planeta.
+pub struct SceneObject {
+    pub name: &'static str,
+    pub shader: ShaderKind,
+    pub scale: f32,
+    pub tilt: f32, // Inclinación del eje de giro, en radianes
+    pub spin: f32, // Radianes por segundo alrededor de su eje
+    pub orbit: Option<Orbit>,
+    // Índice de un objeto anterior de la lista
+    pub parent: Option<usize>,
+    // Con campo magnético: desvía el viento solar (ver `Magnetosphere`)
+    pub magnetosphere: bool,
+}
+
+// `--solar-system`: un sol con planetas en órbita y una luna, cada uno con su shader
+pub struct SolarSystem {
+    pub objects: Vec<SceneObject>,
+}
+
+impl SolarSystem {
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        args.iter().any(|arg| arg == "--solar-system").then(SolarSystem::demo)
+    }
+
+    fn demo() -> Self {
+        let orbit = |radius: f32, period: f32, phase: f32| Some(Orbit { radius, period, phase });
+        SolarSystem {
+            objects: vec![
+                SceneObject { name: "sun", shader: ShaderKind::Sun, scale: 1.0, tilt: 0.0, spin: 0.12, orbit: None, parent: None, magnetosphere: false },
+                SceneObject { name: "inner", shader: ShaderKind::Noise, scale: 0.22, tilt: 0.1, spin: 0.6, orbit: orbit(2.2, 10.0, 0.0), parent: Some(0), magnetosphere: false },
+                SceneObject { name: "earth", shader: ShaderKind::EarthClouds, scale: 0.35, tilt: 0.41, spin: 1.2, orbit: orbit(3.6, 50.0 / 3.0, 2.0), parent: Some(0), magnetosphere: true },
+                SceneObject { name: "moon", shader: ShaderKind::Moon, scale: 0.1, tilt: 0.0, spin: 0.0, orbit: orbit(0.7, 10.0 / 3.0, 0.0), parent: Some(2), magnetosphere: false },
+                SceneObject { name: "giant", shader: ShaderKind::GasGiant, scale: 0.6, tilt: 0.05, spin: 1.8, orbit: orbit(5.5, 30.0, 4.0), parent: Some(0), magnetosphere: true },
+                SceneObject { name: "ice", shader: ShaderKind::Europa, scale: 0.28, tilt: 0.2, spin: 0.48, orbit: orbit(7.5, 140.0 / 3.0, 1.0), parent: Some(0), magnetosphere: false },
+            ],
+        }
+    }
+
+    // Centro de cada objeto en espacio de mundo; los padres se resuelven antes que sus hijos
+    pub fn centers(&self, time: f32) -> Vec<Vec3> {
+        let mut centers: Vec<Vec3> = Vec::with_capacity(self.objects.len());
+        for object in &self.objects {
+            let parent = object.parent.and_then(|parent| centers.get(parent).copied()).unwrap_or_else(Vec3::zeros);
+            let offset = object.orbit.map_or(Vec3::zeros(), |orbit| orbit.position(time));
+            centers.push(parent + offset);
+        }
+        centers
+    }
+
+    // Matriz de modelo de cada objeto en `time`: su centro, su escala y su giro alrededor del eje inclinado
+    pub fn model_matrices(&self, time: f32) -> Vec<Mat4> {
+        self.objects
+            .iter()
+            .zip(self.centers(time))
+            .map(|(object, center)| create_model_matrix(center, object.scale, Vec3::new(0.0, object.spin * time, object.tilt)))
+            .collect()
+    }
+
+    // Campos de los cuerpos con `magnetosphere` en `time`, con el frente mirando al primer objeto
+    // (la estrella); `radius` es el de la esfera sin escalar
+    pub fn magnetospheres(&self, time: f32, radius: f32) -> Vec<Magnetosphere> {
+        let centers = self.centers(time);
+        let star = centers.first().copied().unwrap_or_else(Vec3::zeros);
+        self.objects
+            .iter()
+            .zip(&centers)
+            .filter(|(object, _)| object.magnetosphere)
+            .filter_map(|(object, &center)| Magnetosphere::facing(center, object.scale * radius * STANDOFF_RADII, star))
+            .collect()
+    }
+}
@@ -0,0 +1,222 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::thread;
+use crate::framebuffer::Framebuffer;
+use crate::gizmo::project;
+use crate::magnetosphere::Magnetosphere;
+use crate::Uniforms;
+
+// Presupuesto de partículas: también fija cuántas se actualizan por cuadro
+const MAX_PARTICLES: usize = 3000;
+const SPAWN_RADIUS: f32 = 0.55;
+const MAX_RADIUS: f32 = 5.0;
+const BASE_SPEED: f32 = 0.9; // Unidades por segundo
+const JITTER: f32 = 0.2; // Desviación lateral relativa a la velocidad en cada paso
+// Menos partículas que esto por hilo no compensa lanzar hilos
+const MIN_CHUNK: usize = 512;
+const WIND_COLOR: (f32, f32, f32) = (255.0, 210.0, 140.0);
+
+#[derive(Clone, Copy)]
+struct Particle {
+    position: Vec3,
+    velocity: Vec3,
+}
+
+// Viento solar: puntos que salen en dirección radial desde el cuerpo central con un leve temblor,
+// desviados alrededor de los planetas con campo magnético y dibujados como píxeles aditivos que se
+// apagan con la distancia
+pub struct SolarWind {
+    pub enabled: bool,
+    particles: Vec<Particle>,
+    tick: u32,
+}
+
+impl SolarWind {
+    pub fn new() -> Self {
+        // Radios iniciales repartidos para que el viento no arranque como un solo frente
+        let particles = (0..MAX_PARTICLES as u32)
+            .map(|index| {
+                let mut particle = spawn(index, 0);
+                let distance = SPAWN_RADIUS + (MAX_RADIUS - SPAWN_RADIUS) * random(index, u32::MAX);
+                particle.position = particle.position.normalize() * distance;
+                particle
+            })
+            .collect();
+
+        SolarWind {
+            enabled: false,
+            particles,
+            tick: 0,
+        }
+    }
+
+    // Avanza `delta` segundos, repartido en trozos entre los núcleos disponibles; en pausa no se mueve
+    pub fn update(&mut self, delta: f32, magnetospheres: &[Magnetosphere]) {
+        if !self.enabled || delta <= 0.0 {
+            return;
+        }
+        self.tick = self.tick.wrapping_add(1);
+        let tick = self.tick;
+
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let chunk = self.particles.len().div_ceil(threads).max(MIN_CHUNK);
+
+        thread::scope(|scope| {
+            for (chunk_index, particles) in self.particles.chunks_mut(chunk).enumerate() {
+                scope.spawn(move || {
+                    for (offset, particle) in particles.iter_mut().enumerate() {
+                        advance(particle, (chunk_index * chunk + offset) as u32, tick, delta, magnetospheres);
+                    }
+                });
+            }
+        });
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        if !self.enabled {
+            return;
+        }
+
+        for particle in &self.particles {
+            let Some(screen) = project(&particle.position, uniforms) else {
+                continue;
+            };
+            if screen.x < 0.0 || screen.y < 0.0 {
+                continue;
+            }
+            let (x, y) = (screen.x as usize, screen.y as usize);
+            if x >= framebuffer.width || y >= framebuffer.height {
+                continue;
+            }
+
+            // Detrás del planeta no se ve; delante se suma al color existente
+            let index = y * framebuffer.width + x;
+            if framebuffer.zbuffer[index] < screen.z {
+                continue;
+            }
+            let fade = 1.0 - (particle.position.magnitude() - SPAWN_RADIUS) / (MAX_RADIUS - SPAWN_RADIUS);
+            let brightness = fade.clamp(0.0, 1.0).powi(2) * 0.6;
+            framebuffer.buffer[index] = add_color(framebuffer.buffer[index], brightness);
+        }
+    }
+}
+
+fn advance(particle: &mut Particle, index: u32, tick: u32, delta: f32, magnetospheres: &[Magnetosphere]) {
+    let speed = particle.velocity.magnitude();
+    let jitter = random_direction(index, tick) * (speed * JITTER);
+    particle.velocity = (particle.velocity + jitter).normalize() * speed;
+    for field in magnetospheres {
+        particle.velocity = field.deflect(&particle.position, particle.velocity);
+    }
+    particle.position += particle.velocity * delta;
+
+    if particle.position.magnitude() > MAX_RADIUS {
+        *particle = spawn(index, tick);
+    }
+}
+
+fn spawn(index: u32, tick: u32) -> Particle {
+    let direction = random_direction(index, tick ^ 0x5bd1_e995);
+    let speed = BASE_SPEED * (0.7 + 0.6 * random(index, tick ^ 0x68e3_1da4));
+    Particle {
+        position: direction * SPAWN_RADIUS,
+        velocity: direction * speed,
+    }
+}
+
+fn add_color(color: u32, brightness: f32) -> u32 {
+    let channel = |shift: u32, amount: f32| {
+        let value = ((color >> shift) & 0xFF) as f32 + amount * brightness;
+        (value.min(255.0) as u32) << shift
+    };
+    channel(16, WIND_COLOR.0) | channel(8, WIND_COLOR.1) | channel(0, WIND_COLOR.2)
+}
+
+// Números pseudoaleatorios deterministas por partícula y paso, sin estado compartido entre hilos
+fn random(index: u32, tick: u32) -> f32 {
+    let mut h = index.wrapping_mul(0x9E37_79B9) ^ tick.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    h as f32 / u32::MAX as f32
+}
+
+fn random_direction(index: u32, tick: u32) -> Vec3 {
+    let z = random(index, tick) * 2.0 - 1.0;
+    let angle = random(index ^ 0xA511_E9B3, tick) * 2.0 * PI;
+    let ring = (1.0 - z * z).sqrt();
+    Vec3::new(ring * angle.cos(), z, ring * angle.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::{create_cloud_noise, create_uniforms, DEFAULT_SEED};
+
+    // Partículas dentro del frente, del lado de la estrella
+    fn particles_in_front_of(field: &Magnetosphere, wind: &SolarWind) -> usize {
+        wind.particles
+            .iter()
+            .filter(|particle| {
+                let offset = particle.position - field.center;
+                offset.x < 0.0 && offset.magnitude() < field.boundary(&offset)
+            })
+            .count()
+    }
+
+    #[test]
+    fn magnetosphere_opens_a_void_on_the_sunward_side() {
+        let field = Magnetosphere::facing(Vec3::new(2.5, 0.0, 0.0), 1.0, Vec3::zeros()).unwrap();
+        let run = |fields: &[Magnetosphere]| {
+            let mut wind = SolarWind::new();
+            wind.enabled = true;
+            for _ in 0..240 {
+                wind.update(1.0 / 60.0, fields);
+            }
+            particles_in_front_of(&field, &wind)
+        };
+
+        let open = run(&[]);
+        let shielded = run(&[field]);
+        assert!(open > 10, "sin campo deberían pasar partículas: {}", open);
+        assert!(shielded * 10 < open, "con campo quedan {} de {}", shielded, open);
+    }
+
+    #[test]
+    fn wind_composites_the_same_in_any_order_and_from_either_side() {
+        // Pares en el eje de la vista, uno a cada lado de un muro en z = 0: varios caen en el mismo píxel
+        let particles: Vec<Particle> = [0.8, 1.5, 2.2, 3.0]
+            .into_iter()
+            .flat_map(|z| [0.0, 0.4].map(|y| [Vec3::new(0.0, y, z), Vec3::new(0.0, y, -z)]))
+            .flatten()
+            .map(|position| Particle { position, velocity: Vec3::zeros() })
+            .collect();
+
+        let draw = |eye: Vec3, particles: Vec<Particle>| {
+            let (width, height) = (64, 48);
+            let camera = Camera::new(eye, Vec3::zeros(), Vec3::y());
+            let uniforms = create_uniforms(&camera, width, height, width, height, 0.0, Box::new(create_cloud_noise(DEFAULT_SEED)));
+            let mut framebuffer = Framebuffer::new(width, height);
+            framebuffer.buffer.fill(0x202020);
+            let wall = project(&Vec3::zeros(), &uniforms).unwrap().z;
+            framebuffer.zbuffer.fill(wall);
+            SolarWind { enabled: true, particles, tick: 0 }.draw(&mut framebuffer, &uniforms);
+            framebuffer.buffer
+        };
+        let reversed: Vec<Particle> = particles.iter().rev().copied().collect();
+        let interleaved: Vec<Particle> = particles.iter().step_by(2).chain(particles.iter().skip(1).step_by(2)).copied().collect();
+
+        let front = draw(Vec3::new(0.0, 0.0, 4.5), particles.clone());
+        assert!(front.iter().any(|&pixel| pixel != 0x202020), "no se dibujó ninguna partícula");
+        // Solo la mitad de cada par está delante del muro
+        let visible: Vec<Particle> = particles.iter().filter(|particle| particle.position.z > 0.0).copied().collect();
+        assert_eq!(draw(Vec3::new(0.0, 0.0, 4.5), visible), front);
+        for order in [particles.clone(), reversed, interleaved] {
+            assert_eq!(draw(Vec3::new(0.0, 0.0, 4.5), order.clone()), front);
+            assert_eq!(draw(Vec3::new(0.0, 0.0, -4.5), order), front);
+        }
+    }
+}
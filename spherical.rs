@@ -0,0 +1,100 @@
+use nalgebra_glm::Vec3;
+
+// Coordenadas esféricas sobre posiciones de espacio de objeto normalizadas.
+// Convención: +Y es el polo norte, la latitud va de -PI/2 a PI/2 y la longitud
+// de -PI a PI medida desde +X hacia +Z. Normalizar primero hace que un modelo
+// escalado (incluso de forma no uniforme) dé las mismas coordenadas.
+
+// Latitud y longitud (en radianes) de una posición en espacio de objeto
+pub fn to_lat_long(object_position: &Vec3) -> (f32, f32) {
+    let direction = object_position.normalize();
+    let latitude = direction.y.clamp(-1.0, 1.0).asin();
+    let longitude = direction.z.atan2(direction.x);
+    (latitude, longitude)
+}
+
+// Dirección unitaria para una latitud y longitud dadas
+pub fn lat_long_to_dir(latitude: f32, longitude: f32) -> Vec3 {
+    Vec3::new(
+        latitude.cos() * longitude.cos(),
+        latitude.sin(),
+        latitude.cos() * longitude.sin(),
+    )
+}
+
+// Distancia angular (en radianes) entre dos posiciones sobre la esfera
+pub fn great_circle_distance(a: &Vec3, b: &Vec3) -> f32 {
+    let a = a.normalize();
+    let b = b.normalize();
+    // atan2 es estable también para puntos casi iguales o antipodales, donde acos pierde precisión
+    a.cross(&b).magnitude().atan2(a.dot(&b))
+}
+
+// Rotación diferencial: la malla no gira, se desplaza la longitud según la latitud.
+// omega(lat) = equatorial_rate * (1 - differential * sin²(lat)), así el ecuador gira más rápido
+// que los polos y los rasgos de la superficie se van cizallando con el tiempo.
+pub fn differential_rotation(object_position: &Vec3, equatorial_rate: f32, differential: f32, time: f32) -> Vec3 {
+    let (latitude, longitude) = to_lat_long(object_position);
+    let omega = equatorial_rate * (1.0 - differential * latitude.sin().powi(2));
+    lat_long_to_dir(latitude, longitude + omega * time) * object_position.magnitude()
+}
+
+// Este y norte locales en un punto de la esfera; en los polos cualquier par perpendicular sirve.
+// El este es hacia donde avanza la superficie al girar alrededor de +Y con la matriz de modelo.
+pub fn tangent_basis(direction: &Vec3) -> (Vec3, Vec3) {
+    let east = Vec3::y().cross(direction).try_normalize(1e-6).unwrap_or_else(Vec3::x);
+    (east, direction.cross(&east))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    const EPSILON: f32 = 1e-5;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < EPSILON
+    }
+
+    #[test]
+    fn poles() {
+        assert!(close(to_lat_long(&Vec3::new(0.0, 3.0, 0.0)).0, FRAC_PI_2));
+        assert!(close(to_lat_long(&Vec3::new(0.0, -0.2, 0.0)).0, -FRAC_PI_2));
+        // En el polo la longitud no importa
+        for longitude in [-PI, -1.0, 0.0, 2.5] {
+            assert!((lat_long_to_dir(FRAC_PI_2, longitude) - Vec3::y()).magnitude() < EPSILON);
+        }
+        assert!(close(great_circle_distance(&Vec3::y(), &-Vec3::y()), PI));
+        assert!(close(great_circle_distance(&Vec3::y(), &Vec3::new(2.0, 0.0, 2.0)), FRAC_PI_2));
+        let (east, north) = tangent_basis(&Vec3::y());
+        assert!(close(east.dot(&north), 0.0) && close(east.magnitude(), 1.0) && close(north.magnitude(), 1.0));
+    }
+
+    #[test]
+    fn equator() {
+        assert_eq!(to_lat_long(&Vec3::x()), (0.0, 0.0));
+        let (latitude, longitude) = to_lat_long(&Vec3::new(0.0, 0.0, 5.0));
+        assert!(close(latitude, 0.0) && close(longitude, FRAC_PI_2));
+        // Ida y vuelta, y la misma coordenada con cualquier radio
+        for longitude in [-2.0, -0.5, 0.7, 3.0] {
+            let direction = lat_long_to_dir(0.0, longitude);
+            let (lat, long) = to_lat_long(&(direction * 0.37));
+            assert!(close(lat, 0.0) && close(long, longitude));
+        }
+        assert!(close(great_circle_distance(&Vec3::x(), &Vec3::z()), FRAC_PI_2));
+    }
+
+    #[test]
+    fn date_line() {
+        // -X es la longitud ±PI: a un lado y otro de la costura salta de signo
+        assert!(close(to_lat_long(&-Vec3::x()).1.abs(), PI));
+        assert!(to_lat_long(&Vec3::new(-1.0, 0.0, 1e-3)).1 > PI - 0.01);
+        assert!(to_lat_long(&Vec3::new(-1.0, 0.0, -1e-3)).1 < -PI + 0.01);
+        // Pero la distancia entre 179 y -179 grados son 2 grados, no 358
+        let (a, b) = (lat_long_to_dir(0.3, 179f32.to_radians()), lat_long_to_dir(0.3, (-179f32).to_radians()));
+        let expected = 2f32.to_radians() * 0.3f32.cos();
+        assert!((great_circle_distance(&a, &b) - expected).abs() < 1e-4);
+        assert!((lat_long_to_dir(0.0, PI) - lat_long_to_dir(0.0, -PI)).magnitude() < EPSILON);
+    }
+}
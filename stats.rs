@@ -0,0 +1,179 @@
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+// Contadores de un render; con varios cuadros se acumulan con `add`.
+// Los conteos son deterministas con el mismo tiempo y cámara; los tiempos no.
+#[derive(Clone, Copy, Default)]
+pub struct RenderStats {
+    pub vertices: u64,
+    pub triangles_submitted: u64,
+    // Triángulos que produjeron al menos un fragmento; el resto cayó fuera de pantalla o no tiene área
+    pub triangles_rasterized: u64,
+    // Triángulos descartados antes de rasterizar por tener vértices no finitos (incluidos en los enviados)
+    pub triangles_invalid: u64,
+    // Descartados por el culling, también incluidos en los enviados: los de un cuerpo entero fuera
+    // de la vista, los que dan la espalda y los que quedan enteros fuera del plano cercano o del lejano
+    pub triangles_frustum: u64,
+    pub triangles_backface: u64,
+    pub triangles_near: u64,
+    // Triángulos que cruzaban el plano cercano o el lejano y se recortaron; siguen hacia el rasterizador
+    pub triangles_clipped: u64,
+    pub fragments_emitted: u64,
+    pub fragments_shaded: u64,
+    // Fragmentos que el shader descartó (huecos); no cuentan como sombreados
+    pub fragments_discarded: u64,
+    pub fragments_depth_rejected: u64,
+    pub vertex_time: Duration,
+    pub raster_time: Duration,
+    pub fragment_time: Duration,
+}
+
+impl RenderStats {
+    pub fn add(&mut self, other: &RenderStats) {
+        self.vertices += other.vertices;
+        self.triangles_submitted += other.triangles_submitted;
+        self.triangles_rasterized += other.triangles_rasterized;
+        self.triangles_invalid += other.triangles_invalid;
+        self.triangles_frustum += other.triangles_frustum;
+        self.triangles_backface += other.triangles_backface;
+        self.triangles_near += other.triangles_near;
+        self.triangles_clipped += other.triangles_clipped;
+        self.fragments_emitted += other.fragments_emitted;
+        self.fragments_shaded += other.fragments_shaded;
+        self.fragments_discarded += other.fragments_discarded;
+        self.fragments_depth_rejected += other.fragments_depth_rejected;
+        self.vertex_time += other.vertex_time;
+        self.raster_time += other.raster_time;
+        self.fragment_time += other.fragment_time;
+    }
+
+    pub fn triangles_culled(&self) -> u64 {
+        self.triangles_frustum + self.triangles_backface + self.triangles_near
+    }
+
+    fn to_json(self, indent: &str) -> String {
+        let milliseconds = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        format!(
+            "{i}\"vertices\": {},\n\
+             {i}\"triangles_submitted\": {},\n\
+             {i}\"triangles_rasterized\": {},\n\
+             {i}\"triangles_culled\": {},\n\
+             {i}\"culled\": {{ \"frustum\": {}, \"backface\": {}, \"near\": {} }},\n\
+             {i}\"triangles_clipped\": {},\n\
+             {i}\"triangles_invalid\": {},\n\
+             {i}\"fragments_emitted\": {},\n\
+             {i}\"fragments_shaded\": {},\n\
+             {i}\"fragments_discarded\": {},\n\
+             {i}\"fragments_depth_rejected\": {},\n\
+             {i}\"timings_ms\": {{ \"vertex\": {:.3}, \"raster\": {:.3}, \"fragment\": {:.3} }}",
+            self.vertices,
+            self.triangles_submitted,
+            self.triangles_rasterized,
+            self.triangles_culled(),
+            self.triangles_frustum,
+            self.triangles_backface,
+            self.triangles_near,
+            self.triangles_clipped,
+            self.triangles_invalid,
+            self.fragments_emitted,
+            self.fragments_shaded,
+            self.fragments_discarded,
+            self.fragments_depth_rejected,
+            milliseconds(self.vertex_time),
+            milliseconds(self.raster_time),
+            milliseconds(self.fragment_time),
+            i = indent,
+        )
+    }
+}
+
+// Configuración activa que acompaña a los números en el informe
+pub struct StatsConfig {
+    pub mode: &'static str,
+    pub width: usize,
+    pub height: usize,
+    pub seed: i32,
+    pub noise: &'static str,
+    // Hilos de sombreado, para comparar tiempos entre `--threads 1` y el modo en paralelo
+    pub threads: usize,
+}
+
+// Informe para `--stats-out stats.json`: una entrada por cuerpo y los totales de la escena.
+// El JSON se arma a mano; todos los textos son identificadores fijos o nombres generados con
+// sílabas de una tabla, así que no requieren escape.
+pub struct StatsReport {
+    pub config: StatsConfig,
+    pub frames: u32,
+    entities: Vec<(&'static str, &'static str, RenderStats)>,
+    // Nombre propio y descripción de cada cuerpo, si se conocen
+    labels: Vec<(&'static str, String, String)>,
+}
+
+impl StatsReport {
+    pub fn new(config: StatsConfig) -> Self {
+        StatsReport {
+            config,
+            frames: 0,
+            entities: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn label(&mut self, entity: &'static str, name: &str, description: String) {
+        self.labels.retain(|(other, _, _)| *other != entity);
+        self.labels.push((entity, name.to_string(), description));
+    }
+
+    // Suma los contadores de un cuerpo; los cuadros sucesivos del mismo cuerpo se acumulan
+    pub fn record(&mut self, entity: &'static str, shader: &'static str, stats: &RenderStats) {
+        match self.entities.iter_mut().find(|(name, _, _)| *name == entity) {
+            Some((_, current_shader, total)) => {
+                *current_shader = shader;
+                total.add(stats);
+            }
+            None => self.entities.push((entity, shader, *stats)),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut totals = RenderStats::default();
+        let entities: Vec<String> = self
+            .entities
+            .iter()
+            .map(|(name, shader, stats)| {
+                totals.add(stats);
+                let label = self
+                    .labels
+                    .iter()
+                    .find(|(entity, _, _)| entity == name)
+                    .map(|(_, label, description)| format!("      \"label\": \"{}\",\n      \"description\": \"{}\",\n", label, description))
+                    .unwrap_or_default();
+                format!(
+                    "    {{\n      \"name\": \"{}\",\n{}      \"shader\": \"{}\",\n{}\n    }}",
+                    name,
+                    label,
+                    shader,
+                    stats.to_json("      ")
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\n  \"config\": {{ \"mode\": \"{}\", \"width\": {}, \"height\": {}, \"seed\": {}, \"noise\": \"{}\", \"threads\": {} }},\n  \"frames\": {},\n  \"entities\": [\n{}\n  ],\n  \"totals\": {{\n{}\n  }}\n}}\n",
+            self.config.mode,
+            self.config.width,
+            self.config.height,
+            self.config.seed,
+            self.config.noise,
+            self.config.threads,
+            self.frames,
+            entities.join(",\n"),
+            totals.to_json("    ")
+        )
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
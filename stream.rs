@@ -0,0 +1,157 @@
+use std::io::{self, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+
+use crate::cli::arg_value;
+use crate::framebuffer::Framebuffer;
+
+// Cuadros en cola entre el render y el hilo que escribe; con más cola solo se gana latencia
+const QUEUE_LENGTH: usize = 2;
+pub const FRAME_RATE: u32 = 60;
+
+pub enum StreamTarget {
+    Stdout,
+    Tcp(u16),
+}
+
+pub struct StreamOptions {
+    pub target: StreamTarget,
+    pub drop_frames: bool,
+    pub frames: Option<u32>,
+}
+
+impl StreamOptions {
+    // `--stream rawvideo:-` o `--stream tcp:PORT`, con `--stream-drop` y `--frames N` opcionales
+    pub fn from_args(args: &[String]) -> Option<Result<Self, String>> {
+        let value = arg_value(args, "--stream")?;
+        let target = match value.split_once(':') {
+            Some(("rawvideo", "-")) => StreamTarget::Stdout,
+            Some(("tcp", port)) => match port.parse() {
+                Ok(port) => StreamTarget::Tcp(port),
+                Err(_) => return Some(Err(format!("Puerto inválido en --stream: {}", port))),
+            },
+            _ => return Some(Err(format!("--stream espera rawvideo:- o tcp:PORT, no {}", value))),
+        };
+
+        Some(Ok(StreamOptions {
+            target,
+            drop_frames: args.iter().any(|arg| arg == "--stream-drop"),
+            frames: arg_value(args, "--frames").and_then(|frames| frames.parse().ok()),
+        }))
+    }
+}
+
+// Envía cuadros RGB24 de tamaño fijo a stdout o a un cliente TCP desde un hilo aparte.
+// Si el consumidor es lento el render se bloquea, salvo con `--stream-drop`, que descarta cuadros.
+pub struct FrameStream {
+    pub width: usize,
+    pub height: usize,
+    pub dropped: u64,
+    drop_frames: bool,
+    sender: Option<SyncSender<Vec<u8>>>,
+    worker: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl FrameStream {
+    pub fn open(options: &StreamOptions, width: usize, height: usize) -> Self {
+        let (sender, receiver) = sync_channel::<Vec<u8>>(QUEUE_LENGTH);
+
+        let worker = match options.target {
+            StreamTarget::Stdout => {
+                eprintln!(
+                    "Transmitiendo a stdout; por ejemplo: | ffmpeg -f rawvideo -pixel_format rgb24 -video_size {}x{} -framerate {} -i - out.mp4",
+                    width, height, FRAME_RATE
+                );
+                thread::spawn(move || write_frames(&mut io::stdout().lock(), receiver, false))
+            }
+            StreamTarget::Tcp(port) => thread::spawn(move || {
+                let listener = TcpListener::bind(("0.0.0.0", port))?;
+                eprintln!("Esperando un cliente en el puerto {}", port);
+                let (mut client, address) = listener.accept()?;
+                eprintln!("Cliente conectado: {}", address);
+
+                // Encabezado: ancho y alto en u32 little-endian, una sola vez
+                client.write_all(&(width as u32).to_le_bytes())?;
+                client.write_all(&(height as u32).to_le_bytes())?;
+                write_frames(&mut client, receiver, true)
+            }),
+        };
+
+        FrameStream {
+            width,
+            height,
+            dropped: 0,
+            drop_frames: options.drop_frames,
+            sender: Some(sender),
+            worker: Some(worker),
+        }
+    }
+
+    // Devuelve false cuando el consumidor se cerró y no tiene sentido seguir enviando
+    pub fn send(&mut self, framebuffer: &Framebuffer) -> bool {
+        let Some(sender) = &self.sender else {
+            return false;
+        };
+
+        let frame = self.to_rgb24(framebuffer);
+        let sent = if self.drop_frames {
+            match sender.try_send(frame) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    self.dropped += 1;
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        } else {
+            sender.send(frame).is_ok()
+        };
+
+        if !sent {
+            self.close();
+        }
+        sent
+    }
+
+    // Vacía la cola y espera al hilo de escritura
+    pub fn close(&mut self) {
+        self.sender = None;
+        if let Some(worker) = self.worker.take() {
+            match worker.join() {
+                Ok(Err(error)) if error.kind() != io::ErrorKind::BrokenPipe => eprintln!("Error al transmitir: {}", error),
+                Err(_) => eprintln!("El hilo de transmisión terminó con un pánico"),
+                _ => {}
+            }
+        }
+        if self.dropped > 0 {
+            eprintln!("Cuadros descartados: {}", self.dropped);
+        }
+    }
+
+    // Si el framebuffer tiene otro tamaño se muestrea al más cercano para mantener la resolución fija
+    fn to_rgb24(&self, framebuffer: &Framebuffer) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(self.width * self.height * 3);
+        for y in 0..self.height {
+            let source_y = y * framebuffer.height / self.height;
+            for x in 0..self.width {
+                let source_x = x * framebuffer.width / self.width;
+                let color = framebuffer.buffer[source_y * framebuffer.width + source_x];
+                frame.extend_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+            }
+        }
+        frame
+    }
+}
+
+// Con `length_prefix` cada cuadro va precedido por su largo en bytes (u32 little-endian);
+// en stdout se escribe rawvideo sin marcos, que es lo que espera ffmpeg
+fn write_frames(output: &mut dyn Write, receiver: Receiver<Vec<u8>>, length_prefix: bool) -> io::Result<()> {
+    for frame in receiver {
+        if length_prefix {
+            output.write_all(&(frame.len() as u32).to_le_bytes())?;
+        }
+        output.write_all(&frame)?;
+    }
+    output.flush()
+}
\ No newline at end of file
@@ -0,0 +1,92 @@
+use std::ops::Range;
+use crate::cli::arg_value;
+use crate::color::Color;
+use crate::dither::{diffuse, OutputDither};
+use crate::environment::SPACE_COLOR;
+use crate::framebuffer::Framebuffer;
+
+// Factores por eje que se aceptan: 2 son 4 muestras por píxel y 4 son 16
+const FACTORS: [usize; 3] = [1, 2, 4];
+
+// `--supersample 2|4`; sin la opción queda en 1 (apagado) hasta presionar F3 en la ventana
+pub fn factor_from_args(args: &[String]) -> Result<usize, String> {
+    match arg_value(args, "--supersample") {
+        None => Ok(1),
+        Some(value) => value
+            .parse()
+            .ok()
+            .filter(|factor| FACTORS.contains(factor))
+            .ok_or_else(|| format!("Supermuestreo desconocido '{}' (1, 2 o 4)", value)),
+    }
+}
+
+// Antialiasing por supermuestreo: la escena se rasteriza en un framebuffer interno `factor` veces
+// más grande en cada eje y se promedia en bloques de `factor` x `factor` antes del post-proceso.
+// El rasterizador muestrea en el centro de cada píxel, así que el centro de un bloque cae justo en
+// el centro del píxel de salida y la imagen no se corre medio píxel al activarlo.
+pub struct Supersampling {
+    pub factor: usize,
+    framebuffer: Framebuffer,
+}
+
+impl Supersampling {
+    pub fn new(factor: usize) -> Self {
+        Supersampling { factor, framebuffer: Framebuffer::new(0, 0) }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.factor > 1
+    }
+
+    // 1, 2, 4 y de vuelta a 1
+    pub fn cycle(&mut self) {
+        let next = FACTORS.iter().position(|&factor| factor == self.factor).map_or(0, |index| (index + 1) % FACTORS.len());
+        self.factor = FACTORS[next];
+    }
+
+    // Framebuffer interno limpio para una salida de `width` x `height`; se vuelve a crear solo si
+    // cambió el tamaño o el factor
+    pub fn begin(&mut self, width: usize, height: usize) -> &mut Framebuffer {
+        let (width, height) = (width * self.factor, height * self.factor);
+        if self.framebuffer.width != width || self.framebuffer.height != height {
+            self.framebuffer = Framebuffer::new(width, height);
+            self.framebuffer.set_background_color(SPACE_COLOR.to_hex());
+        }
+        self.framebuffer.clear();
+        &mut self.framebuffer
+    }
+
+    // Filtro de caja sobre `target`. El color y la emisión se promedian en luz lineal; la profundidad
+    // es la más cercana del bloque, para que la niebla y el desenfoque traten el borde como el cuerpo.
+    // El color promediado se vuelve a redondear con el tramado de la salida, o con difusión de error
+    // en las filas `diffusion` si el cuadro se guarda (ver `dither::diffuse`).
+    pub fn resolve(&self, target: &mut Framebuffer, dither: OutputDither, diffusion: Option<Range<usize>>) {
+        let (factor, source) = (self.factor, &self.framebuffer);
+        let weight = 1.0 / (factor * factor) as f32;
+        let mut averaged = Vec::with_capacity(if diffusion.is_some() { target.buffer.len() } else { 0 });
+        for y in 0..target.height {
+            for x in 0..target.width {
+                let (mut color, mut emission, mut depth) = (Color::black(), Color::black(), f32::INFINITY);
+                for sy in y * factor..(y + 1) * factor {
+                    let row = sy * source.width;
+                    for index in row + x * factor..row + (x + 1) * factor {
+                        color = color + Color::from_hex(source.buffer[index]);
+                        emission = emission + Color::from_hex(source.emission[index]);
+                        depth = depth.min(source.zbuffer[index]);
+                    }
+                }
+                let index = y * target.width + x;
+                if diffusion.is_some() {
+                    averaged.push(color * weight);
+                } else {
+                    target.buffer[index] = (color * weight).to_hex_dithered(dither.offset(x, y));
+                }
+                target.emission[index] = (emission * weight).to_hex();
+                target.zbuffer[index] = depth;
+            }
+        }
+        if let Some(rows) = diffusion {
+            target.buffer = diffuse(&averaged, target.width, rows);
+        }
+    }
+}
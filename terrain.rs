@@ -0,0 +1,74 @@
+use crate::noise::{sphere_noise, NoiseSource};
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::plates::PlateField;
+use crate::seasons::Seasons;
+use crate::shader_params::EarthParams;
+use crate::shaders::smoothstep;
+use crate::spherical::to_lat_long;
+
+// Escala del ruido de la superficie terrestre
+pub const EARTH_ZOOM: f32 = 80.0;
+// Desplazamiento por segundo del ruido de los continentes: el reloj del terreno de `earth_clouds`
+// y del relieve de vértices
+pub const EARTH_DRIFT: f32 = 6.0;
+// Por encima de esta elevación hay tierra firme
+pub const SEA_LEVEL: f32 = 0.4;
+// Con placas, el ruido solo da el detalle de las costas y el relieve encima de la corteza
+const PLATE_DETAIL: f32 = 0.7;
+
+// Elevación de la Tierra para una posición en espacio de objeto: en [-1, 1] sin placas; con
+// ellas las cordilleras pueden pasar de 1. La usan `earth_clouds`, el relieve de vértices y los
+// exportadores de heightmaps y mapas de control, así todos ven la misma superficie.
+pub fn earth_elevation(noise: &dyn NoiseSource, position: &Vec3, time: f32, plates: Option<&PlateField>) -> f32 {
+    let detail = sphere_noise(noise, position, EARTH_ZOOM, Vec3::new(time, 0.0, 0.0));
+    match plates {
+        Some(plates) => detail * PLATE_DETAIL + plates.elevation(noise, position),
+        None => detail,
+    }
+}
+
+// Color de los biomas de la Tierra sin nubes ni iluminación.
+// Es lo que el exportador de mapas de control hornea, para poder editarlo y volver a cargarlo.
+// Con estaciones, `year_time` marca el punto del año de la línea de nieve y de la vegetación.
+// Los colores y los umbrales salen de `params` (ver `EarthParams`).
+pub fn earth_albedo(params: &EarthParams, noise: &dyn NoiseSource, position: &Vec3, time: f32, plates: Option<&PlateField>, seasons: Option<&Seasons>, year_time: f32) -> Color {
+    earth_surface(params, noise, position, time, plates, seasons, year_time).0
+}
+
+// Lo mismo que `earth_albedo` y, si el fragmento es mar, cuánto queda por debajo de la costa
+// (0 en la orilla): `earth_clouds` lo usa para el oleaje y el brillo del agua.
+pub fn earth_surface(params: &EarthParams, noise: &dyn NoiseSource, position: &Vec3, time: f32, plates: Option<&PlateField>, seasons: Option<&Seasons>, year_time: f32) -> (Color, Option<f32>) {
+    let land_threshold = SEA_LEVEL;
+
+    let surface_noise = earth_elevation(noise, position, time, plates);
+    let (latitude, _) = to_lat_long(position);
+    let season = seasons.map(|seasons| (seasons, seasons.phase(year_time, latitude)));
+    let snow_latitude = match season {
+        Some((seasons, phase)) => seasons.snow_latitude(params.snow_latitude, phase),
+        None => params.snow_latitude,
+    };
+
+    // Solo las cordilleras de las placas pasan de `rock_threshold`: roca y, en las cumbres, nieve
+    let land = if latitude.abs() > snow_latitude || (plates.is_some() && surface_noise > params.peak_threshold) {
+        params.snow_color
+    } else if plates.is_some() && surface_noise > params.rock_threshold {
+        params.rock_color
+    } else if surface_noise > land_threshold {
+        match season {
+            Some((seasons, phase)) => {
+                let seasonal = if surface_noise > params.forest_threshold { seasons.forest(phase) } else { seasons.grass(phase) };
+                params.land_color.lerp(&seasonal, seasons.weight(latitude))
+            }
+            None => params.land_color,
+        }
+    } else if surface_noise > params.desert_threshold {
+        params.desert_color
+    } else {
+        // Cerca de la costa el fondo se ve: turquesa claro que se oscurece con la profundidad
+        let depth = params.desert_threshold - surface_noise;
+        let deep = smoothstep(0.0, params.shallow_depth.max(1e-4), depth);
+        return (params.shallow_color.lerp(&params.ocean_color, deep), Some(depth));
+    };
+    (land, None)
+}
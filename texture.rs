@@ -0,0 +1,100 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts::PI;
+use crate::cli::arg_value;
+use crate::color::Color;
+use crate::image_io::load_png_rgb;
+use crate::spherical::to_lat_long;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+// De dónde salen las coordenadas de la imagen
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Projection {
+    // Las UV de la malla
+    Uv,
+    // Latitud y longitud de la posición, como una imagen equirectangular; la esfera de
+    // `assets/models` trae UV de 0 a 1 en cada cara, no un mapa del planeta entero
+    Spherical,
+}
+
+// Imagen para el shader `textured`. Las coordenadas se repiten fuera de [0, 1]; v = 0 es la fila
+// de arriba (el cargador de OBJ ya invierte la v).
+pub struct Texture {
+    width: usize,
+    height: usize,
+    // 0xRRGGBB, filas de arriba hacia abajo
+    pixels: Vec<u32>,
+    filter: Filter,
+    projection: Projection,
+}
+
+impl Texture {
+    // `--texture tierra.png`, con `--texture-filter nearest` (bilineal por defecto) y
+    // `--texture-projection spherical` (las UV de la malla por defecto) opcionales
+    pub fn from_args(args: &[String]) -> Option<Result<Self, String>> {
+        let path = arg_value(args, "--texture")?;
+        let filter = match arg_value(args, "--texture-filter").as_deref() {
+            None | Some("bilinear") => Filter::Bilinear,
+            Some("nearest") => Filter::Nearest,
+            Some(other) => return Some(Err(format!("Filtro de textura desconocido '{}' (nearest o bilinear)", other))),
+        };
+        let projection = match arg_value(args, "--texture-projection").as_deref() {
+            None | Some("uv") => Projection::Uv,
+            Some("spherical") => Projection::Spherical,
+            Some(other) => return Some(Err(format!("Proyección de textura desconocida '{}' (uv o spherical)", other))),
+        };
+        Some(Texture::load(&path, filter, projection))
+    }
+
+    // Solo PNG: es el único formato que el proyecto sabe decodificar
+    pub fn load(path: &str, filter: Filter, projection: Projection) -> Result<Self, String> {
+        if !path.to_ascii_lowercase().ends_with(".png") {
+            return Err(format!("La textura {} no es PNG; conviértela a PNG para usarla", path));
+        }
+        let (width, height, pixels) = load_png_rgb(path).map_err(|error| format!("No se pudo cargar la textura {}: {}", path, error))?;
+        if width == 0 || height == 0 {
+            return Err(format!("La textura {} está vacía", path));
+        }
+        Ok(Texture { width, height, pixels, filter, projection })
+    }
+
+    // Coordenadas de la imagen para un fragmento con esas UV y esa posición en espacio de objeto.
+    // La longitud empieza en -180 grados, igual que en los mapas que se exportan y en `--control-map`.
+    pub fn coordinates(&self, tex_coords: Vec2, position: &Vec3) -> Vec2 {
+        match self.projection {
+            Projection::Uv => tex_coords,
+            Projection::Spherical => {
+                let (latitude, longitude) = to_lat_long(position);
+                Vec2::new((longitude + PI) / (2.0 * PI), (PI / 2.0 - latitude) / PI)
+            }
+        }
+    }
+
+    pub fn sample(&self, uv: Vec2) -> Color {
+        // Posición en texeles, con el centro del texel en .5
+        let x = uv.x.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = uv.y.rem_euclid(1.0) * self.height as f32 - 0.5;
+        match self.filter {
+            Filter::Nearest => self.texel(x.round() as i32, y.round() as i32),
+            Filter::Bilinear => {
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i32, y0 as i32);
+                let top = self.texel(x0, y0).lerp(&self.texel(x0 + 1, y0), fx);
+                let bottom = self.texel(x0, y0 + 1).lerp(&self.texel(x0 + 1, y0 + 1), fx);
+                top.lerp(&bottom, fy)
+            }
+        }
+    }
+
+    // Los vecinos también se repiten, así el filtro cruza la costura sin una línea
+    fn texel(&self, x: i32, y: i32) -> Color {
+        let x = x.rem_euclid(self.width as i32) as usize;
+        let y = y.rem_euclid(self.height as i32) as usize;
+        Color::from_hex(self.pixels[y * self.width + x])
+    }
+}
@@ -0,0 +1,30 @@
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::shader_registry::FragmentShader;
+use crate::Uniforms;
+
+// Sombreado de caricatura: la difusa interpolada de los vértices (`fragment.intensity`) baja al
+// escalón de `bands` niveles más cercano por debajo, así el terminador queda en franjas. Vive fuera
+// de `shaders.rs` y entra por `ShaderRegistry::register`, como cualquier shader de otro módulo.
+pub struct ToonShader {
+    pub color: Color,
+    pub bands: f32,
+}
+
+impl Default for ToonShader {
+    fn default() -> Self {
+        ToonShader { color: Color::new(230, 140, 60), bands: 4.0 }
+    }
+}
+
+impl FragmentShader for ToonShader {
+    fn name(&self) -> &'static str {
+        "toon"
+    }
+
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+        let bands = self.bands.max(1.0);
+        let level = (fragment.intensity * bands).floor() / bands;
+        (self.color * level.max(uniforms.light.ambient)).blend_multiply(&uniforms.light.color)
+    }
+}
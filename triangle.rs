@@ -1,17 +1,30 @@
-use nalgebra_glm::{Vec3, dot};
+use nalgebra_glm::Vec3;
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
 use crate::color::Color;
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
+// Los fragmentos se limitan a la pantalla de `width` x `height`
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, width: usize, height: usize) -> Vec<Fragment> {
   let mut fragments = Vec::new();
   let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
-  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+  // Sin área (o con área no finita) no hay nada que pintar y los baricéntricos dividirían por cero
+  let triangle_area = edge_function(&a, &b, &c);
+  if !triangle_area.is_finite() || triangle_area.abs() < f32::EPSILON || width == 0 || height == 0 {
+    return fragments;
+  }
 
-  let light_dir = Vec3::new(0.0, 0.0, 1.0);
+  // La caja se recorta a la pantalla: un vértice muy lejano no puede disparar el recorrido
+  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+  let (min_x, min_y) = (min_x.max(0), min_y.max(0));
+  let (max_x, max_y) = (max_x.min(width as i32 - 1), max_y.min(height as i32 - 1));
 
-  let triangle_area = edge_function(&a, &b, &c);
+  // Tamaño del píxel en espacio de objeto: la raíz de cuánta área de la malla cubre cada píxel del
+  // triángulo, a la profundidad media de sus vértices. Cada fragmento lo escala por su propia w, así
+  // un triángulo largo que se aleja no usa el mismo tamaño en las dos puntas.
+  let object_area = (v2.position - v1.position).cross(&(v3.position - v1.position)).magnitude();
+  let pixel_size = (object_area / triangle_area.abs()).sqrt();
+  let mean_w = (1.0 / v1.inverse_w + 1.0 / v2.inverse_w + 1.0 / v3.inverse_w) / 3.0;
 
   for y in min_y..=max_y {
     for x in min_x..=max_x {
@@ -23,29 +36,36 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
          w2 >= 0.0 && w2 <= 1.0 &&
          w3 >= 0.0 && w3 <= 1.0 {
 
-        let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
-        let normal = normal.normalize();
+        // Corrección de perspectiva: los atributos se interpolan divididos por w y se vuelven a
+        // multiplicar por el w interpolado. La profundidad (z/w) ya es lineal en pantalla y se deja igual.
+        let (p1, p2, p3) = (w1 * v1.inverse_w, w2 * v2.inverse_w, w3 * v3.inverse_w);
+        let inverse_w = p1 + p2 + p3;
+        if inverse_w == 0.0 || !inverse_w.is_finite() {
+          continue;
+        }
+        let (p1, p2, p3) = (p1 / inverse_w, p2 / inverse_w, p3 / inverse_w);
 
-        let intensity = dot(&normal, &light_dir).max(0.0);
+        let normal = v1.transformed_normal * p1 + v2.transformed_normal * p2 + v3.transformed_normal * p3;
+        let normal = normal.normalize();
 
-        let base_color = Color::new(100, 100, 100);
-        let lit_color = base_color * intensity;
+        let intensity = v1.intensity * p1 + v2.intensity * p2 + v3.intensity * p3;
 
         let depth = a.z * w1 + b.z * w2 + c.z * w3;
 
-        let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
-
-        fragments.push(
-            Fragment::new(
-                x as f32,
-                y as f32,
-                lit_color,
-                depth,
-                normal,
-                intensity,
-                vertex_position,
-            )
+        let vertex_position = v1.position * p1 + v2.position * p2 + v3.position * p3;
+
+        let mut fragment = Fragment::new(
+          x as f32,
+          y as f32,
+          Color::new(100, 100, 100),
+          depth,
+          normal,
+          intensity,
+          vertex_position,
         );
+        fragment.tex_coords = v1.tex_coords * p1 + v2.tex_coords * p2 + v3.tex_coords * p3;
+        fragment.footprint = pixel_size / (inverse_w * mean_w);
+        fragments.push(fragment);
       }
     }
   }
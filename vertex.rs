@@ -1,4 +1,4 @@
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
 #[derive(Clone, Debug)]
@@ -9,6 +9,13 @@ pub struct Vertex {
   pub color: Color,
   pub transformed_position: Vec3,
   pub transformed_normal: Vec3,
+  // Ambiente más difusa que calcula `vertex_shader`; los rasterizadores la interpolan a
+  // `Fragment::intensity`
+  pub intensity: f32,
+  // Salida de `vertex_shader`, antes del recorte y de la división por w
+  pub clip_position: Vec4,
+  // 1/w del espacio de recorte, para interpolar con corrección de perspectiva (1 sin proyección)
+  pub inverse_w: f32,
 }
 
 impl Vertex {
@@ -20,6 +27,9 @@ impl Vertex {
       color: Color::black(),
       transformed_position: position,
       transformed_normal: normal,
+      intensity: 1.0,
+      clip_position: Vec4::new(position.x, position.y, position.z, 1.0),
+      inverse_w: 1.0,
     }
   }
 
@@ -31,6 +41,9 @@ impl Vertex {
       color,
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 0.0, 0.0),
+      intensity: 1.0,
+      clip_position: Vec4::new(position.x, position.y, position.z, 1.0),
+      inverse_w: 1.0,
     }
   }
 
@@ -38,6 +51,23 @@ impl Vertex {
     self.transformed_position = position;
     self.transformed_normal = normal;
   }
+
+  // Vértice a la fracción `t` del camino hacia `other`, para los que crea el recorte. Todo lo que
+  // llega al rasterizador es lineal en espacio de recorte; la posición de pantalla no se interpola
+  // porque `clipping::project` la vuelve a calcular desde `clip_position`.
+  pub fn lerp(&self, other: &Vertex, t: f32) -> Vertex {
+    Vertex {
+      position: self.position.lerp(&other.position, t),
+      normal: self.normal.lerp(&other.normal, t),
+      tex_coords: self.tex_coords.lerp(&other.tex_coords, t),
+      color: self.color.lerp(&other.color, t),
+      transformed_position: Vec3::zeros(),
+      transformed_normal: self.transformed_normal.lerp(&other.transformed_normal, t),
+      intensity: self.intensity + (other.intensity - self.intensity) * t,
+      clip_position: self.clip_position.lerp(&other.clip_position, t),
+      inverse_w: 1.0,
+    }
+  }
 }
 
 impl Default for Vertex {
@@ -49,6 +79,9 @@ impl Default for Vertex {
       color: Color::black(),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+      intensity: 1.0,
+      clip_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      inverse_w: 1.0,
     }
   }
 }
@@ -0,0 +1,59 @@
+use nalgebra_glm::Vec3;
+
+// Hash entero de una celda a tres valores en [0, 1)
+fn hash_cell(x: i32, y: i32, z: i32) -> Vec3 {
+    let mut h = (x as u32).wrapping_mul(0x8da6b343)
+        ^ (y as u32).wrapping_mul(0xd8163841)
+        ^ (z as u32).wrapping_mul(0xcb1ab31f);
+    let mut next = || {
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x2c1b3c6d);
+        h ^= h >> 12;
+        h = h.wrapping_mul(0x297a2d39);
+        h ^= h >> 15;
+        (h & 0x00FF_FFFF) as f32 / 16_777_216.0
+    };
+    Vec3::new(next(), next(), next())
+}
+
+// Ruido celular (Worley) en 3D: devuelve las distancias al punto característico
+// más cercano (F1) y al segundo más cercano (F2). Con `drift` distinto de cero
+// los puntos característicos oscilan dentro de su celda, así las celdas se mueven lentamente.
+pub fn worley(point: &Vec3, drift: f32) -> (f32, f32) {
+    let (f1, f2, _) = worley_cell(point, drift);
+    (f1, f2)
+}
+
+// Igual que `worley`, pero también devuelve el hash de la celda más cercana (tres valores en [0, 1)),
+// útil para dar a cada celda un color o un parámetro propio
+pub fn worley_cell(point: &Vec3, drift: f32) -> (f32, f32, Vec3) {
+    let cell_x = point.x.floor() as i32;
+    let cell_y = point.y.floor() as i32;
+    let cell_z = point.z.floor() as i32;
+
+    let mut f1 = f32::MAX;
+    let mut f2 = f32::MAX;
+    let mut nearest = Vec3::zeros();
+
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (x, y, z) = (cell_x + dx, cell_y + dy, cell_z + dz);
+                let jitter = hash_cell(x, y, z);
+                let offset = jitter.map(|j| 0.5 + 0.45 * (drift + j * std::f32::consts::TAU).sin());
+                let feature = Vec3::new(x as f32, y as f32, z as f32) + offset;
+
+                let distance = (feature - point).magnitude();
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                    nearest = jitter;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+    }
+
+    (f1, f2, nearest)
+}
\ No newline at end of file